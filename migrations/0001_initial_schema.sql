@@ -0,0 +1,33 @@
+-- Rooms, users and their bookings.
+-- Fails on a database already holding these tables: a schema set up by hand can't be
+-- adopted, it is dumped and loaded into a migrated database instead.
+
+CREATE TABLE rooms (
+    id          SERIAL PRIMARY KEY,
+    room_name   VARCHAR(16) NOT NULL UNIQUE,
+    capacity    INTEGER NOT NULL CHECK (capacity BETWEEN 1 AND 1000),
+    floor       INTEGER,
+    building    TEXT,
+    equipment   TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE TABLE users (
+    user_id     UUID PRIMARY KEY,
+    user_name   VARCHAR(34) NOT NULL UNIQUE
+);
+
+CREATE TABLE books (
+    id          SERIAL PRIMARY KEY,
+    room_name   VARCHAR(16) NOT NULL,
+    user_name   VARCHAR(34) NOT NULL,
+    date        DATE NOT NULL,
+    start_time  TIME NOT NULL,
+    end_time    TIME NOT NULL,
+    attendees   INTEGER NOT NULL DEFAULT 1 CHECK (attendees >= 1),
+    series_id   UUID,
+    CHECK (start_time < end_time)
+);
+
+CREATE INDEX books_room_date_idx ON books (room_name, date);
+CREATE INDEX books_user_name_idx ON books (user_name);
+CREATE INDEX books_series_id_idx ON books (series_id) WHERE series_id IS NOT NULL;
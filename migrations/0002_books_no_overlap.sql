@@ -0,0 +1,12 @@
+-- Double-booking prevention enforced by the database itself, whatever the state of
+-- each instance's cache: no two bookings of a room may overlap in time.
+-- Ranges are half-open, so back-to-back slots (09:00-10:00, 10:00-11:00) are allowed.
+
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+ALTER TABLE books
+    ADD CONSTRAINT books_no_overlap
+    EXCLUDE USING gist (
+        room_name WITH =,
+        tsrange(date + start_time, date + end_time) WITH &&
+    );
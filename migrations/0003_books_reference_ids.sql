@@ -0,0 +1,52 @@
+-- Bookings reference their room and user by id instead of by name, so renaming a room
+-- or a user no longer detaches its bookings. Names are joined in when reading.
+-- Rooms and users that still have bookings can't be deleted (ON DELETE RESTRICT):
+-- cancel or move their bookings first.
+
+ALTER TABLE books
+    ADD COLUMN room_id INTEGER,
+    ADD COLUMN user_id UUID;
+
+UPDATE books b SET room_id = r.id FROM rooms r WHERE r.room_name = b.room_name;
+UPDATE books b SET user_id = u.user_id FROM users u WHERE u.user_name = b.user_name;
+
+-- Bookings already orphaned by a rename or a delete can't be attached to anything.
+-- They are moved, untouched and with their room and user names, to `books_orphaned`
+-- for an operator to re-attach or drop: nothing is deleted.
+CREATE TABLE books_orphaned AS
+    SELECT * FROM books WHERE room_id IS NULL OR user_id IS NULL;
+DELETE FROM books b USING books_orphaned o WHERE o.id = b.id;
+
+DO $$
+DECLARE
+    orphaned BIGINT := (SELECT count(*) FROM books_orphaned);
+BEGIN
+    IF orphaned > 0 THEN
+        RAISE WARNING '% booking(s) matched no room or user by name: moved to books_orphaned', orphaned;
+    END IF;
+END $$;
+
+ALTER TABLE books
+    ALTER COLUMN room_id SET NOT NULL,
+    ALTER COLUMN user_id SET NOT NULL,
+    ADD CONSTRAINT books_room_id_fkey
+        FOREIGN KEY (room_id) REFERENCES rooms (id) ON DELETE RESTRICT,
+    ADD CONSTRAINT books_user_id_fkey
+        FOREIGN KEY (user_id) REFERENCES users (user_id) ON DELETE RESTRICT;
+
+ALTER TABLE books DROP CONSTRAINT books_no_overlap;
+ALTER TABLE books
+    ADD CONSTRAINT books_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tsrange(date + start_time, date + end_time) WITH &&
+    );
+
+DROP INDEX IF EXISTS books_room_date_idx;
+DROP INDEX IF EXISTS books_user_name_idx;
+CREATE INDEX books_room_id_date_idx ON books (room_id, date);
+CREATE INDEX books_user_id_idx ON books (user_id);
+
+ALTER TABLE books
+    DROP COLUMN room_name,
+    DROP COLUMN user_name;
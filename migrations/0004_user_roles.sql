@@ -0,0 +1,7 @@
+-- Roles: admins manage rooms and users, facility managers handle everyone's bookings,
+-- members only their own. Existing users start as members; promote an admin with
+--   UPDATE users SET role = 'admin' WHERE user_name = '...';
+
+ALTER TABLE users
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'member'
+    CHECK (role IN ('admin', 'facility_manager', 'member'));
@@ -0,0 +1,19 @@
+-- Bookings are cancelled rather than deleted, so the record that they existed is kept.
+-- Cancelled bookings free their slot: the overlap constraint only covers the others.
+
+ALTER TABLE books
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'confirmed'
+        CHECK (status IN ('confirmed', 'cancelled', 'completed', 'no_show')),
+    ADD COLUMN cancelled_at TIMESTAMPTZ,
+    ADD COLUMN cancelled_by UUID REFERENCES users (user_id) ON DELETE SET NULL,
+    ADD COLUMN cancel_reason TEXT,
+    ADD CONSTRAINT books_cancelled_at_check
+        CHECK ((status = 'cancelled') = (cancelled_at IS NOT NULL));
+
+ALTER TABLE books DROP CONSTRAINT books_no_overlap;
+ALTER TABLE books
+    ADD CONSTRAINT books_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tsrange(date + start_time, date + end_time) WITH &&
+    ) WHERE (status <> 'cancelled');
@@ -0,0 +1,18 @@
+-- Users queue for a room's slot already booked. When a cancellation frees it, the
+-- longest waiting entry that fits is promoted to a confirmed booking.
+
+CREATE TABLE waitlist (
+    id          SERIAL PRIMARY KEY,
+    room_id     INTEGER NOT NULL REFERENCES rooms (id) ON DELETE CASCADE,
+    user_id     UUID NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    date        DATE NOT NULL,
+    start_time  TIME NOT NULL,
+    end_time    TIME NOT NULL,
+    attendees   INTEGER NOT NULL DEFAULT 1 CHECK (attendees >= 1),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (start_time < end_time),
+    CONSTRAINT waitlist_once_per_slot UNIQUE (room_id, user_id, date, start_time, end_time)
+);
+
+CREATE INDEX waitlist_room_id_date_idx ON waitlist (room_id, date, created_at);
+CREATE INDEX waitlist_user_id_idx ON waitlist (user_id);
@@ -0,0 +1,27 @@
+-- Some rooms need a facility manager to approve their bookings. Requests for them start
+-- `pending` and hold their slot tentatively until approved (`confirmed`), `rejected`, or
+-- `expired` for lack of a decision. Rejected and expired requests reuse the cancellation
+-- columns: when, by whom (no one when expired) and why. Like cancelled ones, they free
+-- their slot.
+
+ALTER TABLE rooms
+    ADD COLUMN requires_approval BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE books
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    DROP CONSTRAINT books_status_check,
+    ADD CONSTRAINT books_status_check
+        CHECK (status IN ('pending', 'confirmed', 'cancelled', 'rejected', 'expired', 'completed', 'no_show')),
+    DROP CONSTRAINT books_cancelled_at_check,
+    ADD CONSTRAINT books_cancelled_at_check
+        CHECK ((status IN ('cancelled', 'rejected', 'expired')) = (cancelled_at IS NOT NULL));
+
+CREATE INDEX books_pending_idx ON books (created_at) WHERE status = 'pending';
+
+ALTER TABLE books DROP CONSTRAINT books_no_overlap;
+ALTER TABLE books
+    ADD CONSTRAINT books_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tsrange(date + start_time, date + end_time) WITH &&
+    ) WHERE (status NOT IN ('cancelled', 'rejected', 'expired'));
@@ -0,0 +1,10 @@
+-- Secret tokens of the subscribable calendar feeds, one per room or user. Revoking a
+-- feed deletes its token; the next one requested gets a new token.
+
+CREATE TABLE calendar_feeds (
+    token       TEXT PRIMARY KEY,
+    room_id     INTEGER UNIQUE REFERENCES rooms (id) ON DELETE CASCADE,
+    user_id     UUID UNIQUE REFERENCES users (user_id) ON DELETE CASCADE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK ((room_id IS NULL) <> (user_id IS NULL))
+);
@@ -0,0 +1,33 @@
+-- Rooms get a time zone: the dates and times of their bookings are wall-clock times
+-- there. Existing rooms are on UTC until changed. `starts_at` and `ends_at` are the
+-- booked instants, kept in step with the room's zone.
+
+ALTER TABLE rooms
+    ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'UTC';
+
+ALTER TABLE books
+    ADD COLUMN starts_at TIMESTAMPTZ,
+    ADD COLUMN ends_at TIMESTAMPTZ;
+
+UPDATE books b SET
+    starts_at = (b.date + b.start_time) AT TIME ZONE r.time_zone,
+    ends_at = (b.date + b.end_time) AT TIME ZONE r.time_zone
+FROM rooms r
+WHERE r.id = b.room_id;
+
+ALTER TABLE books
+    ALTER COLUMN starts_at SET NOT NULL,
+    ALTER COLUMN ends_at SET NOT NULL;
+
+-- Overlaps are told on the stored instants from now on. Deferrable, so changing a
+-- room's zone can move all its bookings before they are checked.
+ALTER TABLE books DROP CONSTRAINT books_no_overlap;
+ALTER TABLE books
+    ADD CONSTRAINT books_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tstzrange(starts_at, ends_at) WITH &&
+    ) WHERE (status NOT IN ('cancelled', 'rejected', 'expired'))
+    DEFERRABLE INITIALLY IMMEDIATE;
+
+CREATE INDEX books_room_id_starts_at_idx ON books (room_id, starts_at);
@@ -0,0 +1,95 @@
+-- The schema of `migrations/`, as it stands, for SQLite. Dates and times are ISO 8601
+-- text, so they compare in order; uuids are 16-byte blobs.
+
+CREATE TABLE rooms (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    room_name           TEXT NOT NULL UNIQUE,
+    capacity            INTEGER NOT NULL CHECK (capacity BETWEEN 1 AND 1000),
+    floor               INTEGER,
+    building            TEXT,
+    -- Tags separated by commas.
+    equipment           TEXT NOT NULL DEFAULT '',
+    requires_approval   BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE users (
+    user_id     BLOB PRIMARY KEY,
+    user_name   TEXT NOT NULL UNIQUE,
+    role        TEXT NOT NULL DEFAULT 'member'
+        CHECK (role IN ('admin', 'facility_manager', 'member'))
+);
+
+CREATE TABLE books (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    room_id         INTEGER NOT NULL REFERENCES rooms (id) ON DELETE RESTRICT,
+    user_id         BLOB NOT NULL REFERENCES users (user_id) ON DELETE RESTRICT,
+    date            TEXT NOT NULL,
+    start_time      TEXT NOT NULL,
+    end_time        TEXT NOT NULL,
+    attendees       INTEGER NOT NULL DEFAULT 1 CHECK (attendees >= 1),
+    series_id       BLOB,
+    status          TEXT NOT NULL DEFAULT 'confirmed'
+        CHECK (status IN ('pending', 'confirmed', 'cancelled', 'rejected', 'expired', 'completed', 'no_show')),
+    cancelled_at    TEXT,
+    cancelled_by    BLOB REFERENCES users (user_id) ON DELETE SET NULL,
+    cancel_reason   TEXT,
+    created_at      TEXT NOT NULL,
+    CHECK (start_time < end_time),
+    CHECK ((status IN ('cancelled', 'rejected', 'expired')) = (cancelled_at IS NOT NULL))
+);
+
+CREATE INDEX books_room_id_date_idx ON books (room_id, date);
+CREATE INDEX books_user_id_idx ON books (user_id);
+CREATE INDEX books_series_id_idx ON books (series_id) WHERE series_id IS NOT NULL;
+CREATE INDEX books_pending_idx ON books (created_at) WHERE status = 'pending';
+
+-- SQLite has no exclusion constraints: these triggers stand for `books_no_overlap`.
+CREATE TRIGGER books_no_overlap_on_insert
+BEFORE INSERT ON books
+WHEN NEW.status NOT IN ('cancelled', 'rejected', 'expired')
+BEGIN
+    SELECT RAISE(ABORT, 'books_no_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM books b
+        WHERE b.room_id = NEW.room_id AND b.date = NEW.date
+            AND b.start_time < NEW.end_time AND b.end_time > NEW.start_time
+            AND b.status NOT IN ('cancelled', 'rejected', 'expired')
+    );
+END;
+
+CREATE TRIGGER books_no_overlap_on_update
+BEFORE UPDATE ON books
+WHEN NEW.status NOT IN ('cancelled', 'rejected', 'expired')
+BEGIN
+    SELECT RAISE(ABORT, 'books_no_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM books b
+        WHERE b.id <> NEW.id AND b.room_id = NEW.room_id AND b.date = NEW.date
+            AND b.start_time < NEW.end_time AND b.end_time > NEW.start_time
+            AND b.status NOT IN ('cancelled', 'rejected', 'expired')
+    );
+END;
+
+CREATE TABLE waitlist (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    room_id     INTEGER NOT NULL REFERENCES rooms (id) ON DELETE CASCADE,
+    user_id     BLOB NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    date        TEXT NOT NULL,
+    start_time  TEXT NOT NULL,
+    end_time    TEXT NOT NULL,
+    attendees   INTEGER NOT NULL DEFAULT 1 CHECK (attendees >= 1),
+    created_at  TEXT NOT NULL,
+    CHECK (start_time < end_time),
+    CONSTRAINT waitlist_once_per_slot UNIQUE (room_id, user_id, date, start_time, end_time)
+);
+
+CREATE INDEX waitlist_room_id_date_idx ON waitlist (room_id, date, created_at);
+CREATE INDEX waitlist_user_id_idx ON waitlist (user_id);
+
+CREATE TABLE calendar_feeds (
+    token       TEXT PRIMARY KEY,
+    room_id     INTEGER UNIQUE REFERENCES rooms (id) ON DELETE CASCADE,
+    user_id     BLOB UNIQUE REFERENCES users (user_id) ON DELETE CASCADE,
+    created_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((room_id IS NULL) <> (user_id IS NULL))
+);
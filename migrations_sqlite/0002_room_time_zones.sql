@@ -0,0 +1,46 @@
+-- Rooms get a time zone, their bookings' dates and times being wall-clock times there.
+-- Existing rooms are on UTC. `starts_at` and `ends_at` are the booked instants, in UTC:
+-- overlaps are checked on them.
+
+ALTER TABLE rooms ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'UTC';
+
+ALTER TABLE books ADD COLUMN starts_at TEXT NOT NULL DEFAULT '';
+ALTER TABLE books ADD COLUMN ends_at TEXT NOT NULL DEFAULT '';
+
+UPDATE books SET
+    starts_at = date || 'T' || start_time || '+00:00',
+    ends_at = date || 'T' || end_time || '+00:00';
+
+-- Overlaps are told on the stored instants from now on.
+DROP TRIGGER books_no_overlap_on_insert;
+DROP TRIGGER books_no_overlap_on_update;
+
+CREATE TRIGGER books_no_overlap_on_insert
+BEFORE INSERT ON books
+WHEN NEW.status NOT IN ('cancelled', 'rejected', 'expired')
+BEGIN
+    SELECT RAISE(ABORT, 'books_no_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM books b
+        WHERE b.room_id = NEW.room_id
+            AND julianday(b.starts_at) < julianday(NEW.ends_at)
+            AND julianday(b.ends_at) > julianday(NEW.starts_at)
+            AND b.status NOT IN ('cancelled', 'rejected', 'expired')
+    );
+END;
+
+CREATE TRIGGER books_no_overlap_on_update
+BEFORE UPDATE ON books
+WHEN NEW.status NOT IN ('cancelled', 'rejected', 'expired')
+BEGIN
+    SELECT RAISE(ABORT, 'books_no_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM books b
+        WHERE b.id <> NEW.id AND b.room_id = NEW.room_id
+            AND julianday(b.starts_at) < julianday(NEW.ends_at)
+            AND julianday(b.ends_at) > julianday(NEW.starts_at)
+            AND b.status NOT IN ('cancelled', 'rejected', 'expired')
+    );
+END;
+
+CREATE INDEX books_room_id_starts_at_idx ON books (room_id, starts_at);
@@ -1,42 +1,135 @@
-use axum::{Router, middleware::from_fn};
-use sqlx::postgres::PgPoolOptions;
-use std::sync::Arc;
+use axum::{
+    Router,
+    middleware::{from_fn, from_fn_with_state},
+};
+use sqlx::{
+    PgPool, SqlitePool,
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+use std::{str::FromStr, sync::Arc};
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
-    app::{state::AppState, status_test::log_status},
+    app::{
+        deprecation::deprecated,
+        expiry::spawn_pending_expiry,
+        openapi::ApiDoc,
+        request_id::{X_REQUEST_ID, request_id},
+        state::AppState,
+        status_test::log_status,
+    },
+    config::Config,
+    domain::UserName,
     error::ErrService,
     features::{
-        book::{routes::book_routes, service::BookService},
-        room::{routes::room_routes, service::RoomService},
-        user::{routes::user_routes, service::UserService},
+        auth::{
+            middleware::{require_admin, require_auth},
+            routes::auth_routes,
+            service::AuthService,
+        },
+        book::{
+            routes::{book_admin_routes, book_legacy_routes, book_routes},
+            service::BookService,
+        },
+        calendar::{
+            routes::{calendar_feed_routes, calendar_import_routes, calendar_routes},
+            service::CalendarService,
+        },
+        room::{
+            routes::{room_admin_routes, room_legacy_admin_routes, room_routes},
+            service::RoomService,
+        },
+        user::{
+            routes::{signup_routes, user_admin_routes, user_legacy_admin_routes, user_routes},
+            service::UserService,
+        },
+        waitlist::routes::waitlist_routes,
+    },
+    infra::{
+        backend::Backend,
+        cache::try_init_caches,
+        clock::{Clock, SystemClock},
     },
-    infra::{cache::try_init_caches, db::DBClient},
 };
 
-pub async fn build_app(database_url: &str) -> Result<Router, ErrService> {
+/// Opens the connection pool the service, and `room-admin`, talk to the database through.
+pub async fn connect(database_url: &str) -> Result<PgPool, ErrService> {
     let pool = PgPoolOptions::new()
         .max_connections(30)
         .connect(database_url)
         .await?;
 
-    let db_client = DBClient::new(pool);
+    Ok(pool)
+}
 
-    let room_service = Arc::new(RoomService::new(db_client.clone()));
-    let user_service = Arc::new(UserService::new(db_client.clone()));
-    let book_service = Arc::new(BookService::new(db_client.clone()));
+/// Opens the SQLite database at `database_url` (`sqlite://rooms.db`), creating its file
+/// when missing.
+pub async fn connect_sqlite(database_url: &str) -> Result<SqlitePool, ErrService> {
+    let options = SqliteConnectOptions::from_str(database_url)?
+        .create_if_missing(true)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(8)
+        .connect_with(options)
+        .await?;
+
+    Ok(pool)
+}
+
+/// Issues a token for an existing user, e.g. one created before authentication
+/// was required (`--issue-token <user_name>`).
+pub async fn issue_token<B: Backend>(
+    config: &Config,
+    backend: &B,
+    user_name: &str,
+) -> Result<String, ErrService> {
+    let user = backend.get_one_user(&UserName::new(user_name)?).await?;
+
+    Ok(AuthService::new(&config.jwt_secret)
+        .issue_token(&user)?
+        .token)
+}
+
+/// Builds the service over `backend`, after bringing its schema up to date.
+pub async fn build_app<B: Backend>(config: &Config, backend: B) -> Result<Router, ErrService> {
+    build_app_with_clock(config, backend, Arc::new(SystemClock)).await
+}
+
+/// [`build_app`], telling the time with `clock` rather than the system's.
+pub async fn build_app_with_clock<B: Backend>(
+    config: &Config,
+    backend: B,
+    clock: Arc<dyn Clock>,
+) -> Result<Router, ErrService> {
+    backend.run_migrations().await?;
+
+    let room_service = Arc::new(RoomService::new(backend.clone()));
+    let user_service = Arc::new(UserService::new(backend.clone()));
+    let book_service = Arc::new(BookService::new(backend.clone()).with_clock(clock.clone()));
+    let auth_service = Arc::new(AuthService::new(&config.jwt_secret));
+    let calendar_service = Arc::new(CalendarService::new(backend));
 
     // room_service.populate_cache().await?;
     // user_service.populate_cache().await?;
     // book_service.populate_cache().await?;
 
     try_init_caches(&user_service, &room_service, &book_service).await?;
+    if let Some(admin_user) = &config.admin_user {
+        user_service.bootstrap_admin(admin_user).await?;
+    }
+    spawn_pending_expiry(book_service.clone(), config.pending_approval_ttl);
 
     let state = AppState {
         user_service: user_service.clone(),
         room_service: room_service.clone(),
         book_service: book_service.clone(),
+        auth_service,
+        calendar_service,
+        clock,
     };
 
     info!("{:?}", room_service.list_cache_rooms().await);
@@ -46,15 +139,46 @@ pub async fn build_app(database_url: &str) -> Result<Router, ErrService> {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([X_REQUEST_ID.clone()]);
 
-    let app = Router::new()
+    let mut admin = Router::new()
+        .merge(book_admin_routes())
+        .merge(room_admin_routes())
+        .merge(user_admin_routes());
+
+    let mut authenticated = Router::new()
         .merge(book_routes())
         .merge(room_routes())
         .merge(user_routes())
+        .merge(waitlist_routes())
+        .merge(calendar_feed_routes())
+        .merge(calendar_import_routes())
+        .merge(auth_routes());
+
+    if config.legacy_routes {
+        admin = admin.merge(
+            Router::new()
+                .merge(room_legacy_admin_routes())
+                .merge(user_legacy_admin_routes())
+                .route_layer(from_fn(deprecated)),
+        );
+        authenticated = authenticated.merge(book_legacy_routes().route_layer(from_fn(deprecated)));
+    }
+
+    let authenticated = authenticated
+        .merge(admin.route_layer(from_fn(require_admin)))
+        .route_layer(from_fn_with_state(state.clone(), require_auth::<B>));
+
+    let app = Router::new()
+        .merge(signup_routes())
+        .merge(calendar_routes())
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
+        .merge(authenticated)
         .with_state(state)
         .layer(cors)
-        .layer(from_fn(log_status));
+        .layer(from_fn(log_status))
+        .layer(from_fn(request_id));
 
     Ok(app)
 }
@@ -0,0 +1,16 @@
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+/// Flags responses of routes kept for older clients (RFC 9745 `Deprecation` header).
+pub async fn deprecated(req: Request<Body>, next: Next) -> Response<Body> {
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert("deprecation", HeaderValue::from_static("true"));
+
+    response
+}
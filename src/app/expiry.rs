@@ -0,0 +1,24 @@
+use chrono::Duration;
+use tracing::{info, warn};
+
+use crate::{app::state::SharedBookService, infra::backend::Backend};
+
+/// How often pending bookings are checked for expiry.
+const EXPIRY_PERIOD: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Expires, in the background, the pending bookings left without a decision for `ttl`.
+pub fn spawn_pending_expiry<B: Backend>(book_service: SharedBookService<B>, ttl: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(EXPIRY_PERIOD);
+        loop {
+            ticker.tick().await;
+            match book_service.expire_pending(ttl).await {
+                Ok(expired) if !expired.is_empty() => {
+                    info!("Expired {} pending booking(s)", expired.len())
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Unable to expire pending bookings: {:?}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,215 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{HeaderMap, Method, Request, StatusCode, header},
+};
+use chrono::Duration;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use crate::{
+    app::build::{build_app_with_clock, issue_token},
+    config::{Config, Storage},
+    domain::UserName,
+    features::user::repo::UserRepo,
+    infra::{
+        clock::ManualClock,
+        in_memory::{in_memo_helper::today, in_memo_repo::InMemoryRepo},
+    },
+};
+
+/// Dates counted from [`today`], the day the app's clock stands on, as the service
+/// fixture counts them.
+pub use crate::infra::in_memory::in_memo_helper::day;
+
+/// The user made admin on startup, `ADMIN_USER` of the app under test.
+const ADMIN_USER: &str = "sophie";
+
+/// The whole service, routes and middlewares, over an in-memory store: no database
+/// needed. Each request goes through a clone of the router with `oneshot`. Its clock
+/// stands at 08:00 UTC on [`today`] until moved.
+pub struct TestApp {
+    app: Router,
+    pub repo: InMemoryRepo,
+    pub clock: Arc<ManualClock>,
+    /// `sophie`, bootstrapped as admin the way `ADMIN_USER` does.
+    pub admin: Caller,
+}
+
+/// What the service answered; `body` is the JSON body, a string for other bodies and
+/// `null` when empty.
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+impl TestResponse {
+    /// The `code` of a problem body.
+    pub fn code(&self) -> &str {
+        self.body["code"].as_str().unwrap_or_default()
+    }
+}
+
+/// A signed up user, and the token to act as them.
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub id: String,
+    pub token: String,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        let config = Config {
+            storage: Storage::Memory,
+            jwt_secret: "0123456789abcdef0123456789abcdef-test".to_string(),
+            legacy_routes: true,
+            pending_approval_ttl: Duration::hours(48),
+            admin_user: Some(ADMIN_USER.to_string()),
+        };
+        let repo = InMemoryRepo::new();
+        let clock = Arc::new(ManualClock::at(today(), 8, 0));
+        let app = build_app_with_clock(&config, repo.clone(), clock.clone())
+            .await
+            .unwrap();
+
+        let admin = repo
+            .get_one_user(&UserName::new(ADMIN_USER).unwrap())
+            .await
+            .unwrap();
+        let admin = Caller {
+            id: admin.user_id.id.to_string(),
+            token: issue_token(&config, &repo, ADMIN_USER).await.unwrap(),
+        };
+
+        Self {
+            app,
+            repo,
+            clock,
+            admin,
+        }
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        caller: Option<&Caller>,
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(caller) = caller {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", caller.token));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+        };
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn get(&self, uri: &str, caller: &Caller) -> TestResponse {
+        self.request(Method::GET, uri, Some(caller), None).await
+    }
+
+    pub async fn post(&self, uri: &str, caller: &Caller, body: Value) -> TestResponse {
+        self.request(Method::POST, uri, Some(caller), Some(body))
+            .await
+    }
+
+    pub async fn patch(&self, uri: &str, caller: &Caller, body: Value) -> TestResponse {
+        self.request(Method::PATCH, uri, Some(caller), Some(body))
+            .await
+    }
+
+    pub async fn delete(&self, uri: &str, caller: &Caller) -> TestResponse {
+        self.request(Method::DELETE, uri, Some(caller), None).await
+    }
+
+    /// Signs `user_name` up, as a member.
+    pub async fn signup(&self, user_name: &str) -> Caller {
+        let response = self
+            .request(
+                Method::POST,
+                "/users",
+                None,
+                Some(json!({ "user_name": user_name })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+
+        Caller {
+            id: response.body["user_id"].as_str().unwrap().to_string(),
+            token: response.body["token"].as_str().unwrap().to_string(),
+        }
+    }
+
+    /// Gives `user` the `role`, as `admin`.
+    pub async fn grant(&self, admin: &Caller, user: &Caller, role: &str) {
+        let response = self
+            .patch(
+                &format!("/users/{}", user.id),
+                admin,
+                json!({ "role": role }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    }
+
+    /// Creates a room as `admin` and returns its id.
+    pub async fn create_room(&self, admin: &Caller, room_name: &str, capacity: i32) -> i64 {
+        let response = self
+            .post(
+                "/room",
+                admin,
+                json!({ "room_name": room_name, "capacity": capacity }),
+            )
+            .await;
+        assert!(response.status.is_success(), "{:?}", response.body);
+
+        response.body["id"].as_i64().unwrap()
+    }
+
+    /// Books `room_name` as `caller`; the response is left to check.
+    pub async fn book(
+        &self,
+        caller: &Caller,
+        room_name: &str,
+        date: &str,
+        start_time: &str,
+        end_time: &str,
+    ) -> TestResponse {
+        self.post(
+            "/book",
+            caller,
+            json!({
+                "room_name": room_name,
+                "date": date,
+                "start_time": start_time,
+                "end_time": end_time,
+            }),
+        )
+        .await
+    }
+}
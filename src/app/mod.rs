@@ -1,3 +1,9 @@
 pub mod build;
+pub mod deprecation;
+pub mod expiry;
+#[cfg(test)]
+pub mod harness;
+pub mod openapi;
+pub mod request_id;
 pub mod state;
 pub mod status_test;
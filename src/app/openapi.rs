@@ -0,0 +1,119 @@
+#![allow(deprecated)]
+
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+};
+
+use crate::{
+    error::{FieldErrorDto, ProblemDto},
+    features,
+};
+
+/// OpenAPI 3 document of the API, served at `/openapi.json` and browsable at `/docs`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Room Reservation API"),
+    paths(
+        features::book::handlers::create_booking,
+        features::book::handlers::list_book,
+        features::book::handlers::export_books,
+        features::book::handlers::import_books,
+        features::book::handlers::get_book,
+        features::book::handlers::put_book,
+        features::book::handlers::patch_book,
+        features::book::handlers::cancel_book,
+        features::book::handlers::update_book_status,
+        features::book::handlers::approve_book,
+        features::book::handlers::reject_book,
+        features::book::handlers::update_series,
+        features::book::handlers::delete_series,
+        features::book::handlers::delete_all_books,
+        features::book::handlers::update_book,
+        features::book::handlers::delete_book,
+        features::room::handlers::create_room,
+        features::room::handlers::list_room,
+        features::room::handlers::import_rooms,
+        features::room::handlers::list_available_rooms,
+        features::room::handlers::get_room,
+        features::room::handlers::put_room,
+        features::room::handlers::patch_room,
+        features::room::handlers::delete_room_by_id,
+        features::room::handlers::update_room_name,
+        features::room::handlers::delete_room,
+        features::user::handlers::create_user,
+        features::user::handlers::list_users,
+        features::user::handlers::import_users,
+        features::user::handlers::get_user,
+        features::user::handlers::put_user,
+        features::user::handlers::patch_user,
+        features::user::handlers::delete_user_by_id,
+        features::user::handlers::update_user,
+        features::user::handlers::update_user_role,
+        features::user::handlers::delete_user,
+        features::calendar::handlers::room_calendar,
+        features::calendar::handlers::user_calendar,
+        features::calendar::handlers::room_calendar_feed,
+        features::calendar::handlers::revoke_room_calendar_feed,
+        features::calendar::handlers::user_calendar_feed,
+        features::calendar::handlers::revoke_user_calendar_feed,
+        features::calendar::handlers::import_calendar,
+        features::waitlist::handlers::join_waitlist,
+        features::waitlist::handlers::list_waitlist,
+        features::waitlist::handlers::leave_waitlist,
+        features::auth::handlers::refresh_token,
+    ),
+    components(schemas(ProblemDto, FieldErrorDto)),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "book", description = "Bookings and recurring series"),
+        (name = "room", description = "Rooms and their availability"),
+        (name = "user", description = "Users and roles"),
+        (name = "waitlist", description = "Queues for booked slots"),
+        (name = "calendar", description = "iCalendar feeds of rooms and users"),
+        (name = "auth", description = "API tokens"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `Authorization: Bearer <token>` scheme every route but sign-up requires.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                Http::builder()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use utoipa::OpenApi;
+
+    use super::ApiDoc;
+
+    #[test]
+    fn spec_documents_resources_and_error_body() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        for path in ["/book/{id}", "/room/{id}", "/users/{id}", "/auth/token"] {
+            assert!(spec["paths"][path].is_object(), "{path} is undocumented");
+        }
+        assert_eq!(spec["paths"]["/book/update"]["post"]["deprecated"], true);
+        assert_eq!(
+            spec["paths"]["/users"]["post"]["security"],
+            serde_json::json!([{}])
+        );
+        assert!(spec["components"]["schemas"]["ProblemDto"].is_object());
+        assert!(spec["components"]["securitySchemes"]["bearer"].is_object());
+    }
+}
@@ -2,18 +2,26 @@ use std::sync::Arc;
 
 use crate::{
     features::{
-        book::service::BookService, room::service::RoomService, user::service::UserService,
+        auth::service::AuthService, book::service::BookService, calendar::service::CalendarService,
+        room::service::RoomService, user::service::UserService,
     },
-    infra::db::DBClient,
+    infra::{backend::Backend, clock::Clock},
 };
 
-pub type SharedUserService = Arc<UserService<DBClient>>;
-pub type SharedRoomService = Arc<RoomService<DBClient>>;
-pub type SharedBookService = Arc<BookService<DBClient>>;
+pub type SharedUserService<B> = Arc<UserService<B>>;
+pub type SharedRoomService<B> = Arc<RoomService<B>>;
+pub type SharedBookService<B> = Arc<BookService<B>>;
+pub type SharedAuthService = Arc<AuthService>;
+pub type SharedCalendarService<B> = Arc<CalendarService<B>>;
 
+/// What the handlers share, over the storage backend `B` picked on startup.
 #[derive(Clone)]
-pub struct AppState {
-    pub user_service: SharedUserService,
-    pub room_service: SharedRoomService,
-    pub book_service: SharedBookService,
+pub struct AppState<B: Backend> {
+    pub user_service: SharedUserService<B>,
+    pub room_service: SharedRoomService<B>,
+    pub book_service: SharedBookService<B>,
+    pub auth_service: SharedAuthService,
+    pub calendar_service: SharedCalendarService<B>,
+    /// The clock the book service runs on, for handlers that need the time.
+    pub clock: Arc<dyn Clock>,
 }
@@ -2,6 +2,8 @@ use axum::{body::Body, http::Request, middleware::Next, response::Response};
 use std::time::Instant;
 use tracing::{error, info};
 
+use crate::app::request_id::current_request_id;
+
 pub async fn log_status(req: Request<Body>, next: Next) -> Response<Body> {
     let now = Instant::now();
 
@@ -11,11 +13,12 @@ pub async fn log_status(req: Request<Body>, next: Next) -> Response<Body> {
     let response = next.run(req).await;
     let status = response.status().as_u16();
     let elapsed = now.elapsed().as_micros();
+    let request_id = current_request_id().unwrap_or_default();
 
     if status >= 500 {
-        error!(%method, %uri, status, elapsed, "Server error");
+        error!(%method, %uri, status, elapsed, %request_id, "Server error");
     } else {
-        info!(%method, %uri, status, elapsed, "Request completed");
+        info!(%method, %uri, status, elapsed, %request_id, "Request completed");
     }
 
     response
@@ -0,0 +1,363 @@
+//! `room-admin`: administers rooms, users and bookings straight in the database, through
+//! the service's repositories. A running service only sees the changes once restarted,
+//! its caches being loaded on startup.
+
+mod output;
+
+use std::process::ExitCode;
+
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand};
+use serde_json::json;
+
+use room_reservations::{
+    app::build::{connect, connect_sqlite},
+    domain::{
+        BookDate, BookKey, Cancellation, MAX_PAGE_SIZE, PageRequest, Role, Room, RoomName,
+        SortOrder, User, UserName,
+    },
+    error::{ErrBook, ErrRoom, ErrService, ErrUser, Problem},
+    features::{
+        book::dto::{BookDto, ExportBookQuery},
+        room::dto::{CreateRoomDto, RoomDto},
+        user::dto::UserDto,
+    },
+    infra::{
+        backend::Backend,
+        clock::{Clock, SystemClock},
+        db::DBClient,
+        sqlite::SqliteClient,
+    },
+};
+
+use output::{Format, print_done, print_list, print_one};
+
+/// Administers rooms, users and bookings straight in the database.
+#[derive(Parser)]
+#[command(name = "room-admin", version)]
+struct Cli {
+    /// Database of the service, PostgreSQL or SQLite (`sqlite://...`).
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: String,
+    #[arg(long, short, value_enum, default_value_t = Format::Table, global = true)]
+    output: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Applies pending migrations.
+    Migrate,
+    /// Lists, creates, renames or deletes rooms.
+    #[command(subcommand)]
+    Rooms(RoomCommand),
+    /// Lists, creates, renames or deletes users.
+    #[command(subcommand)]
+    Users(UserCommand),
+    /// Lists, cancels or purges bookings.
+    #[command(subcommand)]
+    Bookings(BookingCommand),
+}
+
+#[derive(Subcommand)]
+enum RoomCommand {
+    List,
+    Create {
+        name: String,
+        #[arg(long)]
+        capacity: i32,
+        #[arg(long)]
+        floor: Option<i32>,
+        #[arg(long)]
+        building: Option<String>,
+        /// Equipment tags, comma separated.
+        #[arg(long, value_delimiter = ',')]
+        equipment: Vec<String>,
+        /// Members' bookings wait for a facility manager's approval.
+        #[arg(long)]
+        requires_approval: bool,
+        /// IANA name of the room's time zone, `UTC` by default.
+        #[arg(long)]
+        time_zone: Option<String>,
+    },
+    Rename {
+        name: String,
+        new_name: String,
+    },
+    /// Deletes a room without bookings.
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    List,
+    Create {
+        name: String,
+        /// `member`, `facility_manager` or `admin`.
+        #[arg(long, default_value = "member")]
+        role: String,
+    },
+    Rename {
+        name: String,
+        new_name: String,
+    },
+    /// Deletes a user without bookings.
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum BookingCommand {
+    /// Lists bookings, soonest first; dates are `dd.mm.yy`.
+    List {
+        #[arg(long)]
+        room: Option<String>,
+        #[arg(long)]
+        user: Option<String>,
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        include_cancelled: bool,
+    },
+    /// Cancels a booking, on nobody's behalf. Nobody on its waitlist gets the slot.
+    Cancel {
+        id: i32,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Deletes the bookings dated before a day, whatever their status.
+    Purge {
+        /// `dd.mm.yy`, today (UTC) by default.
+        #[arg(long)]
+        before: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let problem = Problem::from(err);
+            eprintln!("error: {} ({})", problem.detail(), problem.code());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), ErrService> {
+    if cli.database_url.starts_with("sqlite:") {
+        let db = SqliteClient::new(connect_sqlite(&cli.database_url).await?);
+        execute(&db, &SystemClock, cli.output, cli.command).await
+    } else {
+        let db = DBClient::new(connect(&cli.database_url).await?);
+        execute(&db, &SystemClock, cli.output, cli.command).await
+    }
+}
+
+async fn execute<B: Backend>(
+    db: &B,
+    clock: &dyn Clock,
+    format: Format,
+    command: Command,
+) -> Result<(), ErrService> {
+    match command {
+        Command::Migrate => {
+            db.run_migrations().await?;
+            print_done(
+                format,
+                "Database schema up to date",
+                json!({ "migrated": true }),
+            )
+        }
+        Command::Rooms(command) => rooms(db, format, command).await,
+        Command::Users(command) => users(db, format, command).await,
+        Command::Bookings(command) => bookings(db, clock, format, command).await,
+    }
+}
+
+async fn rooms<B: Backend>(db: &B, format: Format, command: RoomCommand) -> Result<(), ErrService> {
+    match command {
+        RoomCommand::List => {
+            let mut rooms = db.get_all_rooms().await?;
+            rooms.sort_by(|a, b| a.room_name.cmp(&b.room_name));
+            let rooms: Vec<RoomDto> = rooms.into_iter().map(RoomDto::from).collect();
+            print_list(format, &rooms)
+        }
+        RoomCommand::Create {
+            name,
+            capacity,
+            floor,
+            building,
+            equipment,
+            requires_approval,
+            time_zone,
+        } => {
+            let room = Room::try_from(CreateRoomDto {
+                room_name: name,
+                capacity,
+                floor,
+                building,
+                equipment,
+                requires_approval,
+                time_zone,
+            })?;
+            if db.get_one_room(&room.room_name).await.is_ok() {
+                return Err(ErrService::Room(ErrRoom::AlreadyExist));
+            }
+
+            let room = db.insert_room(&room).await?;
+            print_one(format, &RoomDto::from(room))
+        }
+        RoomCommand::Rename { name, new_name } => {
+            let room = db.get_one_room(&RoomName::new(&name)?).await?;
+            let new_name = RoomName::new(&new_name)?;
+            if db.get_one_room(&new_name).await.is_ok() {
+                return Err(ErrService::Room(ErrRoom::AlreadyExist));
+            }
+
+            let room = db
+                .update_room(&Room {
+                    room_name: new_name,
+                    ..room
+                })
+                .await?;
+            print_one(format, &RoomDto::from(room))
+        }
+        RoomCommand::Delete { name } => {
+            let room = db.get_one_room(&RoomName::new(&name)?).await?;
+            db.delete_room_by_id(room.id).await?;
+
+            print_done(
+                format,
+                &format!("Room {} deleted", room.room_name.name),
+                json!({ "deleted": room.id }),
+            )
+        }
+    }
+}
+
+async fn users<B: Backend>(db: &B, format: Format, command: UserCommand) -> Result<(), ErrService> {
+    match command {
+        UserCommand::List => {
+            let mut users = db.get_all_users().await?;
+            users.sort_by(|a, b| a.user_name.cmp(&b.user_name));
+            let users: Vec<UserDto> = users.into_iter().map(UserDto::from).collect();
+            print_list(format, &users)
+        }
+        UserCommand::Create { name, role } => {
+            let user = User::new(&name)?.with_role(Role::new(&role)?);
+            if db.get_one_user(&user.user_name).await.is_ok() {
+                return Err(ErrService::User(ErrUser::AlreadyExist));
+            }
+
+            let user = db.insert_user(&user).await?;
+            print_one(format, &UserDto::from(user))
+        }
+        UserCommand::Rename { name, new_name } => {
+            let user = db.get_one_user(&UserName::new(&name)?).await?;
+            let new_name = UserName::new(&new_name)?;
+            if db.get_one_user(&new_name).await.is_ok() {
+                return Err(ErrService::User(ErrUser::AlreadyExist));
+            }
+
+            let user = db.update_user(user.user_id.id, new_name).await?;
+            print_one(format, &UserDto::from(user))
+        }
+        UserCommand::Delete { name } => {
+            let user = db.get_one_user(&UserName::new(&name)?).await?;
+            db.delete_user_by_id(user.user_id.id).await?;
+
+            print_done(
+                format,
+                &format!("User {} deleted", user.user_name.name),
+                json!({ "deleted": user.user_id.id }),
+            )
+        }
+    }
+}
+
+async fn bookings<B: Backend>(
+    db: &B,
+    clock: &dyn Clock,
+    format: Format,
+    command: BookingCommand,
+) -> Result<(), ErrService> {
+    match command {
+        BookingCommand::List {
+            room,
+            user,
+            from,
+            to,
+            status,
+            include_cancelled,
+        } => {
+            let filter = ExportBookQuery {
+                room,
+                user,
+                from,
+                to,
+                status,
+                include_cancelled: Some(include_cancelled),
+            }
+            .filter()?;
+
+            let mut page = PageRequest::<BookKey> {
+                limit: MAX_PAGE_SIZE,
+                after: None,
+                order: SortOrder::Asc,
+            };
+            let mut books = Vec::new();
+            loop {
+                let listed = db.list_books(&filter, &page).await?;
+                page.after = listed.items.last().map(BookKey::from);
+                books.extend(listed.items.into_iter().map(BookDto::from));
+                if !listed.has_more {
+                    break;
+                }
+            }
+
+            print_list(format, &books)
+        }
+        BookingCommand::Cancel { id, reason } => {
+            db.find_book(id)
+                .await?
+                .ok_or(ErrBook::BookNotFound)?
+                .check_live()?;
+
+            let cancellation = Cancellation {
+                at: clock.now(),
+                by: None,
+                reason,
+            };
+            let book = db.cancel_book(id, &cancellation).await?;
+            print_one(format, &BookDto::from(book))
+        }
+        BookingCommand::Purge { before } => {
+            let before = match before {
+                Some(date) => BookDate::new(&date)?,
+                None => BookDate {
+                    date: clock.today_in(Tz::UTC),
+                },
+            };
+
+            let purged = db.purge_books_before(before.date).await?;
+            print_done(
+                format,
+                &format!("{purged} bookings dated before {} purged", before.date),
+                json!({ "purged": purged, "before": before.date }),
+            )
+        }
+    }
+}
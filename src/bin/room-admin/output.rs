@@ -0,0 +1,177 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+use room_reservations::{
+    error::ErrService,
+    features::{book::dto::BookDto, room::dto::RoomDto, user::dto::UserDto},
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// Aligned columns, for people.
+    Table,
+    /// The API's JSON bodies, for scripts.
+    Json,
+}
+
+/// A listing printed one row per item in table mode.
+pub trait Tabular: Serialize {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+impl Tabular for RoomDto {
+    const HEADERS: &'static [&'static str] = &[
+        "ID",
+        "NAME",
+        "CAPACITY",
+        "FLOOR",
+        "BUILDING",
+        "EQUIPMENT",
+        "APPROVAL",
+        "TIME ZONE",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.room_name.clone(),
+            self.capacity.to_string(),
+            optional(self.floor),
+            optional(self.building.as_deref()),
+            optional(Some(self.equipment.join(",")).filter(|e| !e.is_empty())),
+            if self.requires_approval {
+                "required"
+            } else {
+                "-"
+            }
+            .to_string(),
+            self.time_zone.clone(),
+        ]
+    }
+}
+
+impl Tabular for UserDto {
+    const HEADERS: &'static [&'static str] = &["ID", "NAME", "ROLE"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.user_id.to_string(),
+            self.user_name.clone(),
+            self.role.to_string(),
+        ]
+    }
+}
+
+impl Tabular for BookDto {
+    const HEADERS: &'static [&'static str] = &[
+        "ID",
+        "ROOM",
+        "USER",
+        "DATE",
+        "START",
+        "END",
+        "ATTENDEES",
+        "STATUS",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.room_name.clone(),
+            self.user_name.clone(),
+            self.date.to_string(),
+            self.start_time.format("%H:%M").to_string(),
+            self.end_time.format("%H:%M").to_string(),
+            self.attendees.to_string(),
+            self.status.to_string(),
+        ]
+    }
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+pub fn print_list<T: Tabular>(format: Format, items: &[T]) -> Result<(), ErrService> {
+    match format {
+        Format::Table => {
+            let rows: Vec<Vec<String>> = items.iter().map(Tabular::cells).collect();
+            print!("{}", render_table(T::HEADERS, &rows));
+        }
+        Format::Json => print_json(&items)?,
+    }
+
+    Ok(())
+}
+
+pub fn print_one<T: Tabular>(format: Format, item: &T) -> Result<(), ErrService> {
+    match format {
+        Format::Table => print_list(format, std::slice::from_ref(item)),
+        Format::Json => print_json(item),
+    }
+}
+
+/// Reports a command that doesn't return items: `text` for people, `json` for scripts.
+pub fn print_done(format: Format, text: &str, json: Value) -> Result<(), ErrService> {
+    match format {
+        Format::Table => {
+            println!("{text}");
+            Ok(())
+        }
+        Format::Json => print_json(&json),
+    }
+}
+
+fn print_json(value: &impl Serialize) -> Result<(), ErrService> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| ErrService::IO(e.into()))?;
+    println!("{json}");
+
+    Ok(())
+}
+
+/// Pads every column but the last to its widest cell.
+fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    let mut table = String::new();
+    for row in std::iter::once(&headers).chain(rows) {
+        let last = row.len().saturating_sub(1);
+        for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            if i == last {
+                table.push_str(cell);
+            } else {
+                table.push_str(&format!("{cell:<width$}  "));
+            }
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_table;
+
+    #[test]
+    fn aligns_columns_on_their_widest_cell() {
+        let rows = vec![
+            vec!["1".to_string(), "BOARDROOM".to_string(), "12".to_string()],
+            vec!["12".to_string(), "ALPHA".to_string(), "4".to_string()],
+        ];
+
+        assert_eq!(
+            render_table(&["ID", "NAME", "CAPACITY"], &rows),
+            "ID  NAME       CAPACITY\n1   BOARDROOM  12\n12  ALPHA      4\n"
+        );
+    }
+}
@@ -1,13 +1,80 @@
+use chrono::Duration;
+
+/// HS256 keys shorter than the hash output (256 bits) weaken the signature.
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// How long bookings wait for approval before expiring, unless `PENDING_APPROVAL_HOURS` says.
+const DEFAULT_PENDING_APPROVAL_HOURS: i64 = 48;
+
+/// Where the service keeps its data, picked by `STORAGE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Storage {
+    /// PostgreSQL at `DATABASE_URL`, the default.
+    Postgres { database_url: String },
+    /// A SQLite file at `DATABASE_URL`, e.g. `sqlite://rooms.db`.
+    Sqlite { database_url: String },
+    /// The process memory: everything is lost on shutdown.
+    Memory,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub database_url: String,
+    pub storage: Storage,
+    pub jwt_secret: String,
+    /// Keeps serving the body-addressed routes superseded by `/{resource}/{id}`.
+    pub legacy_routes: bool,
+    /// How long a booking may stay pending before it expires.
+    pub pending_approval_ttl: Duration,
+    /// User made admin on startup, created if missing: signing up never makes admins.
+    pub admin_user: Option<String>,
 }
 
 impl Config {
     pub fn init() -> Config {
         let database_url =
-            std::env::var("DATABASE_URL").expect("Please enter a valid database address.");
+            || std::env::var("DATABASE_URL").expect("Please enter a valid database address.");
+        let storage = match std::env::var("STORAGE").as_deref().map(str::trim) {
+            Err(_) | Ok("postgres") => Storage::Postgres {
+                database_url: database_url(),
+            },
+            Ok("sqlite") => Storage::Sqlite {
+                database_url: database_url(),
+            },
+            Ok("memory") => Storage::Memory,
+            Ok(other) => panic!("STORAGE must be postgres, sqlite or memory, not {other}."),
+        };
+        let jwt_secret =
+            std::env::var("JWT_SECRET").expect("Please enter a secret to sign API tokens.");
+        assert!(
+            jwt_secret.len() >= MIN_JWT_SECRET_LEN,
+            "JWT_SECRET must be at least {MIN_JWT_SECRET_LEN} bytes long."
+        );
+
+        let legacy_routes = std::env::var("LEGACY_ROUTES")
+            .map(|v| !matches!(v.trim(), "false" | "0"))
+            .unwrap_or(true);
+
+        let pending_approval_hours = std::env::var("PENDING_APPROVAL_HOURS")
+            .map(|v| {
+                v.trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|h| *h > 0)
+                    .expect("PENDING_APPROVAL_HOURS must be a positive number of hours.")
+            })
+            .unwrap_or(DEFAULT_PENDING_APPROVAL_HOURS);
+
+        let admin_user = std::env::var("ADMIN_USER")
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
 
-        Config { database_url }
+        Config {
+            storage,
+            jwt_secret,
+            legacy_routes,
+            pending_approval_ttl: Duration::hours(pending_approval_hours),
+            admin_user,
+        }
     }
 }
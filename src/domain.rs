@@ -1,4 +1,9 @@
-use chrono::NaiveDate;
+use std::collections::BTreeSet;
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, Months, NaiveDate, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
 use uuid::Uuid;
 
 use crate::error::{ErrBook, ErrDomain, ErrRoom, ErrUser};
@@ -9,6 +14,7 @@ use crate::error::{ErrBook, ErrDomain, ErrRoom, ErrUser};
 pub struct User {
     pub user_id: UserID,
     pub user_name: UserName,
+    pub role: Role,
 }
 
 impl User {
@@ -16,8 +22,52 @@ impl User {
         Ok(Self {
             user_id: UserID::new(),
             user_name: UserName::new(name)?,
+            role: Role::default(),
         })
     }
+
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Whether this user may change or cancel the bookings of `booker`.
+    pub fn can_manage_bookings_of(&self, booker: &UserID) -> bool {
+        self.user_id == *booker || self.role.manages_all_bookings()
+    }
+}
+
+/// Admins manage rooms and users, facility managers handle everyone's bookings,
+/// members only their own.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum Role {
+    Admin,
+    FacilityManager,
+    #[default]
+    Member,
+}
+
+impl Role {
+    pub fn new(input: &str) -> Result<Self, ErrDomain> {
+        match input.trim().to_lowercase().replace('-', "_").as_str() {
+            "admin" => Ok(Role::Admin),
+            "facility_manager" => Ok(Role::FacilityManager),
+            "member" => Ok(Role::Member),
+            _ => Err(ErrDomain::User(ErrUser::InvalidRole)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::FacilityManager => "facility_manager",
+            Role::Member => "member",
+        }
+    }
+
+    pub fn manages_all_bookings(&self) -> bool {
+        matches!(self, Role::Admin | Role::FacilityManager)
+    }
 }
 
 #[derive(Debug, Eq, Hash, PartialEq, Clone)]
@@ -36,7 +86,7 @@ impl Default for UserID {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, sqlx::FromRow)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, sqlx::FromRow)]
 pub struct UserName {
     pub name: String,
 }
@@ -46,7 +96,7 @@ impl UserName {
         if cleaned_name.len() <= 2 {
             Err(ErrDomain::User(ErrUser::InvalidNameTooShort))
         } else if cleaned_name.len() >= 35 {
-            return Err(ErrDomain::User(ErrUser::InvalidNameTooLong));
+            Err(ErrDomain::User(ErrUser::InvalidNameTooLong))
         } else {
             Ok(Self {
                 name: cleaned_name.to_string(),
@@ -61,18 +111,55 @@ impl UserName {
 pub struct Room {
     pub id: i32,
     pub room_name: RoomName,
+    pub capacity: RoomCapacity,
+    pub location: RoomLocation,
+    pub equipment: BTreeSet<Equipment>,
+    /// Bookings by members start pending until a facility manager approves them.
+    pub requires_approval: bool,
+    /// Where the room is: its bookings' dates and times are wall-clock times there.
+    pub time_zone: Tz,
 }
 
 impl Room {
-    pub fn new(name: &str) -> Result<Self, ErrDomain> {
+    pub fn new(name: &str, capacity: i32) -> Result<Self, ErrDomain> {
         Ok(Self {
             id: 0,
             room_name: RoomName::new(name)?,
+            capacity: RoomCapacity::new(capacity)?,
+            location: RoomLocation::default(),
+            equipment: BTreeSet::new(),
+            requires_approval: false,
+            time_zone: Tz::UTC,
         })
     }
+
+    pub fn with_location(mut self, location: RoomLocation) -> Self {
+        self.location = location;
+        self
+    }
+
+    pub fn with_equipment(mut self, equipment: BTreeSet<Equipment>) -> Self {
+        self.equipment = equipment;
+        self
+    }
+
+    pub fn with_approval(mut self, requires_approval: bool) -> Self {
+        self.requires_approval = requires_approval;
+        self
+    }
+
+    pub fn with_time_zone(mut self, time_zone: Tz) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    /// Whether bookings of `user` in this room wait for a facility manager's approval.
+    pub fn needs_approval_from(&self, user: &User) -> bool {
+        self.requires_approval && !user.role.manages_all_bookings()
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, sqlx::FromRow)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, sqlx::FromRow)]
 pub struct RoomName {
     pub name: String,
 }
@@ -83,7 +170,7 @@ impl RoomName {
         if name.len() <= 2 {
             Err(ErrDomain::Room(ErrRoom::InvalidNameTooShort))
         } else if name.len() >= 17 {
-            return Err(ErrDomain::Room(ErrRoom::InvalidNameTooLong));
+            Err(ErrDomain::Room(ErrRoom::InvalidNameTooLong))
         } else {
             Ok(Self {
                 name: name.to_string().to_uppercase(),
@@ -92,25 +179,296 @@ impl RoomName {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct RoomCapacity {
+    pub seats: i32,
+}
+
+impl RoomCapacity {
+    pub const MAX_SEATS: i32 = 1000;
+
+    pub fn new(seats: i32) -> Result<Self, ErrDomain> {
+        if !(1..=Self::MAX_SEATS).contains(&seats) {
+            return Err(ErrDomain::Room(ErrRoom::InvalidCapacity));
+        }
+
+        Ok(Self { seats })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct RoomLocation {
+    pub floor: Option<i32>,
+    pub building: Option<String>,
+}
+
+impl RoomLocation {
+    pub fn new(floor: Option<i32>, building: Option<&str>) -> Self {
+        Self {
+            floor,
+            building: building
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(str::to_string),
+        }
+    }
+}
+
+/// Reads an IANA time zone name, such as `Europe/Paris`.
+pub fn parse_time_zone(name: &str) -> Result<Tz, ErrDomain> {
+    name.trim()
+        .parse()
+        .map_err(|_| ErrDomain::Room(ErrRoom::InvalidTimeZone))
+}
+
+/// The instant `date` at `time` is on the wall clocks of `time_zone`. Times skipped when
+/// clocks go forward are read an hour later, repeated ones at their first occurrence.
+pub fn instant_in(time_zone: Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    let local = date.and_time(time);
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            time_zone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum Equipment {
+    Projector,
+    VideoConference,
+    Whiteboard,
+}
+
+impl Equipment {
+    pub fn new(tag: &str) -> Result<Self, ErrDomain> {
+        match tag.trim().to_lowercase().replace('_', "-").as_str() {
+            "projector" => Ok(Self::Projector),
+            "video-conf" | "video-conference" | "videoconference" => Ok(Self::VideoConference),
+            "whiteboard" => Ok(Self::Whiteboard),
+            _ => Err(ErrDomain::Room(ErrRoom::InvalidEquipment)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Projector => "projector",
+            Self::VideoConference => "video-conf",
+            Self::Whiteboard => "whiteboard",
+        }
+    }
+
+    pub fn parse_all<S: AsRef<str>>(tags: &[S]) -> Result<BTreeSet<Self>, ErrDomain> {
+        tags.iter().map(|tag| Self::new(tag.as_ref())).collect()
+    }
+}
+
 ////////////////////////////REGISTERY BOOK
+/// A booking references its room and user by id, so renaming either keeps it attached.
+/// Their names, and the room's time zone, are carried along, as joined when the booking
+/// was read. `date` and `slot` are wall-clock times in that zone; `starts_at` and
+/// `ends_at` are the booked instants, as stored, which conflicts are checked on.
 #[derive(Debug, sqlx::FromRow, Eq, Hash, PartialEq, Clone)]
 pub struct Book {
     pub id: i32,
+    pub room_id: i32,
+    pub user_id: UserID,
     pub room_name: RoomName,
     pub user_name: UserName,
+    pub time_zone: Tz,
     pub date: BookDate,
+    pub slot: BookSlot,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub attendees: i32,
+    pub series_id: Option<Uuid>,
+    pub status: BookStatus,
+    pub cancellation: Option<Cancellation>,
+    pub created_at: DateTime<Utc>,
 }
 
 impl Book {
-    pub fn new(room_name: &str, user_name: &str, date: BookDate) -> Result<Self, ErrDomain> {
-        Ok(Self {
+    /// A booking of `room` by `user`, requested at `created_at`.
+    pub fn new(
+        room: &Room,
+        user: &User,
+        date: BookDate,
+        slot: BookSlot,
+        attendees: i32,
+        created_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
             id: 0,
-            room_name: RoomName::new(room_name)?,
-            user_name: UserName::new(user_name)?,
+            room_id: room.id,
+            user_id: user.user_id.clone(),
+            room_name: room.room_name.clone(),
+            user_name: user.user_name.clone(),
+            time_zone: room.time_zone,
+            starts_at: instant_in(room.time_zone, date.date, slot.start),
+            ends_at: instant_in(room.time_zone, date.date, slot.end),
             date,
-        })
+            slot,
+            attendees,
+            series_id: None,
+            status: if room.needs_approval_from(user) {
+                BookStatus::Pending
+            } else {
+                BookStatus::Confirmed
+            },
+            cancellation: None,
+            created_at,
+        }
+    }
+
+    /// `self` with its instants worked out again from its date, slot and time zone, once
+    /// one of them was changed.
+    pub fn with_instants(self) -> Self {
+        Self {
+            starts_at: instant_in(self.time_zone, self.date.date, self.slot.start),
+            ends_at: instant_in(self.time_zone, self.date.date, self.slot.end),
+            ..self
+        }
+    }
+
+    /// Cancelled bookings are kept for the record but no longer hold their slot.
+    pub fn is_cancelled(&self) -> bool {
+        self.status == BookStatus::Cancelled
+    }
+
+    /// Whether the booking still holds its slot, pending requests included.
+    pub fn is_live(&self) -> bool {
+        self.status.holds_slot()
+    }
+
+    /// Why the booking can no longer be changed, if it can't.
+    pub fn check_live(&self) -> Result<(), ErrBook> {
+        match self.status {
+            BookStatus::Cancelled => Err(ErrBook::Cancelled),
+            BookStatus::Rejected | BookStatus::Expired => Err(ErrBook::Declined),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the booking holds room `room_id` at some point between `starts_at` and
+    /// `ends_at`; back-to-back bookings don't conflict.
+    pub fn conflicts_with(
+        &self,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    ) -> bool {
+        self.is_live()
+            && self.room_id == room_id
+            && self.starts_at < ends_at
+            && self.ends_at > starts_at
+    }
+
+    pub fn cancel(self, cancellation: Cancellation) -> Self {
+        Self {
+            status: BookStatus::Cancelled,
+            cancellation: Some(cancellation),
+            ..self
+        }
+    }
+}
+
+/// Bookings start confirmed, or pending in rooms requiring approval until a facility
+/// manager approves or rejects them, or they expire. Once their day has come, facility
+/// managers mark them completed or no-show.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum BookStatus {
+    Pending,
+    #[default]
+    Confirmed,
+    Cancelled,
+    Rejected,
+    Expired,
+    Completed,
+    NoShow,
+}
+
+impl BookStatus {
+    pub fn new(input: &str) -> Result<Self, ErrDomain> {
+        match input.trim().to_lowercase().replace('-', "_").as_str() {
+            "pending" => Ok(BookStatus::Pending),
+            "confirmed" => Ok(BookStatus::Confirmed),
+            "cancelled" => Ok(BookStatus::Cancelled),
+            "rejected" => Ok(BookStatus::Rejected),
+            "expired" => Ok(BookStatus::Expired),
+            "completed" => Ok(BookStatus::Completed),
+            "no_show" => Ok(BookStatus::NoShow),
+            _ => Err(ErrDomain::Book(ErrBook::InvalidStatus)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookStatus::Pending => "pending",
+            BookStatus::Confirmed => "confirmed",
+            BookStatus::Cancelled => "cancelled",
+            BookStatus::Rejected => "rejected",
+            BookStatus::Expired => "expired",
+            BookStatus::Completed => "completed",
+            BookStatus::NoShow => "no_show",
+        }
+    }
+
+    /// Pending and confirmed bookings are yet to be settled: they keep their room and
+    /// user from being deleted, the others go with them.
+    pub fn is_open(&self) -> bool {
+        matches!(self, BookStatus::Pending | BookStatus::Confirmed)
+    }
+
+    /// Cancelled, rejected and expired bookings are kept for the record only.
+    pub fn holds_slot(&self) -> bool {
+        !matches!(
+            self,
+            BookStatus::Cancelled | BookStatus::Rejected | BookStatus::Expired
+        )
+    }
+}
+
+/// A user queuing for a room's slot on a date, promoted to a booking once it's free.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct WaitlistEntry {
+    pub id: i32,
+    pub room_id: i32,
+    pub user_id: UserID,
+    pub room_name: RoomName,
+    pub user_name: UserName,
+    pub date: BookDate,
+    pub slot: BookSlot,
+    pub attendees: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WaitlistEntry {
+    /// The booking this entry of `user` turns into once promoted at `created_at`, pending
+    /// as their direct booking of `room` would be.
+    pub fn to_book(&self, room: &Room, user: &User, created_at: DateTime<Utc>) -> Book {
+        Book::new(
+            room,
+            user,
+            self.date.clone(),
+            self.slot.clone(),
+            self.attendees,
+            created_at,
+        )
     }
 }
+
+/// Who cancelled or rejected a booking, when and why; no one for expired requests.
+/// `by` is lost if that user was deleted.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Cancellation {
+    pub at: DateTime<Utc>,
+    pub by: Option<UserID>,
+    pub reason: Option<String>,
+}
 #[derive(Debug, PartialEq, Eq, Hash, Clone, sqlx::FromRow)]
 pub struct BookDate {
     pub date: NaiveDate,
@@ -137,3 +495,681 @@ impl BookDate {
         Ok(Self { date: input_date })
     }
 }
+
+/// Inclusive range of days, e.g. for availability searches.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BookDateRange {
+    pub from: BookDate,
+    pub to: BookDate,
+}
+
+impl BookDateRange {
+    pub fn new(from: BookDate, to: BookDate) -> Result<Self, ErrDomain> {
+        if from.date > to.date {
+            return Err(ErrDomain::Book(ErrBook::InvalidDateRange));
+        }
+
+        Ok(Self { from, to })
+    }
+
+    pub fn single(date: BookDate) -> Self {
+        Self {
+            from: date.clone(),
+            to: date,
+        }
+    }
+
+    pub fn contains(&self, date: &BookDate) -> bool {
+        self.from.date <= date.date && date.date <= self.to.date
+    }
+}
+
+/// Time range of a booking within its day, `start` inclusive and `end` exclusive,
+/// so back-to-back slots (9:00-10:00 then 10:00-11:00) do not overlap.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct BookSlot {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl BookSlot {
+    pub fn new(start: &str, end: &str) -> Result<Self, ErrDomain> {
+        let parse = |input: &str| {
+            NaiveTime::parse_from_str(input.trim(), "%H:%M")
+                .map_err(|_| ErrDomain::Book(ErrBook::InvalidTimeFormat))
+        };
+
+        Self::from_naive(parse(start)?, parse(end)?)
+    }
+
+    pub fn from_naive(start: NaiveTime, end: NaiveTime) -> Result<Self, ErrDomain> {
+        if start >= end {
+            return Err(ErrDomain::Book(ErrBook::InvalidTimeRange));
+        }
+
+        Ok(Self { start, end })
+    }
+
+    pub fn overlaps(&self, other: &BookSlot) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+////////////////////////////CALENDAR FEEDS
+
+/// Whose bookings a calendar feed publishes.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum FeedSubject {
+    Room(i32),
+    User(UserID),
+}
+
+/// A secret token granting read access to a subject's calendar, so calendar apps can
+/// subscribe to it without an `Authorization` header.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CalendarFeed {
+    pub token: String,
+    pub subject: FeedSubject,
+}
+
+impl FeedSubject {
+    /// Room calendars are open to every user, a user's calendar to them and to those
+    /// managing every booking.
+    pub fn readable_by(&self, user: &User) -> bool {
+        match self {
+            FeedSubject::Room(_) => true,
+            FeedSubject::User(id) => user.can_manage_bookings_of(id),
+        }
+    }
+
+    /// Whether `user` may revoke the subject's feed, shared by everyone for rooms.
+    pub fn managed_by(&self, user: &User) -> bool {
+        match self {
+            FeedSubject::Room(_) => user.role.manages_all_bookings(),
+            FeedSubject::User(id) => user.can_manage_bookings_of(id),
+        }
+    }
+}
+
+impl CalendarFeed {
+    pub fn new(subject: FeedSubject) -> Self {
+        Self {
+            token: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            subject,
+        }
+    }
+}
+
+////////////////////////////RECURRENCE
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    pub fn new(input: &str) -> Result<Self, ErrDomain> {
+        match input.trim().to_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            "monthly" => Ok(Self::Monthly),
+            _ => Err(ErrDomain::Book(ErrBook::InvalidRecurrence)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RecurrenceEnd {
+    Until(BookDate),
+    Count(u32),
+}
+
+/// Recurrence rule of a booking series, a small subset of RFC 5545 RRULE:
+/// `frequency` every `interval` periods, restricted to `by_weekday` when given,
+/// until a date (inclusive) or for a number of occurrences.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub end: RecurrenceEnd,
+    pub by_weekday: Vec<Weekday>,
+}
+
+impl Recurrence {
+    pub const MAX_OCCURRENCES: usize = 366;
+    /// Bounds the expansion of sparse rules (e.g. daily on a weekday never reached).
+    const MAX_PERIODS: u32 = 3660;
+
+    pub fn new(
+        frequency: &str,
+        interval: Option<u32>,
+        until: Option<&str>,
+        count: Option<u32>,
+        by_weekday: &[String],
+    ) -> Result<Self, ErrDomain> {
+        let frequency = Frequency::new(frequency)?;
+
+        let interval = interval.unwrap_or(1);
+        if interval == 0 {
+            return Err(ErrDomain::Book(ErrBook::InvalidRecurrence));
+        }
+
+        let end = match (until, count) {
+            (Some(until), None) => RecurrenceEnd::Until(BookDate::new(until)?),
+            (None, Some(count)) if count > 0 => RecurrenceEnd::Count(count),
+            _ => return Err(ErrDomain::Book(ErrBook::InvalidRecurrence)),
+        };
+
+        let mut days = by_weekday
+            .iter()
+            .map(|day| parse_weekday(day))
+            .collect::<Result<Vec<Weekday>, ErrDomain>>()?;
+        days.sort_by_key(|d| d.num_days_from_monday());
+        days.dedup();
+
+        if frequency == Frequency::Monthly && !days.is_empty() {
+            return Err(ErrDomain::Book(ErrBook::InvalidRecurrence));
+        }
+
+        Ok(Self {
+            frequency,
+            interval,
+            end,
+            by_weekday: days,
+        })
+    }
+
+    /// Expands the rule into the dates of every occurrence, `first` included when it matches.
+    pub fn occurrences(&self, first: &BookDate) -> Result<Vec<BookDate>, ErrDomain> {
+        let first = first.date;
+        let mut dates = Vec::new();
+
+        'periods: for period in 0..Self::MAX_PERIODS {
+            let Some(candidates) = self.period_dates(first, period) else {
+                break;
+            };
+
+            for date in candidates.into_iter().filter(|d| *d >= first) {
+                let reached_end = match &self.end {
+                    RecurrenceEnd::Until(until) => date > until.date,
+                    RecurrenceEnd::Count(count) => dates.len() == *count as usize,
+                };
+                if reached_end {
+                    break 'periods;
+                }
+                if dates.len() == Self::MAX_OCCURRENCES {
+                    return Err(ErrDomain::Book(ErrBook::TooManyOccurrences));
+                }
+                dates.push(BookDate { date });
+            }
+        }
+
+        if dates.is_empty() {
+            return Err(ErrDomain::Book(ErrBook::InvalidRecurrence));
+        }
+
+        Ok(dates)
+    }
+
+    /// Candidate dates of the `period`-th period after `first`, `None` once past chrono's range.
+    fn period_dates(&self, first: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval)?;
+
+        match self.frequency {
+            Frequency::Daily => {
+                let date = first.checked_add_days(Days::new(step.into()))?;
+                if self.by_weekday.is_empty() || self.by_weekday.contains(&date.weekday()) {
+                    Some(vec![date])
+                } else {
+                    Some(vec![])
+                }
+            }
+            Frequency::Weekly => {
+                let monday = first
+                    .checked_sub_days(Days::new(first.weekday().num_days_from_monday().into()))?
+                    .checked_add_days(Days::new(u64::from(step) * 7))?;
+                if self.by_weekday.is_empty() {
+                    let offset = first.weekday().num_days_from_monday();
+                    return Some(vec![monday.checked_add_days(Days::new(offset.into()))?]);
+                }
+                self.by_weekday
+                    .iter()
+                    .map(|d| monday.checked_add_days(Days::new(d.num_days_from_monday().into())))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                // Like RFC 5545, months without the start day (e.g. the 31st) are skipped.
+                let date = first.checked_add_months(Months::new(step))?;
+                if date.day() == first.day() {
+                    Some(vec![date])
+                } else {
+                    Some(vec![])
+                }
+            }
+        }
+    }
+}
+
+fn parse_weekday(input: &str) -> Result<Weekday, ErrDomain> {
+    let day = input.trim().to_lowercase();
+    let day = match day.as_str() {
+        "mo" => "mon",
+        "tu" => "tue",
+        "we" => "wed",
+        "th" => "thu",
+        "fr" => "fri",
+        "sa" => "sat",
+        "su" => "sun",
+        other => other,
+    };
+
+    day.parse::<Weekday>()
+        .map_err(|_| ErrDomain::Book(ErrBook::InvalidRecurrence))
+}
+
+////////////////////////////LISTING
+
+/// Largest page a list endpoint returns.
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Keyset pagination: up to `limit` items, sorted by their key in `order`, starting
+/// right after the `after` key (the last item of the previous page).
+#[derive(Debug, Clone)]
+pub struct PageRequest<K> {
+    pub limit: u32,
+    pub after: Option<K>,
+    pub order: SortOrder,
+}
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Builds a page out of up to `limit + 1` items: the extra one only tells there's more.
+    pub fn from_overfetched(mut items: Vec<T>, limit: u32) -> Self {
+        let has_more = items.len() > limit as usize;
+        items.truncate(limit as usize);
+        Self { items, has_more }
+    }
+
+    /// Pages through `items` in memory the way the SQL repos do with `ORDER BY`/`LIMIT`.
+    pub fn from_unsorted<K: Ord>(
+        mut items: Vec<T>,
+        request: &PageRequest<K>,
+        key: impl Fn(&T) -> K,
+    ) -> Self {
+        items.sort_by_key(|item| key(item));
+        if request.order == SortOrder::Desc {
+            items.reverse();
+        }
+
+        let items: Vec<T> = items
+            .into_iter()
+            .filter(|item| match (&request.after, request.order) {
+                (None, _) => true,
+                (Some(after), SortOrder::Asc) => key(item) > *after,
+                (Some(after), SortOrder::Desc) => key(item) < *after,
+            })
+            .take(request.limit as usize + 1)
+            .collect();
+
+        Self::from_overfetched(items, request.limit)
+    }
+}
+
+/// Sort key of a booking in listings: chronological, ties broken by id.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct BookKey {
+    pub date: NaiveDate,
+    pub start: NaiveTime,
+    pub id: i32,
+}
+
+impl From<&Book> for BookKey {
+    fn from(book: &Book) -> Self {
+        Self {
+            date: book.date.date,
+            start: book.slot.start,
+            id: book.id,
+        }
+    }
+}
+
+/// Bookings of a room and/or a user, dated within `from..=to` when given.
+/// Cancelled, rejected and expired ones are left out unless `status` asks for them or
+/// `include_cancelled` is set.
+#[derive(Debug, Default)]
+pub struct BookFilter {
+    pub room: Option<RoomName>,
+    pub user: Option<UserName>,
+    pub from: Option<BookDate>,
+    pub to: Option<BookDate>,
+    pub status: Option<BookStatus>,
+    pub include_cancelled: bool,
+}
+
+impl BookFilter {
+    pub fn matches(&self, book: &Book) -> bool {
+        self.room
+            .as_ref()
+            .is_none_or(|room| book.room_name == *room)
+            && self
+                .user
+                .as_ref()
+                .is_none_or(|user| book.user_name == *user)
+            && self
+                .from
+                .as_ref()
+                .is_none_or(|from| book.date.date >= from.date)
+            && self.to.as_ref().is_none_or(|to| book.date.date <= to.date)
+            && match self.status {
+                Some(status) => book.status == status,
+                None => self.include_cancelled || book.is_live(),
+            }
+    }
+}
+
+/// Rooms are listed by name.
+#[derive(Debug, Default)]
+pub struct RoomFilter {
+    pub building: Option<String>,
+    pub min_capacity: Option<i32>,
+}
+
+impl RoomFilter {
+    pub fn matches(&self, room: &Room) -> bool {
+        self.building
+            .as_ref()
+            .is_none_or(|building| room.location.building.as_ref() == Some(building))
+            && self
+                .min_capacity
+                .is_none_or(|seats| room.capacity.seats >= seats)
+    }
+}
+
+/// Users are listed by name.
+#[derive(Debug, Default)]
+pub struct UserFilter {
+    pub role: Option<Role>,
+}
+
+impl UserFilter {
+    pub fn matches(&self, user: &User) -> bool {
+        self.role.is_none_or(|role| user.role == role)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(input: &str) -> BookDate {
+        BookDate::new(input).unwrap()
+    }
+
+    /// `hour:minute` UTC on `day`.
+    fn at(day: &str, hour: u32, minute: u32) -> DateTime<Utc> {
+        date(day)
+            .date
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn weekly_series_on_given_weekdays() {
+        let rule = Recurrence::new(
+            "weekly",
+            None,
+            None,
+            Some(4),
+            &["MO".to_string(), "wed".to_string()],
+        )
+        .unwrap();
+
+        // 02.06.25 is a Monday
+        let dates = rule.occurrences(&date("03.06.25")).unwrap();
+        assert_eq!(
+            dates,
+            vec![
+                date("04.06.25"),
+                date("09.06.25"),
+                date("11.06.25"),
+                date("16.06.25")
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_series_until_date_with_interval() {
+        let rule = Recurrence::new("daily", Some(2), Some("07.06.25"), None, &[]).unwrap();
+
+        let dates = rule.occurrences(&date("02.06.25")).unwrap();
+        assert_eq!(
+            dates,
+            vec![date("02.06.25"), date("04.06.25"), date("06.06.25")]
+        );
+    }
+
+    #[test]
+    fn monthly_series_skips_short_months() {
+        let rule = Recurrence::new("monthly", None, None, Some(3), &[]).unwrap();
+
+        let dates = rule.occurrences(&date("31.01.25")).unwrap();
+        assert_eq!(
+            dates,
+            vec![date("31.01.25"), date("31.03.25"), date("31.05.25")]
+        );
+    }
+
+    #[test]
+    fn invalid_recurrence_rules() {
+        assert!(Recurrence::new("hourly", None, None, Some(2), &[]).is_err());
+        assert!(Recurrence::new("daily", Some(0), None, Some(2), &[]).is_err());
+        assert!(Recurrence::new("daily", None, None, None, &[]).is_err()); // no end
+        assert!(Recurrence::new("daily", None, Some("01.01.26"), Some(2), &[]).is_err());
+        assert!(Recurrence::new("monthly", None, None, Some(2), &["mo".to_string()]).is_err());
+
+        let endless = Recurrence::new("daily", None, Some("01.01.35"), None, &[]).unwrap();
+        assert!(endless.occurrences(&date("01.01.25")).is_err()); // too many occurrences
+    }
+
+    #[test]
+    fn bookings_conflict_by_room_id() {
+        let mut room = Room::new("alpha", 6).unwrap();
+        room.id = 1;
+        let user = User::new("sophie").unwrap();
+        let slot = BookSlot::new("09:00", "10:00").unwrap();
+        let book = Book::new(
+            &room,
+            &user,
+            date("10.01.27"),
+            slot,
+            2,
+            at("09.01.27", 8, 0),
+        );
+
+        let (later, later_end) = (at("10.01.27", 9, 30), at("10.01.27", 10, 30));
+        let (next, next_end) = (at("10.01.27", 10, 0), at("10.01.27", 11, 0));
+        assert!(book.conflicts_with(1, later, later_end));
+        assert!(!book.conflicts_with(1, next, next_end)); // back-to-back
+        assert!(!book.conflicts_with(2, later, later_end));
+        assert!(!book.conflicts_with(1, at("11.01.27", 9, 30), at("11.01.27", 10, 30)));
+    }
+
+    #[test]
+    fn cancelled_bookings_free_their_slot_and_leave_listings() {
+        let mut room = Room::new("alpha", 6).unwrap();
+        room.id = 1;
+        let user = User::new("sophie").unwrap();
+        let slot = BookSlot::new("09:00", "10:00").unwrap();
+        let book = Book::new(
+            &room,
+            &user,
+            date("10.01.27"),
+            slot.clone(),
+            2,
+            at("09.01.27", 8, 0),
+        );
+        assert!(BookFilter::default().matches(&book));
+
+        let cancelled = book.cancel(Cancellation {
+            at: at("09.01.27", 12, 0),
+            by: Some(user.user_id.clone()),
+            reason: Some("moved online".to_string()),
+        });
+        assert_eq!(cancelled.status, BookStatus::Cancelled);
+        assert!(!cancelled.conflicts_with(1, cancelled.starts_at, cancelled.ends_at));
+        assert!(!BookFilter::default().matches(&cancelled));
+
+        let history = BookFilter {
+            include_cancelled: true,
+            ..BookFilter::default()
+        };
+        let only_cancelled = BookFilter {
+            status: Some(BookStatus::new("Cancelled").unwrap()),
+            ..BookFilter::default()
+        };
+        assert!(history.matches(&cancelled));
+        assert!(only_cancelled.matches(&cancelled));
+        assert!(BookStatus::new("no-show").is_ok());
+        assert!(BookStatus::new("postponed").is_err());
+    }
+
+    #[test]
+    fn only_booker_or_managers_handle_a_booking() {
+        let booker = User::new("sophie").unwrap();
+        let member = User::new("jordan").unwrap();
+        let manager = User::new("alice")
+            .unwrap()
+            .with_role(Role::new("facility_manager").unwrap());
+
+        assert!(booker.can_manage_bookings_of(&booker.user_id));
+        assert!(!member.can_manage_bookings_of(&booker.user_id));
+        assert!(manager.can_manage_bookings_of(&booker.user_id));
+        assert!(Role::new("superuser").is_err());
+    }
+
+    #[test]
+    fn pending_requests_hold_their_slot_until_declined() {
+        let mut room = Room::new("board", 12).unwrap().with_approval(true);
+        room.id = 1;
+        let member = User::new("jordan").unwrap();
+        let manager = User::new("alice")
+            .unwrap()
+            .with_role(Role::new("facility_manager").unwrap());
+        let slot = BookSlot::new("09:00", "10:00").unwrap();
+
+        let request = Book::new(
+            &room,
+            &member,
+            date("10.01.27"),
+            slot.clone(),
+            4,
+            at("09.01.27", 8, 0),
+        );
+        assert_eq!(request.status, BookStatus::Pending);
+        assert!(request.conflicts_with(1, request.starts_at, request.ends_at));
+        assert!(request.check_live().is_ok());
+        let managed = Book::new(
+            &room,
+            &manager,
+            date("10.01.27"),
+            slot.clone(),
+            4,
+            at("09.01.27", 8, 0),
+        );
+        assert_eq!(managed.status, BookStatus::Confirmed);
+        let queued = |user: &User| WaitlistEntry {
+            id: 1,
+            room_id: room.id,
+            user_id: user.user_id.clone(),
+            room_name: room.room_name.clone(),
+            user_name: user.user_name.clone(),
+            date: date("10.01.27"),
+            slot: slot.clone(),
+            attendees: 4,
+            created_at: at("09.01.27", 8, 0),
+        };
+        let promoted = |user: &User| {
+            queued(user)
+                .to_book(&room, user, at("09.01.27", 9, 0))
+                .status
+        };
+        assert_eq!(promoted(&member), BookStatus::Pending);
+        assert_eq!(promoted(&manager), BookStatus::Confirmed);
+
+        let rejected = Book {
+            status: BookStatus::Rejected,
+            ..request
+        };
+        assert!(!rejected.conflicts_with(1, rejected.starts_at, rejected.ends_at));
+        assert!(!BookFilter::default().matches(&rejected));
+        assert!(matches!(rejected.check_live(), Err(ErrBook::Declined)));
+    }
+
+    #[test]
+    fn bookings_start_at_the_wall_clock_of_their_room() {
+        let room = Room::new("board", 12)
+            .unwrap()
+            .with_time_zone(parse_time_zone("Europe/Paris").unwrap());
+        let user = User::new("jordan").unwrap();
+        let book = Book::new(
+            &room,
+            &user,
+            date("10.07.27"),
+            BookSlot::new("09:00", "10:30").unwrap(),
+            1,
+            at("09.07.27", 8, 0),
+        );
+        assert_eq!(book.time_zone, Tz::Europe__Paris);
+        assert_eq!(
+            book.starts_at,
+            Utc.with_ymd_and_hms(2027, 7, 10, 7, 0, 0).unwrap()
+        );
+        assert_eq!(
+            book.ends_at,
+            Utc.with_ymd_and_hms(2027, 7, 10, 8, 30, 0).unwrap()
+        );
+
+        // 02:30 doesn't exist on the last Sunday of March, it's read as 03:30.
+        let paris = Tz::Europe__Paris;
+        let skipped = instant_in(
+            paris,
+            date("28.03.27").date,
+            NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+        );
+        assert_eq!(
+            skipped,
+            Utc.with_ymd_and_hms(2027, 3, 28, 1, 30, 0).unwrap()
+        );
+        // 02:30 happens twice on the last Sunday of October, the first one is kept.
+        let repeated = instant_in(
+            paris,
+            date("31.10.27").date,
+            NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+        );
+        assert_eq!(
+            repeated,
+            Utc.with_ymd_and_hms(2027, 10, 31, 0, 30, 0).unwrap()
+        );
+
+        assert_eq!(Room::new("annex", 4).unwrap().time_zone, Tz::UTC);
+        assert!(matches!(
+            parse_time_zone("Mars/Olympus"),
+            Err(ErrDomain::Room(ErrRoom::InvalidTimeZone))
+        ));
+    }
+}
@@ -5,6 +5,8 @@ pub enum ErrUser {
     InvalidID,
     AlreadyExist,
     UserNotFound,
+    HasBookings,
+    InvalidRole,
 }
 
 #[derive(Debug)]
@@ -14,6 +16,10 @@ pub enum ErrRoom {
     InvalidID,
     AlreadyExist,
     RoomNotFound,
+    InvalidCapacity,
+    InvalidEquipment,
+    InvalidTimeZone,
+    HasBookings,
 }
 
 #[derive(Debug)]
@@ -23,10 +29,40 @@ pub enum ErrBook {
     AlreadyBooked,
     InvalidDateFormat,
     InvalidDate,
-    InvalidID,
+    InvalidTimeFormat,
+    InvalidTimeRange,
+    InvalidDateRange,
+    InvalidAttendees,
+    CapacityExceeded,
+    InvalidRecurrence,
+    TooManyOccurrences,
+    SeriesNotFound,
+    BookNotFound,
+    NotOwner,
+    Cancelled,
+    Declined,
+    InvalidStatus,
+    SlotHeld,
+    NotPending,
+    Duplicate,
+    InvalidCalendar,
+    SlotAvailable,
+    AlreadyQueued,
+    WaitlistEntryNotFound,
     UnableToRead,
 }
 
+#[derive(Debug)]
+pub enum ErrAuth {
+    MissingToken,
+    InvalidToken,
+    ExpiredToken,
+    UnknownUser,
+    Forbidden,
+    UnknownFeed,
+    UnableToIssue,
+}
+
 #[derive(Debug)]
 pub enum ErrType {
     RawConversionFailed,
@@ -40,6 +76,8 @@ pub enum ErrRepo {
     BadRequest,
     UnableToDelete,
     IsEmpty,
+    InvalidCursor,
+    InvalidPageRequest,
 }
 
 #[derive(Debug)]
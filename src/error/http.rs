@@ -1,42 +1,144 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{
+        HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, WWW_AUTHENTICATE},
+    },
     response::{IntoResponse, Response},
 };
-use serde_json::json;
+use serde::Serialize;
+use utoipa::ToSchema;
 
-pub fn bad_request(msg: &str) -> Response {
-    (StatusCode::BAD_REQUEST, Json(json!({"error" : msg }))).into_response()
+use crate::app::request_id::current_request_id;
+
+/// Body of every error response (RFC 7807), served as `application/problem+json`.
+#[derive(Serialize, ToSchema)]
+pub struct ProblemDto {
+    /// Always `about:blank`: `code` tells problems apart.
+    #[serde(rename = "type")]
+    #[schema(example = "about:blank")]
+    pub kind: &'static str,
+    /// Reason phrase of `status`.
+    #[schema(example = "Conflict")]
+    pub title: &'static str,
+    #[schema(example = 409)]
+    pub status: u16,
+    /// Stable identifier for clients to branch on.
+    #[schema(example = "BOOK_ALREADY_BOOKED")]
+    pub code: &'static str,
+    /// Human readable explanation, wording may change.
+    #[schema(example = "Room already booked for this time slot")]
+    pub detail: String,
+    /// Offending request fields, when the problem is about the input.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldErrorDto>,
+    /// Also sent as the `x-request-id` header, to quote when reporting an issue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FieldErrorDto {
+    #[schema(example = "start_time")]
+    pub field: &'static str,
+    #[schema(example = "Invalid time format, expected HH:MM")]
+    pub message: &'static str,
+}
+
+/// An error response under construction.
+pub struct Problem {
+    status: StatusCode,
+    code: &'static str,
+    detail: &'static str,
+    fields: Vec<&'static str>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, code: &'static str, detail: &'static str) -> Self {
+        Problem {
+            status,
+            code,
+            detail,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Blames request field `field` for the problem.
+    pub fn field(mut self, field: &'static str) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn detail(&self) -> &'static str {
+        self.detail
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let body = ProblemDto {
+            kind: "about:blank",
+            title: self.status.canonical_reason().unwrap_or_default(),
+            status: self.status.as_u16(),
+            code: self.code,
+            detail: self.detail.to_string(),
+            errors: self
+                .fields
+                .into_iter()
+                .map(|field| FieldErrorDto {
+                    field,
+                    message: self.detail,
+                })
+                .collect(),
+            request_id: current_request_id(),
+        };
+
+        let mut response = (self.status, Json(body)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        if self.status == StatusCode::UNAUTHORIZED {
+            headers.insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+
+        response
+    }
+}
+
+pub fn bad_request(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::BAD_REQUEST, code, detail)
+}
+
+pub fn unauthorized(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::UNAUTHORIZED, code, detail)
+}
+
+pub fn forbidden(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::FORBIDDEN, code, detail)
 }
 
-pub fn conflict(msg: &str) -> Response {
-    (StatusCode::CONFLICT, Json(json!({"error" : msg}))).into_response()
+pub fn conflict(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::CONFLICT, code, detail)
 }
 
-pub fn unavailable(msg: &str) -> Response {
-    (
-        StatusCode::SERVICE_UNAVAILABLE,
-        Json(json!({"error" : msg})),
-    )
-        .into_response()
+pub fn unavailable(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::SERVICE_UNAVAILABLE, code, detail)
 }
 
-pub fn internal_error(msg: &str) -> Response {
-    (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(json!({"error" : msg})),
-    )
-        .into_response()
+pub fn internal_error(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::INTERNAL_SERVER_ERROR, code, detail)
 }
 
-pub fn unprocessable_entity(msg: &str) -> Response {
-    (
-        StatusCode::UNPROCESSABLE_ENTITY,
-        Json(json!({"error" : msg})),
-    )
-        .into_response()
+pub fn unprocessable_entity(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::UNPROCESSABLE_ENTITY, code, detail)
 }
 
-pub fn not_found(msg: &str) -> Response {
-    (StatusCode::NOT_FOUND, Json(json!({"error" : msg}))).into_response()
+pub fn not_found(code: &'static str, detail: &'static str) -> Problem {
+    Problem::new(StatusCode::NOT_FOUND, code, detail)
 }
@@ -1,17 +1,14 @@
 use crate::error::{domain::*, http::*};
 
-use axum::{
-    Json,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-};
-use serde_json::json;
+use axum::response::{IntoResponse, Response};
+use tracing::error;
 
 #[derive(Debug)]
 pub enum ErrService {
     Book(ErrBook),
     User(ErrUser),
     Room(ErrRoom),
+    Auth(ErrAuth),
     Repo(ErrRepo),
     Domain(ErrDomain),
     Type(ErrType),
@@ -37,6 +34,12 @@ impl From<ErrRoom> for ErrService {
     }
 }
 
+impl From<ErrAuth> for ErrService {
+    fn from(err: ErrAuth) -> Self {
+        ErrService::Auth(err)
+    }
+}
+
 impl From<ErrRepo> for ErrService {
     fn from(err: ErrRepo) -> Self {
         ErrService::Repo(err)
@@ -69,56 +72,250 @@ impl From<std::io::Error> for ErrService {
 
 impl IntoResponse for ErrService {
     fn into_response(self) -> Response {
-        match self {
-            //  BOOK ERROR
-            ErrService::Book(ErrBook::InvalidDateFormat) => bad_request("Invalid date format"),
-            ErrService::Book(ErrBook::AlreadyBooked) => {
-                conflict("Room already booked at this date")
-            }
-            ErrService::Book(ErrBook::InvalidDate) => unprocessable_entity("Date already past"),
-            ErrService::Book(ErrBook::RoomNotFound) => not_found("Room not found in the system"),
-            ErrService::Book(ErrBook::UserNotFound) => not_found("User not found in the system"),
-            ErrService::Book(ErrBook::UnableToRead) => internal_error("Unable to read book"),
-            ErrService::Book(ErrBook::InvalidID) => bad_request("Invalid ID, please check book ID"),
-            //  USER ERROR
-            ErrService::User(ErrUser::InvalidNameTooShort) => {
-                unprocessable_entity("User's name is too short")
-            }
-            ErrService::User(ErrUser::InvalidNameTooLong) => {
-                unprocessable_entity("User's name is too long")
-            }
-            ErrService::User(ErrUser::InvalidID) => not_found("User's ID not found in the system"),
-            ErrService::User(ErrUser::UserNotFound) => not_found("User not found in the system"),
-            ErrService::User(ErrUser::AlreadyExist) => conflict("User already exists"),
+        Problem::from(self).into_response()
+    }
+}
 
-            //  ROOM ERROR
-            ErrService::Room(ErrRoom::InvalidNameTooShort) => {
-                unprocessable_entity("Room's name is too short")
-            }
-            ErrService::Room(ErrRoom::InvalidNameTooLong) => {
-                unprocessable_entity("Room's name is too long")
-            }
-            ErrService::Room(ErrRoom::InvalidID) => bad_request("Invalid room's ID"),
-            ErrService::Room(ErrRoom::AlreadyExist) => conflict("Room already exists"),
-            ErrService::Room(ErrRoom::RoomNotFound) => not_found("Room not found in the system"),
-            // TYPE ERROR
+impl From<ErrService> for Problem {
+    fn from(err: ErrService) -> Self {
+        // Value objects fail with the same variants as the services.
+        match err {
+            ErrService::Book(err) | ErrService::Domain(ErrDomain::Book(err)) => book_problem(err),
+            ErrService::User(err) | ErrService::Domain(ErrDomain::User(err)) => user_problem(err),
+            ErrService::Room(err) | ErrService::Domain(ErrDomain::Room(err)) => room_problem(err),
+            ErrService::Auth(err) => auth_problem(err),
+            ErrService::Repo(err) => repo_problem(err),
             ErrService::Type(ErrType::RawConversionFailed) => {
-                internal_error("Raw conversion failed")
+                internal_error("RAW_CONVERSION_FAILED", "Raw conversion failed")
+            }
+            ErrService::Sqlx(sqlx::Error::PoolTimedOut | sqlx::Error::Io(_)) => {
+                unavailable("DATABASE_UNREACHABLE", "Database is unreachable")
             }
-            // DBREQUEST ERR
-            ErrService::Repo(ErrRepo::BadRequest) => bad_request("Invalid request"),
-            ErrService::Repo(ErrRepo::Unreachable) => {
-                let body = Json(json!({"error" : "Database is unreachable"}));
-                (StatusCode::SERVICE_UNAVAILABLE, body).into_response()
+            ErrService::Sqlx(err) => {
+                error!("Database error: {:?}", err);
+                internal_error("DATABASE_ERROR", "Database error")
             }
-            ErrService::Repo(ErrRepo::DoesntExist) => not_found("Not found in the system"),
-            ErrService::Repo(ErrRepo::IsEmpty) => not_found("Already empty"),
-            ErrService::Repo(ErrRepo::UnableToDelete) => unavailable("Unable to do this action"),
-            // CATCH ALL
-            _ => {
-                let body = json!({ "error": "Service error" });
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+            ErrService::IO(err) => {
+                error!("IO error: {:?}", err);
+                internal_error("INTERNAL_ERROR", "Internal error")
             }
         }
     }
 }
+
+fn book_problem(err: ErrBook) -> Problem {
+    match err {
+        ErrBook::InvalidDateFormat => bad_request(
+            "BOOK_INVALID_DATE_FORMAT",
+            "Invalid date format, expected dd.mm.yy",
+        )
+        .field("date"),
+        ErrBook::AlreadyBooked => conflict(
+            "BOOK_ALREADY_BOOKED",
+            "Room already booked for this time slot",
+        ),
+        ErrBook::InvalidDate => {
+            unprocessable_entity("BOOK_DATE_PAST", "Date already past").field("date")
+        }
+        ErrBook::InvalidTimeFormat => bad_request(
+            "BOOK_INVALID_TIME_FORMAT",
+            "Invalid time format, expected HH:MM",
+        )
+        .field("start_time")
+        .field("end_time"),
+        ErrBook::InvalidTimeRange => unprocessable_entity(
+            "BOOK_INVALID_TIME_RANGE",
+            "Booking must end after it starts",
+        )
+        .field("end_time"),
+        ErrBook::InvalidDateRange => bad_request(
+            "BOOK_INVALID_DATE_RANGE",
+            "Invalid date range, provide `date` or `from` and `to`",
+        ),
+        ErrBook::InvalidAttendees => {
+            unprocessable_entity("BOOK_INVALID_ATTENDEES", "Attendees must be at least 1")
+                .field("attendees")
+        }
+        ErrBook::CapacityExceeded => unprocessable_entity(
+            "BOOK_CAPACITY_EXCEEDED",
+            "Attendees exceed the room's capacity",
+        )
+        .field("attendees"),
+        ErrBook::InvalidRecurrence => {
+            unprocessable_entity("BOOK_INVALID_RECURRENCE", "Invalid recurrence rule")
+                .field("recurrence")
+        }
+        ErrBook::TooManyOccurrences => unprocessable_entity(
+            "BOOK_TOO_MANY_OCCURRENCES",
+            "Recurrence produces too many occurrences",
+        )
+        .field("recurrence"),
+        ErrBook::SeriesNotFound => not_found("BOOK_SERIES_NOT_FOUND", "Booking series not found"),
+        ErrBook::RoomNotFound => {
+            not_found("ROOM_NOT_FOUND", "Room not found in the system").field("room_name")
+        }
+        ErrBook::UserNotFound => not_found("USER_NOT_FOUND", "User not found in the system"),
+        ErrBook::UnableToRead => internal_error("BOOK_UNREADABLE", "Unable to read book"),
+        ErrBook::BookNotFound => not_found("BOOK_NOT_FOUND", "Booking not found"),
+        ErrBook::NotOwner => forbidden(
+            "BOOK_NOT_OWNER",
+            "Only the booker or a facility manager can change this booking",
+        ),
+        ErrBook::Cancelled => conflict("BOOK_CANCELLED", "Booking is cancelled"),
+        ErrBook::InvalidCalendar => bad_request(
+            "BOOK_INVALID_CALENDAR",
+            "Invalid iCalendar document, expected a VCALENDAR",
+        ),
+        ErrBook::Duplicate => conflict(
+            "BOOK_DUPLICATE",
+            "Identical booking already on record",
+        ),
+        ErrBook::Declined => conflict(
+            "BOOK_DECLINED",
+            "Booking request was rejected or expired",
+        ),
+        ErrBook::SlotHeld => conflict(
+            "BOOK_SLOT_PENDING_APPROVAL",
+            "Room held for this time slot by a booking awaiting approval",
+        ),
+        ErrBook::NotPending => conflict(
+            "BOOK_NOT_PENDING",
+            "Only pending bookings can be approved or rejected",
+        ),
+        ErrBook::InvalidStatus => unprocessable_entity(
+            "BOOK_INVALID_STATUS",
+            "Invalid status, expected confirmed, completed or no_show for a booking no longer pending, the latter two once its day has come",
+        )
+        .field("status"),
+        ErrBook::SlotAvailable => conflict(
+            "WAITLIST_SLOT_AVAILABLE",
+            "The slot is free, book it instead of queuing",
+        ),
+        ErrBook::AlreadyQueued => conflict(
+            "WAITLIST_ALREADY_QUEUED",
+            "Already on the waitlist for this slot",
+        ),
+        ErrBook::WaitlistEntryNotFound => {
+            not_found("WAITLIST_ENTRY_NOT_FOUND", "Waitlist entry not found")
+        }
+    }
+}
+
+fn user_problem(err: ErrUser) -> Problem {
+    match err {
+        ErrUser::InvalidNameTooShort => {
+            unprocessable_entity("USER_NAME_TOO_SHORT", "User's name is too short")
+                .field("user_name")
+        }
+        ErrUser::InvalidNameTooLong => {
+            unprocessable_entity("USER_NAME_TOO_LONG", "User's name is too long").field("user_name")
+        }
+        ErrUser::InvalidID => not_found("USER_INVALID_ID", "User's ID not found in the system"),
+        ErrUser::UserNotFound => not_found("USER_NOT_FOUND", "User not found in the system"),
+        ErrUser::AlreadyExist => {
+            conflict("USER_ALREADY_EXISTS", "User already exists").field("user_name")
+        }
+        ErrUser::InvalidRole => unprocessable_entity(
+            "USER_INVALID_ROLE",
+            "Unknown role, expected admin, facility_manager or member",
+        )
+        .field("role"),
+        ErrUser::HasBookings => conflict(
+            "USER_HAS_BOOKINGS",
+            "User has pending or confirmed bookings: cancel or settle them first",
+        ),
+    }
+}
+
+fn room_problem(err: ErrRoom) -> Problem {
+    match err {
+        ErrRoom::InvalidNameTooShort => {
+            unprocessable_entity("ROOM_NAME_TOO_SHORT", "Room's name is too short")
+                .field("room_name")
+        }
+        ErrRoom::InvalidNameTooLong => {
+            unprocessable_entity("ROOM_NAME_TOO_LONG", "Room's name is too long").field("room_name")
+        }
+        ErrRoom::InvalidID => bad_request("ROOM_INVALID_ID", "Invalid room's ID").field("id"),
+        ErrRoom::AlreadyExist => {
+            conflict("ROOM_ALREADY_EXISTS", "Room already exists").field("room_name")
+        }
+        ErrRoom::RoomNotFound => not_found("ROOM_NOT_FOUND", "Room not found in the system"),
+        ErrRoom::HasBookings => conflict(
+            "ROOM_HAS_BOOKINGS",
+            "Room has pending or confirmed bookings: cancel or settle them first",
+        ),
+        ErrRoom::InvalidCapacity => unprocessable_entity(
+            "ROOM_INVALID_CAPACITY",
+            "Room's capacity must be between 1 and 1000 seats",
+        )
+        .field("capacity"),
+        ErrRoom::InvalidEquipment => unprocessable_entity(
+            "ROOM_INVALID_EQUIPMENT",
+            "Unknown equipment, expected projector, video-conf or whiteboard",
+        )
+        .field("equipment"),
+        ErrRoom::InvalidTimeZone => unprocessable_entity(
+            "ROOM_INVALID_TIME_ZONE",
+            "Unknown time zone, expected an IANA name such as Europe/Paris",
+        )
+        .field("time_zone"),
+    }
+}
+
+fn auth_problem(err: ErrAuth) -> Problem {
+    match err {
+        ErrAuth::MissingToken => unauthorized("AUTH_MISSING_TOKEN", "Missing bearer token"),
+        ErrAuth::InvalidToken => unauthorized("AUTH_INVALID_TOKEN", "Invalid token"),
+        ErrAuth::ExpiredToken => unauthorized("AUTH_EXPIRED_TOKEN", "Token expired"),
+        ErrAuth::UnknownUser => unauthorized("AUTH_UNKNOWN_USER", "Token's user no longer exists"),
+        ErrAuth::Forbidden => forbidden("AUTH_FORBIDDEN", "Your role doesn't allow this action"),
+        ErrAuth::UnknownFeed => not_found("CALENDAR_FEED_NOT_FOUND", "Unknown calendar feed"),
+        ErrAuth::UnableToIssue => internal_error("AUTH_UNABLE_TO_ISSUE", "Unable to issue token"),
+    }
+}
+
+fn repo_problem(err: ErrRepo) -> Problem {
+    match err {
+        ErrRepo::BadRequest => bad_request("REPO_BAD_REQUEST", "Invalid request"),
+        ErrRepo::RequestError => internal_error("REPO_REQUEST_FAILED", "Database request failed"),
+        ErrRepo::Unreachable => unavailable("DATABASE_UNREACHABLE", "Database is unreachable"),
+        ErrRepo::DoesntExist => not_found("NOT_FOUND", "Not found in the system"),
+        ErrRepo::IsEmpty => not_found("ALREADY_EMPTY", "Already empty"),
+        ErrRepo::InvalidCursor => bad_request("INVALID_CURSOR", "Invalid cursor").field("cursor"),
+        ErrRepo::InvalidPageRequest => bad_request(
+            "INVALID_PAGE_REQUEST",
+            "Invalid paging, expected a limit of 1-200 and order asc or desc",
+        ),
+        ErrRepo::UnableToDelete => unavailable("REPO_UNABLE_TO_DELETE", "Unable to do this action"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::to_bytes,
+        http::{StatusCode, header::CONTENT_TYPE},
+        response::IntoResponse,
+    };
+    use serde_json::Value;
+
+    use crate::error::{ErrDomain, ErrRoom, ErrService};
+
+    #[tokio::test]
+    async fn domain_errors_map_to_problem_details() {
+        let response =
+            ErrService::Domain(ErrDomain::Room(ErrRoom::InvalidCapacity)).into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/problem+json");
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let problem: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["type"], "about:blank");
+        assert_eq!(problem["title"], "Unprocessable Entity");
+        assert_eq!(problem["status"], 422);
+        assert_eq!(problem["code"], "ROOM_INVALID_CAPACITY");
+        assert_eq!(problem["errors"][0]["field"], "capacity");
+        assert!(problem.get("request_id").is_none());
+    }
+}
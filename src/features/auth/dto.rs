@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::service::AuthToken;
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenDto {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<AuthToken> for TokenDto {
+    fn from(token: AuthToken) -> Self {
+        TokenDto {
+            token: token.token,
+            expires_at: token.expires_at,
+        }
+    }
+}
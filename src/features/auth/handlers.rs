@@ -0,0 +1,27 @@
+use axum::{Extension, Json, extract::State, response::IntoResponse};
+
+use crate::{
+    app::state::AppState,
+    error::{ErrService, ProblemDto},
+    features::auth::{dto::TokenDto, middleware::AuthUser},
+    infra::backend::Backend,
+};
+
+/// Trades the caller's still valid token for a fresh one.
+#[utoipa::path(
+    post,
+    path = "/auth/token",
+    tag = "auth",
+    responses(
+        (status = 200, description = "A fresh token", body = TokenDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn refresh_token<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ErrService> {
+    let token = state.auth_service.issue_token(&user)?;
+
+    Ok(Json(TokenDto::from(token)))
+}
@@ -0,0 +1,64 @@
+use axum::{
+    Extension,
+    extract::{Request, State},
+    http::{HeaderMap, header::AUTHORIZATION},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    app::state::AppState,
+    domain::{Role, User},
+    error::{ErrAuth, ErrService},
+    infra::backend::Backend,
+};
+
+/// The authenticated caller, added to the request extensions by [`require_auth`].
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub User);
+
+/// Rejects requests without a valid `Authorization: Bearer <token>` header and
+/// resolves the token to a known user.
+pub async fn require_auth<B: Backend>(
+    State(state): State<AppState<B>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ErrService> {
+    let user = authenticate(&state, req.headers()).await?;
+
+    req.extensions_mut().insert(AuthUser(user));
+    Ok(next.run(req).await)
+}
+
+/// Resolves the `Authorization: Bearer <token>` header to a known user, for routes
+/// outside [`require_auth`] that accept other credentials too.
+pub async fn authenticate<B: Backend>(
+    state: &AppState<B>,
+    headers: &HeaderMap,
+) -> Result<User, ErrService> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ErrService::Auth(ErrAuth::MissingToken))?;
+
+    let user_id = state.auth_service.verify_token(token.trim())?;
+    state
+        .user_service
+        .get_user_by_id_on_cache(user_id)
+        .await?
+        .ok_or(ErrService::Auth(ErrAuth::UnknownUser))
+}
+
+/// Restricts the routes it layers to admins. Runs inside [`require_auth`].
+pub async fn require_admin(
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ErrService> {
+    if user.role != Role::Admin {
+        return Err(ErrService::Auth(ErrAuth::Forbidden));
+    }
+
+    Ok(next.run(req).await)
+}
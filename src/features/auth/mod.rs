@@ -0,0 +1,5 @@
+pub mod dto;
+pub mod handlers;
+pub mod middleware;
+pub mod routes;
+pub mod service;
@@ -0,0 +1,9 @@
+use axum::{Router, routing::post};
+
+use crate::{
+    app::state::AppState, features::auth::handlers::refresh_token, infra::backend::Backend,
+};
+
+pub fn auth_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new().route("/auth/token", post(refresh_token::<B>))
+}
@@ -1,37 +1,346 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
 use crate::{
-    domain::{Book, BookDate, RoomName, UserName},
+    domain::{
+        Book, BookDate, BookDateRange, BookFilter, BookKey, BookSlot, BookStatus, Cancellation,
+        PageRequest, Recurrence, RoomName, UserID, UserName, parse_time_zone,
+    },
     error::ErrService,
+    features::{book::service::BookRequest, page::page_request},
 };
 
-#[derive(Deserialize)]
+/// Attendee count of a booking when the request doesn't say.
+const DEFAULT_ATTENDEES: i32 = 1;
+
+#[derive(Deserialize, ToSchema)]
 pub struct CreateBookDto {
     pub room_name: String,
-    pub user_name: String,
+    #[schema(example = "24.12.30")]
     pub date: String,
+    #[schema(example = "09:00")]
+    pub start_time: String,
+    #[schema(example = "10:30")]
+    pub end_time: String,
+    pub attendees: Option<i32>,
+    pub recurrence: Option<RecurrenceDto>,
+}
+
+impl CreateBookDto {
+    pub fn request(&self) -> BookRequest<'_> {
+        BookRequest {
+            room: &self.room_name,
+            date: &self.date,
+            start_time: &self.start_time,
+            end_time: &self.end_time,
+            attendees: self.attendees.unwrap_or(DEFAULT_ATTENDEES),
+        }
+    }
 }
 
+/// A row of `POST /book/import.csv`, booked on behalf of `user_name`.
 #[derive(Deserialize)]
+pub struct BookCsvRow {
+    pub room_name: String,
+    pub user_name: String,
+    pub date: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub attendees: Option<i32>,
+}
+
+impl BookCsvRow {
+    pub fn request(&self) -> BookRequest<'_> {
+        BookRequest {
+            room: &self.room_name,
+            date: &self.date,
+            start_time: &self.start_time,
+            end_time: &self.end_time,
+            attendees: self.attendees.unwrap_or(DEFAULT_ATTENDEES),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RecurrenceDto {
+    pub frequency: String,
+    pub interval: Option<u32>,
+    pub until: Option<String>,
+    pub count: Option<u32>,
+    #[serde(default)]
+    pub by_weekday: Vec<String>,
+}
+
+/// Body of the deprecated `POST /book/update`.
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateBookDto {
     pub old_id: i32,
+    #[serde(flatten)]
+    pub book: ReplaceBookDto,
+}
+
+/// Body of `PUT /book/{id}`: the booking's new room, date and slot.
+#[derive(Deserialize, ToSchema)]
+pub struct ReplaceBookDto {
     pub room_name: String,
-    pub user_name: String,
+    #[schema(example = "24.12.30")]
     pub date: String,
+    #[schema(example = "09:00")]
+    pub start_time: String,
+    #[schema(example = "10:30")]
+    pub end_time: String,
+    pub attendees: Option<i32>,
 }
 
-#[derive(Deserialize)]
+impl ReplaceBookDto {
+    pub fn request(&self) -> BookRequest<'_> {
+        BookRequest {
+            room: &self.room_name,
+            date: &self.date,
+            start_time: &self.start_time,
+            end_time: &self.end_time,
+            attendees: self.attendees.unwrap_or(DEFAULT_ATTENDEES),
+        }
+    }
+}
+
+/// Body of `PATCH /book/{id}`: only the fields to change.
+#[derive(Deserialize, ToSchema)]
+pub struct PatchBookDto {
+    pub room_name: Option<String>,
+    pub date: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub attendees: Option<i32>,
+}
+
+impl PatchBookDto {
+    /// Fills the fields left out with those of `current`.
+    pub fn merge(self, current: &Book) -> ReplaceBookDto {
+        ReplaceBookDto {
+            room_name: self
+                .room_name
+                .unwrap_or_else(|| current.room_name.name.clone()),
+            date: self
+                .date
+                .unwrap_or_else(|| current.date.date.format("%d.%m.%y").to_string()),
+            start_time: self
+                .start_time
+                .unwrap_or_else(|| current.slot.start.format("%H:%M").to_string()),
+            end_time: self
+                .end_time
+                .unwrap_or_else(|| current.slot.end.format("%H:%M").to_string()),
+            attendees: Some(self.attendees.unwrap_or(current.attendees)),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct DeleteBookByIdDto {
     pub id: i32,
+    pub reason: Option<String>,
 }
 
-#[derive(Serialize)]
+/// Query of `DELETE /book/{id}`.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CancelBookQuery {
+    /// Why the booking is cancelled, kept on record.
+    pub reason: Option<String>,
+}
+
+/// Body of `PUT /book/{id}/status`.
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateBookStatusDto {
+    #[schema(example = "no_show")]
+    pub status: String,
+}
+
+/// Body of `POST /book/{id}/reject`.
+#[derive(Deserialize, ToSchema)]
+pub struct RejectBookDto {
+    /// Why the request is turned down, kept on record.
+    pub reason: Option<String>,
+}
+
+/// Targets a whole series, or only the occurrences on and after `from_date` when given.
+#[derive(Deserialize, ToSchema)]
+pub struct DeleteSeriesDto {
+    pub series_id: Uuid,
+    pub from_date: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateSeriesDto {
+    pub series_id: Uuid,
+    pub from_date: Option<String>,
+    pub room_name: String,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+/// Query of `GET /book`: paging, plus optional `room`, `user`, `from`/`to` date and
+/// `status` filters. Cancelled bookings are left out unless `status=cancelled` or
+/// `include_cancelled=true`.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListBookQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub order: Option<String>,
+    pub room: Option<String>,
+    pub user: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub status: Option<String>,
+    pub include_cancelled: Option<bool>,
+}
+
+impl ListBookQuery {
+    pub fn filter(&self) -> Result<BookFilter, ErrService> {
+        book_filter(
+            self.room.as_deref(),
+            self.user.as_deref(),
+            self.from.as_deref(),
+            self.to.as_deref(),
+            self.status.as_deref(),
+            self.include_cancelled,
+        )
+    }
+
+    pub fn page(&self) -> Result<PageRequest<BookKey>, ErrService> {
+        page_request(
+            self.limit,
+            self.cursor.as_deref(),
+            self.order.as_deref(),
+            parse_book_key,
+        )
+    }
+}
+
+/// Query of `GET /book/export.csv`: the filters of `GET /book`, without paging.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportBookQuery {
+    pub room: Option<String>,
+    pub user: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub status: Option<String>,
+    pub include_cancelled: Option<bool>,
+}
+
+impl ExportBookQuery {
+    pub fn filter(&self) -> Result<BookFilter, ErrService> {
+        book_filter(
+            self.room.as_deref(),
+            self.user.as_deref(),
+            self.from.as_deref(),
+            self.to.as_deref(),
+            self.status.as_deref(),
+            self.include_cancelled,
+        )
+    }
+}
+
+fn book_filter(
+    room: Option<&str>,
+    user: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+    status: Option<&str>,
+    include_cancelled: Option<bool>,
+) -> Result<BookFilter, ErrService> {
+    let filter = BookFilter {
+        room: room.map(RoomName::new).transpose()?,
+        user: user.map(UserName::new).transpose()?,
+        from: from.map(BookDate::new).transpose()?,
+        to: to.map(BookDate::new).transpose()?,
+        status: status.map(BookStatus::new).transpose()?,
+        include_cancelled: include_cancelled.unwrap_or(false),
+    };
+    if let (Some(from), Some(to)) = (&filter.from, &filter.to) {
+        BookDateRange::new(from.clone(), to.clone())?;
+    }
+
+    Ok(filter)
+}
+
+/// Renders a booking's sort key for its listing cursor.
+pub fn book_key(book: &Book) -> String {
+    let key = BookKey::from(book);
+    format!("{}|{}|{}", key.date, key.start, key.id)
+}
+
+fn parse_book_key(key: &str) -> Option<BookKey> {
+    let mut parts = key.split('|');
+    let key = BookKey {
+        date: parts.next()?.parse().ok()?,
+        start: parts.next()?.parse().ok()?,
+        id: parts.next()?.parse().ok()?,
+    };
+
+    parts.next().is_none().then_some(key)
+}
+
+/// `date`, `start_time` and `end_time` are wall-clock times in the room's `time_zone`;
+/// `starts_at` and `ends_at` are the same instants with that zone's offset.
+#[derive(Serialize, ToSchema)]
 pub struct BookDto {
     pub id: i32,
     pub room_name: String,
     pub user_name: String,
     pub date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    #[schema(example = "Europe/Paris")]
+    pub time_zone: String,
+    pub starts_at: DateTime<FixedOffset>,
+    pub ends_at: DateTime<FixedOffset>,
+    pub attendees: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<Uuid>,
+    #[schema(example = "confirmed")]
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancellation: Option<CancellationDto>,
+}
+
+/// A line of `GET /book/export.csv`.
+#[derive(Serialize)]
+pub struct BookCsvDto {
+    pub id: i32,
+    pub room_name: String,
+    pub user_name: String,
+    pub date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    /// The room's zone, on whose wall clocks `date`, `start_time` and `end_time` are.
+    pub time_zone: &'static str,
+    /// Length of the slot between its instants, for summing booked time.
+    pub minutes: i64,
+    pub attendees: i32,
+    pub status: &'static str,
+    pub series_id: Option<Uuid>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub cancel_reason: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CancellationDto {
+    pub cancelled_at: DateTime<Utc>,
+    /// Null once that user was deleted.
+    pub cancelled_by: Option<Uuid>,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BookSeriesDto {
+    pub series_id: Uuid,
+    pub occurrences: Vec<BookDto>,
 }
 
 // #[derive(Debug, sqlx::FromRow)]
@@ -39,44 +348,144 @@ pub struct BookDto {
 
 pub struct BookRowDto {
     pub id: i32,
+    pub room_id: i32,
+    pub user_id: Uuid,
     pub room_name: String,
     pub user_name: String,
+    pub time_zone: String,
     pub date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub attendees: i32,
+    pub series_id: Option<Uuid>,
+    pub status: String,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub cancelled_by: Option<Uuid>,
+    pub cancel_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
 }
 
-impl TryFrom<CreateBookDto> for Book {
+impl TryFrom<BookRowDto> for Book {
     type Error = ErrService;
 
-    fn try_from(dto: CreateBookDto) -> Result<Self, Self::Error> {
+    fn try_from(dto: BookRowDto) -> Result<Self, Self::Error> {
         Ok(Book {
-            id: 0,
+            id: dto.id,
+            room_id: dto.room_id,
+            user_id: UserID { id: dto.user_id },
             room_name: RoomName::new(&dto.room_name)?,
             user_name: UserName::new(&dto.user_name)?,
-            date: BookDate::new(&dto.date)?,
+            time_zone: parse_time_zone(&dto.time_zone)?,
+            date: BookDate::from_naive(dto.date)?,
+            slot: BookSlot::from_naive(dto.start_time, dto.end_time)?,
+            starts_at: dto.starts_at,
+            ends_at: dto.ends_at,
+            attendees: dto.attendees,
+            series_id: dto.series_id,
+            status: BookStatus::new(&dto.status)?,
+            cancellation: dto.cancelled_at.map(|at| Cancellation {
+                at,
+                by: dto.cancelled_by.map(|id| UserID { id }),
+                reason: dto.cancel_reason,
+            }),
+            created_at: dto.created_at,
         })
     }
 }
 
-impl TryFrom<BookRowDto> for Book {
+impl TryFrom<RecurrenceDto> for Recurrence {
     type Error = ErrService;
 
-    fn try_from(dto: BookRowDto) -> Result<Self, Self::Error> {
-        Ok(Book {
-            id: dto.id,
-            room_name: RoomName::new(&dto.room_name)?,
-            user_name: UserName::new(&dto.user_name)?,
-            date: BookDate::from_naive(dto.date)?,
-        })
+    fn try_from(dto: RecurrenceDto) -> Result<Self, Self::Error> {
+        Ok(Recurrence::new(
+            &dto.frequency,
+            dto.interval,
+            dto.until.as_deref(),
+            dto.count,
+            &dto.by_weekday,
+        )?)
+    }
+}
+
+impl From<Book> for BookCsvDto {
+    fn from(book: Book) -> Self {
+        let (cancelled_at, cancel_reason) = match book.cancellation {
+            Some(c) => (Some(c.at), c.reason),
+            None => (None, None),
+        };
+
+        BookCsvDto {
+            id: book.id,
+            room_name: book.room_name.name,
+            user_name: book.user_name.name,
+            date: book.date.date,
+            start_time: book.slot.start,
+            end_time: book.slot.end,
+            time_zone: book.time_zone.name(),
+            minutes: (book.ends_at - book.starts_at).num_minutes(),
+            attendees: book.attendees,
+            status: book.status.as_str(),
+            series_id: book.series_id,
+            cancelled_at,
+            cancel_reason,
+        }
     }
 }
 
 impl From<Book> for BookDto {
     fn from(book: Book) -> Self {
+        let in_zone = |at: DateTime<Utc>| at.with_timezone(&book.time_zone).fixed_offset();
+
         BookDto {
             id: book.id,
+            starts_at: in_zone(book.starts_at),
+            ends_at: in_zone(book.ends_at),
             room_name: book.room_name.name,
             user_name: book.user_name.name,
+            time_zone: book.time_zone.name().to_string(),
             date: book.date.date,
+            start_time: book.slot.start,
+            end_time: book.slot.end,
+            attendees: book.attendees,
+            series_id: book.series_id,
+            status: book.status.as_str(),
+            cancellation: book.cancellation.map(|c| CancellationDto {
+                cancelled_at: c.at,
+                cancelled_by: c.by.map(|u| u.id),
+                reason: c.reason,
+            }),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::domain::{Room, User};
+
+    #[test]
+    fn exported_lengths_are_told_on_the_instants() {
+        let room = Room::new("board", 12)
+            .unwrap()
+            .with_time_zone(Tz::Europe__Paris);
+        let user = User::new("jordan").unwrap();
+        // clocks go forward from 02:00 to 03:00 that night
+        let book = Book::new(
+            &room,
+            &user,
+            BookDate::new("28.03.27").unwrap(),
+            BookSlot::new("01:00", "04:00").unwrap(),
+            1,
+            Utc::now(),
+        );
+
+        let line = BookCsvDto::from(book);
+        assert_eq!(line.time_zone, "Europe/Paris");
+        assert_eq!(line.minutes, 120);
+    }
+}
@@ -1,4 +1,12 @@
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{
+        StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::Mutex;
@@ -6,103 +14,515 @@ use tracing::info;
 
 use crate::{
     app::state::AppState,
-    error::ErrService,
-    features::book::{
-        dto::{BookDto, CreateBookDto},
-        service::BookService,
+    domain::{Book, Recurrence},
+    error::{ErrAuth, ErrService, ProblemDto},
+    features::{
+        auth::middleware::AuthUser,
+        book::{
+            dto::{BookDto, BookSeriesDto, CreateBookDto, ListBookQuery, book_key},
+            service::BookService,
+        },
+        csv::{CsvImportQuery, CsvImportReportDto, TEXT_CSV, write_rows},
+        page::PageDto,
     },
+    infra::backend::Backend,
 };
 
-use super::dto::{DeleteBookByIdDto, UpdateBookDto};
+use super::dto::{
+    BookCsvDto, BookCsvRow, CancelBookQuery, DeleteBookByIdDto, DeleteSeriesDto, ExportBookQuery,
+    PatchBookDto, RejectBookDto, ReplaceBookDto, UpdateBookDto, UpdateBookStatusDto,
+    UpdateSeriesDto,
+};
 
 pub type SharedBookService<T> = Arc<Mutex<BookService<T>>>;
 
-pub async fn create_booking(
-    State(state): State<AppState>,
-    Json(payload): Json<CreateBookDto>,
-) -> Result<impl IntoResponse, ErrService> {
+#[utoipa::path(
+    post,
+    path = "/book",
+    tag = "book",
+    request_body = CreateBookDto,
+    responses(
+        (status = 200, description = "The booking, or the series when `recurrence` is set", body = BookDto),
+        (status = 400, description = "Malformed date or time", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room already booked, or held by a pending request, for this slot", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Past date, bad slot, recurrence or attendees", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_booking<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(mut payload): Json<CreateBookDto>,
+) -> Result<Response, ErrService> {
     let t0 = Instant::now();
     let service = state.book_service;
     let elapsed_lock = t0.elapsed();
     info!("Lock BookService duration {:?}", elapsed_lock);
 
+    if let Some(recurrence) = payload.recurrence.take() {
+        let recurrence = Recurrence::try_from(recurrence)?;
+        let (series_id, books) = service
+            .book_series(&user, payload.request(), &recurrence)
+            .await?;
+
+        let series_dto = BookSeriesDto {
+            series_id,
+            occurrences: books.into_iter().map(BookDto::from).collect(),
+        };
+        return Ok(Json(series_dto).into_response());
+    }
+
     let t1 = Instant::now();
-    let dto = service
-        .book_room(&payload.room_name, &payload.user_name, &payload.date)
-        .await?;
+    let dto = service.book_room(&user, payload.request()).await?;
 
-    let book_dto = BookDto {
-        id: dto.id,
-        room_name: dto.room_name.name,
-        user_name: dto.user_name.name,
-        date: dto.date.date,
-    };
+    let book_dto = BookDto::from(dto);
     let elapsed_inside = t1.elapsed();
     info!("BookService dto + book dto duration: {:?}", elapsed_inside);
-    Ok(Json(book_dto))
+    Ok(Json(book_dto).into_response())
 }
 
-pub async fn update_book(
-    State(state): State<AppState>,
+#[utoipa::path(
+    post,
+    path = "/book/update",
+    tag = "book",
+    request_body = UpdateBookDto,
+    responses(
+        (status = 200, description = "The updated booking", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking or room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room already booked, or held by a pending request, for this slot", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn update_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
     Json(payload): Json<UpdateBookDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.book_service;
 
     let dto = service
-        .update_book_by_id(
-            payload.old_id,
-            &payload.room_name,
-            &payload.user_name,
-            &payload.date,
-        )
+        .update_book_by_id(&user, payload.old_id, payload.book.request())
         .await?;
 
-    let book_dto = BookDto {
-        id: dto.id,
-        room_name: dto.room_name.name,
-        user_name: dto.user_name.name,
-        date: dto.date.date,
-    };
+    let book_dto = BookDto::from(dto);
 
     Ok(Json(book_dto))
 }
 
-pub async fn list_book(State(state): State<AppState>) -> Result<impl IntoResponse, ErrService> {
+#[utoipa::path(
+    get,
+    path = "/book/{id}",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id")),
+    responses(
+        (status = 200, description = "The booking", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    let book = state.book_service.get_book(id)?;
+
+    Ok(Json(BookDto::from(book)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/book/{id}",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id")),
+    request_body = ReplaceBookDto,
+    responses(
+        (status = 200, description = "The updated booking", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking or room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room already booked, or held by a pending request, for this slot", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Past date, bad slot or attendees", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn put_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+    Json(payload): Json<ReplaceBookDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let book = state
+        .book_service
+        .update_book_by_id(&user, id, payload.request())
+        .await?;
+
+    Ok(Json(BookDto::from(book)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/book/{id}",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id")),
+    request_body = PatchBookDto,
+    responses(
+        (status = 200, description = "The updated booking", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking or room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room already booked, or held by a pending request, for this slot", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Past date, bad slot or attendees", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+    Json(payload): Json<PatchBookDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let service = state.book_service;
+
+    let current = service.get_book(id)?;
+    let replacement = payload.merge(&current);
+    let book = service
+        .update_book_by_id(&user, id, replacement.request())
+        .await?;
+
+    Ok(Json(BookDto::from(book)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/book/{id}",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id"), CancelBookQuery),
+    responses(
+        (status = 204, description = "Booking cancelled, it stays on record"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Booking already cancelled, rejected or expired", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn cancel_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+    Query(query): Query<CancelBookQuery>,
+) -> Result<impl IntoResponse, ErrService> {
+    state
+        .book_service
+        .cancel_book(&user, id, query.reason)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/book/{id}/status",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id")),
+    request_body = UpdateBookStatusDto,
+    responses(
+        (status = 200, description = "The booking with its new status", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Booking cancelled, rejected or expired", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Unknown status, booking still pending, or its day hasn't come", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_book_status<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateBookStatusDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let book = state
+        .book_service
+        .set_book_status(&user, id, &payload.status)
+        .await?;
+
+    Ok(Json(BookDto::from(book)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/book/{id}/approve",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id")),
+    responses(
+        (status = 200, description = "The booking, confirmed", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Booking isn't pending", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn approve_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    let book = state.book_service.approve_book(&user, id).await?;
+
+    Ok(Json(BookDto::from(book)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/book/{id}/reject",
+    tag = "book",
+    params(("id" = i32, Path, description = "Booking id")),
+    request_body = RejectBookDto,
+    responses(
+        (status = 200, description = "The booking, rejected", body = BookDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Booking isn't pending", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn reject_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+    Json(payload): Json<RejectBookDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let book = state
+        .book_service
+        .reject_book(&user, id, payload.reason)
+        .await?;
+
+    Ok(Json(BookDto::from(book)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/book",
+    tag = "book",
+    params(ListBookQuery),
+    responses(
+        (status = 200, description = "One page of bookings", body = PageDto<BookDto>),
+        (status = 400, description = "Invalid filter, cursor or paging", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<ListBookQuery>,
+) -> Result<impl IntoResponse, ErrService> {
     let service = state.book_service;
-    let books = service.list_book().await.map_err(|e| {
-        eprintln!("List book error: {:?}", e);
-        e
-    })?;
-    let dto: Vec<BookDto> = books
-        .into_iter()
-        .map(|u| BookDto {
-            id: u.id,
-            user_name: u.user_name.name,
-            room_name: u.room_name.name,
-            date: u.date.date,
-        })
-        .collect();
+    let books = service
+        .list_book(&query.filter()?, &query.page()?)
+        .await
+        .map_err(|e| {
+            eprintln!("List book error: {:?}", e);
+            e
+        })?;
+    let dto: PageDto<BookDto> = PageDto::from_page(books, book_key);
 
     Ok(Json(dto))
 }
 
-pub async fn delete_book(
-    State(state): State<AppState>,
+#[utoipa::path(
+    get,
+    path = "/book/export.csv",
+    tag = "book",
+    params(ExportBookQuery),
+    responses(
+        (status = 200, description = "The bookings matching the filters, soonest first", body = String, content_type = "text/csv"),
+        (status = 400, description = "Invalid filter", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn export_books<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<ExportBookQuery>,
+) -> Result<impl IntoResponse, ErrService> {
+    let books = state.book_service.cached_books(&query.filter()?);
+    let csv = write_rows(books.into_iter().map(BookCsvDto::from))?;
+
+    Ok((
+        [
+            (CONTENT_TYPE, TEXT_CSV),
+            (CONTENT_DISPOSITION, "attachment; filename=\"bookings.csv\""),
+        ],
+        csv,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/book/import.csv",
+    tag = "book",
+    params(CsvImportQuery),
+    request_body(content = String, content_type = "text/csv", description = "A header line, then one booking per line: `room_name,user_name,date,start_time,end_time,attendees`"),
+    responses(
+        (status = 200, description = "Every booking imported, or importable on a dry run", body = CsvImportReportDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "A slot was booked meanwhile; nothing was imported", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Rows in error; nothing was imported", body = CsvImportReportDto),
+    )
+)]
+pub async fn import_books<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<CsvImportQuery>,
+    body: String,
+) -> Result<impl IntoResponse, ErrService> {
+    if !user.role.manages_all_bookings() {
+        return Err(ErrService::Auth(ErrAuth::Forbidden));
+    }
+    let service = state.book_service;
+
+    let mut report = CsvImportReportDto::new(&query);
+    let mut books: Vec<Book> = Vec::new();
+    for (line, row) in report.read_rows::<BookCsvRow>(&body) {
+        match service
+            .prepare_import(&user, &row.user_name, row.request(), &books)
+            .await
+        {
+            Ok(book) => books.push(book),
+            Err(err) => report.reject(line, err),
+        }
+    }
+
+    if report.is_writable() {
+        report.imported = service.import_books(books).await?.len();
+    }
+
+    Ok(report)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/book",
+    tag = "book",
+    request_body = DeleteBookByIdDto,
+    responses(
+        (status = 200, description = "Booking cancelled"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown booking", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Booking already cancelled, rejected or expired", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn delete_book<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
     Json(payload): Json<DeleteBookByIdDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.book_service;
 
-    service.delete_book_by_id(payload.id).await?;
+    service
+        .cancel_book(&user, payload.id, payload.reason)
+        .await?;
 
     Ok(())
 }
 
-pub async fn delete_all_books(
-    State(state): State<AppState>,
+#[utoipa::path(
+    delete,
+    path = "/book/delete_all",
+    tag = "book",
+    params(CancelBookQuery),
+    responses(
+        (status = 204, description = "Every live booking cancelled, they stay on record and the waitlist takes their slots"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "No live booking to cancel", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_all_books<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<CancelBookQuery>,
+) -> Result<impl IntoResponse, ErrService> {
+    state
+        .book_service
+        .cancel_all_books(&user, query.reason)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/book/series/update",
+    tag = "book",
+    request_body = UpdateSeriesDto,
+    responses(
+        (status = 200, description = "The series' updated occurrences", body = BookSeriesDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown series or room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room already booked, or held by a pending request, for one of the occurrences", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_series<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(payload): Json<UpdateSeriesDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.book_service;
 
-    service.delete_all_book().await?;
+    let books = service
+        .update_series(
+            &user,
+            payload.series_id,
+            payload.from_date.as_deref(),
+            &payload.room_name,
+            &payload.start_time,
+            &payload.end_time,
+        )
+        .await?;
+
+    let series_dto = BookSeriesDto {
+        series_id: payload.series_id,
+        occurrences: books.into_iter().map(BookDto::from).collect(),
+    };
 
-    Ok(())
+    Ok(Json(series_dto))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/book/series",
+    tag = "book",
+    request_body = DeleteSeriesDto,
+    responses(
+        (status = 200, description = "The occurrences just cancelled", body = BookSeriesDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the booker nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown series", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_series<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(payload): Json<DeleteSeriesDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let service = state.book_service;
+
+    let books = service
+        .cancel_series(
+            &user,
+            payload.series_id,
+            payload.from_date.as_deref(),
+            payload.reason,
+        )
+        .await?;
+
+    let series_dto = BookSeriesDto {
+        series_id: payload.series_id,
+        occurrences: books.into_iter().map(BookDto::from).collect(),
+    };
+
+    Ok(Json(series_dto))
 }
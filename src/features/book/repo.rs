@@ -1,43 +1,115 @@
 use crate::{
-    domain::{Book, BookDate, RoomName, UserName},
+    domain::{
+        Book, BookDateRange, BookFilter, BookKey, BookSlot, BookStatus, Cancellation, Page,
+        PageRequest, Room,
+    },
     error::{ErrBook, ErrRepo, ErrService, ErrType},
-    features::book::dto::BookRowDto,
-    infra::db::DBClient,
+    features::{book::dto::BookRowDto, room::dto::RoomRowDto},
+    infra::db::{DBClient, is_exclusion_violation, keyset},
 };
 
 use async_trait::async_trait;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use uuid::Uuid;
+
+/// Book columns with the room and user names joined in, over a `b` relation.
+const SELECT_BOOK: &str = "SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM books b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+const INSERT_BOOK: &str = "WITH b AS (INSERT INTO books (room_id, user_id, date, start_time, end_time, attendees, series_id, status, starts_at, ends_at, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING *) SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+const CANCEL_BOOK: &str = "WITH b AS (UPDATE books SET status = 'cancelled', cancelled_at = $2, cancelled_by = $3, cancel_reason = $4 WHERE id = $1 AND status NOT IN ('cancelled', 'rejected', 'expired') RETURNING *) SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+const SET_BOOK_STATUS: &str = "WITH b AS (UPDATE books SET status = $2 WHERE id = $1 AND status NOT IN ('cancelled', 'rejected', 'expired') RETURNING *) SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+const RESOLVE_PENDING_BOOK: &str = "WITH b AS (UPDATE books SET status = $2, cancelled_at = $3, cancelled_by = $4, cancel_reason = $5 WHERE id = $1 AND status = 'pending' RETURNING *) SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+const UPDATE_BOOK: &str = "WITH b AS (UPDATE books SET room_id = $2, user_id = $3, date = $4, start_time = $5, end_time = $6, attendees = $7, status = $8, starts_at = $9, ends_at = $10 WHERE id = $1 RETURNING *) SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+/// Overlapping bookings are rejected by the `books_no_overlap` constraint, so a
+/// conflict missed by the service's cache (another instance, a racing request)
+/// still surfaces as `AlreadyBooked`.
+fn map_write_err(err: sqlx::Error) -> ErrService {
+    if is_exclusion_violation(&err) {
+        ErrService::Book(ErrBook::AlreadyBooked)
+    } else {
+        ErrService::Repo(ErrRepo::BadRequest)
+    }
+}
 
 #[async_trait]
 pub trait BookRepo {
     async fn insert_book(&self, book: &Book) -> Result<Book, ErrService>;
     async fn update_book(&self, book: &Book) -> Result<Book, ErrService>;
     async fn get_all_books(&self) -> Result<Vec<Book>, ErrService>;
-    async fn delete_book_by_id(&self, id: i32) -> Result<bool, ErrService>;
-    async fn delete_all_book(&self) -> Result<bool, ErrService>;
+    async fn list_books(
+        &self,
+        filter: &BookFilter,
+        page: &PageRequest<BookKey>,
+    ) -> Result<Page<Book>, ErrService>;
+    /// Marks a booking cancelled, keeping it on record.
+    async fn cancel_book(&self, id: i32, cancellation: &Cancellation) -> Result<Book, ErrService>;
+    async fn set_book_status(&self, id: i32, status: BookStatus) -> Result<Book, ErrService>;
+    /// Settles a pending booking: `confirmed`, or `rejected` / `expired` with who decided,
+    /// when and why.
+    async fn resolve_pending_book(
+        &self,
+        id: i32,
+        status: BookStatus,
+        resolution: Option<&Cancellation>,
+    ) -> Result<Book, ErrService>;
+    /// Cancels every live booking, keeping them on record.
+    async fn cancel_all_books(&self, cancellation: &Cancellation) -> Result<Vec<Book>, ErrService>;
     async fn get_one_book(&self, book: &Book) -> Result<Option<Book>, ErrService>;
+    async fn find_book(&self, id: i32) -> Result<Option<Book>, ErrService>;
+    /// Deletes the bookings dated before `date`, whatever their status; returns how many.
+    async fn purge_books_before(&self, date: NaiveDate) -> Result<u64, ErrService>;
+    /// Inserts every book or none of them.
+    async fn insert_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService>;
+    /// Updates every book or none of them.
+    async fn update_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService>;
+    /// Cancels the occurrences of a series, only those dated `from` onward when given.
+    async fn cancel_series(
+        &self,
+        series_id: Uuid,
+        from: Option<NaiveDate>,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<Book>, ErrService>;
+    /// Whether a live booking holds room `room_id` between `starts_at` and `ends_at`.
     async fn is_room_already_booked(
         &self,
-        room: &str,
-        date: &NaiveDate,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
     ) -> Result<bool, ErrService>;
+    /// Rooms without any live booking in `range`, or without one overlapping `slot` when given.
+    async fn get_available_rooms(
+        &self,
+        range: &BookDateRange,
+        slot: Option<&BookSlot>,
+    ) -> Result<Vec<Room>, ErrService>;
 }
 
 #[async_trait]
 impl BookRepo for DBClient {
     async fn insert_book(&self, book: &Book) -> Result<Book, ErrService> {
-        let row = sqlx::query_as::<_, BookRowDto>(
-                "INSERT INTO books (room_name, user_name, date) VALUES ($1, $2, $3) RETURNING id, room_name, user_name, date"
-                )
-                .bind(&book.room_name.name)
-                .bind(&book.user_name.name)
-                .bind(book.date.date)
-                .fetch_one(&self.pool)
-                .await
-                .map_err(|e: sqlx::Error| {
-                        eprintln!("Insert error: {:?}", e);
-                        ErrService::Repo(ErrRepo::BadRequest)
-                     })?;
+        let row = sqlx::query_as::<_, BookRowDto>(INSERT_BOOK)
+            .bind(book.room_id)
+            .bind(book.user_id.id)
+            .bind(book.date.date)
+            .bind(book.slot.start)
+            .bind(book.slot.end)
+            .bind(book.attendees)
+            .bind(book.series_id)
+            .bind(book.status.as_str())
+            .bind(book.starts_at)
+            .bind(book.ends_at)
+            .bind(book.created_at)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e: sqlx::Error| {
+                eprintln!("Insert error: {:?}", e);
+                map_write_err(e)
+            })?;
 
         let book: Book = match row.try_into() {
             Ok(book) => book,
@@ -48,35 +120,29 @@ impl BookRepo for DBClient {
     }
 
     async fn update_book(&self, book: &Book) -> Result<Book, ErrService> {
-        let row = sqlx::query_as::<_, BookRowDto>(
-            "UPDATE books SET room_name = $2, user_name = $3, date = $4 WHERE id = $1  RETURNING id, room_name, user_name, date",
-            )
+        let row = sqlx::query_as::<_, BookRowDto>(UPDATE_BOOK)
             .bind(book.id)
-            .bind(&book.room_name.name)
-            .bind(&book.user_name.name)
+            .bind(book.room_id)
+            .bind(book.user_id.id)
             .bind(book.date.date)
+            .bind(book.slot.start)
+            .bind(book.slot.end)
+            .bind(book.attendees)
+            .bind(book.status.as_str())
+            .bind(book.starts_at)
+            .bind(book.ends_at)
             .fetch_one(&self.pool)
             .await
-            .map_err(|_e| ErrRepo::BadRequest)?;
+            .map_err(map_write_err)?;
 
-        // let book: Book = row.try_into()?;
-        let book: Book = Book {
-            id: book.id,
-            room_name: RoomName {
-                name: row.room_name,
-            },
-            user_name: UserName {
-                name: row.user_name,
-            },
-            date: BookDate { date: row.date },
-        };
+        let book: Book = row.try_into().map_err(|_| ErrType::RawConversionFailed)?;
         Ok(book)
     }
 
     async fn get_all_books(&self) -> Result<Vec<Book>, ErrService> {
-        let rows = sqlx::query_as::<_, BookRowDto>(
-            "SELECT id, room_name, user_name, date FROM books ORDER BY date",
-        )
+        let rows = sqlx::query_as::<_, BookRowDto>(&format!(
+            "{SELECT_BOOK} ORDER BY b.date, b.start_time"
+        ))
         .fetch_all(&self.pool)
         .await
         .map_err(|_e| ErrType::RawConversionFailed)?;
@@ -89,71 +155,261 @@ impl BookRepo for DBClient {
         Ok(books)
     }
 
+    async fn list_books(
+        &self,
+        filter: &BookFilter,
+        page: &PageRequest<BookKey>,
+    ) -> Result<Page<Book>, ErrService> {
+        let (cmp, dir) = keyset(page.order);
+        let rows = sqlx::query_as::<_, BookRowDto>(&format!(
+            "{SELECT_BOOK} WHERE ($1::text IS NULL OR r.room_name = $1) \
+            AND ($2::text IS NULL OR u.user_name = $2) \
+            AND ($3::date IS NULL OR b.date >= $3) \
+            AND ($4::date IS NULL OR b.date <= $4) \
+            AND ($5::date IS NULL OR (b.date, b.start_time, b.id) {cmp} ($5, $6, $7)) \
+            AND (b.status = $9 OR ($9::text IS NULL AND ($10 OR b.status NOT IN ('cancelled', 'rejected', 'expired')))) \
+            ORDER BY b.date {dir}, b.start_time {dir}, b.id {dir} LIMIT $8"
+        ))
+        .bind(filter.room.as_ref().map(|r| &r.name))
+        .bind(filter.user.as_ref().map(|u| &u.name))
+        .bind(filter.from.as_ref().map(|d| d.date))
+        .bind(filter.to.as_ref().map(|d| d.date))
+        .bind(page.after.as_ref().map(|k| k.date))
+        .bind(page.after.as_ref().map(|k| k.start))
+        .bind(page.after.as_ref().map(|k| k.id))
+        .bind(i64::from(page.limit) + 1)
+        .bind(filter.status.map(|s| s.as_str()))
+        .bind(filter.include_cancelled)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let books: Vec<Book> = rows
+            .into_iter()
+            .map(|dto| dto.try_into().map_err(|_| ErrBook::UnableToRead))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Page::from_overfetched(books, page.limit))
+    }
+
     async fn get_one_book(&self, book: &Book) -> Result<Option<Book>, ErrService> {
-        let row = sqlx::query_as::<_, BookRowDto>("SELECT * FROM books WHERE id = $1")
-            .bind(book.id)
+        self.find_book(book.id).await
+    }
+
+    async fn find_book(&self, id: i32) -> Result<Option<Book>, ErrService> {
+        let row = sqlx::query_as::<_, BookRowDto>(&format!("{SELECT_BOOK} WHERE b.id = $1"))
+            .bind(id)
             .fetch_optional(&self.pool)
             .await
             .map_err(|_| ErrRepo::BadRequest)?;
 
-        if let Some(row_book) = row {
-            let book = Book {
-                id: row_book.id,
-                room_name: RoomName {
-                    name: (row_book.room_name),
-                },
-                user_name: UserName {
-                    name: (row_book.user_name),
-                },
-                date: BookDate {
-                    date: (row_book.date),
-                },
-            };
-            Ok(Some(book))
-        } else {
-            Ok(None)
+        match row {
+            Some(row) => Ok(Some(Book::try_from(row)?)),
+            None => Ok(None),
         }
     }
 
-    async fn delete_book_by_id(&self, id: i32) -> Result<bool, ErrService> {
-        let result = sqlx::query("DELETE FROM books WHERE id = $1")
-            .bind(id)
+    async fn purge_books_before(&self, date: NaiveDate) -> Result<u64, ErrService> {
+        let result = sqlx::query("DELETE FROM books WHERE date < $1")
+            .bind(date)
             .execute(&self.pool)
             .await
             .map_err(|_e| ErrRepo::BadRequest)?;
 
-        if result.rows_affected() == 0 {
-            Err(ErrService::Book(ErrBook::InvalidID))
-        } else {
-            Ok(true)
-        }
+        Ok(result.rows_affected())
     }
 
-    async fn delete_all_book(&self) -> Result<bool, ErrService> {
-        let result = sqlx::query("DELETE FROM books")
-            .execute(&self.pool)
+    async fn cancel_book(&self, id: i32, cancellation: &Cancellation) -> Result<Book, ErrService> {
+        let row = sqlx::query_as::<_, BookRowDto>(CANCEL_BOOK)
+            .bind(id)
+            .bind(cancellation.at)
+            .bind(cancellation.by.as_ref().map(|u| u.id))
+            .bind(cancellation.reason.as_deref())
+            .fetch_optional(&self.pool)
             .await
-            .map_err(|_e| ErrRepo::BadRequest)?;
+            .map_err(|_e| ErrRepo::BadRequest)?
+            .ok_or(ErrBook::Cancelled)?;
+
+        Ok(Book::try_from(row)?)
+    }
+
+    async fn set_book_status(&self, id: i32, status: BookStatus) -> Result<Book, ErrService> {
+        let row = sqlx::query_as::<_, BookRowDto>(SET_BOOK_STATUS)
+            .bind(id)
+            .bind(status.as_str())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?
+            .ok_or(ErrBook::Cancelled)?;
+
+        Ok(Book::try_from(row)?)
+    }
+
+    async fn resolve_pending_book(
+        &self,
+        id: i32,
+        status: BookStatus,
+        resolution: Option<&Cancellation>,
+    ) -> Result<Book, ErrService> {
+        let row = sqlx::query_as::<_, BookRowDto>(RESOLVE_PENDING_BOOK)
+            .bind(id)
+            .bind(status.as_str())
+            .bind(resolution.map(|r| r.at))
+            .bind(resolution.and_then(|r| r.by.as_ref()).map(|u| u.id))
+            .bind(resolution.and_then(|r| r.reason.as_deref()))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(map_write_err)?
+            .ok_or(ErrBook::NotPending)?;
+
+        Ok(Book::try_from(row)?)
+    }
+
+    async fn cancel_all_books(&self, cancellation: &Cancellation) -> Result<Vec<Book>, ErrService> {
+        let rows = sqlx::query_as::<_, BookRowDto>(
+            "WITH b AS (UPDATE books SET status = 'cancelled', cancelled_at = $1, cancelled_by = $2, cancel_reason = $3 \
+            WHERE status NOT IN ('cancelled', 'rejected', 'expired') RETURNING *) \
+            SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at \
+            FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id",
+        )
+        .bind(cancellation.at)
+        .bind(cancellation.by.as_ref().map(|u| u.id))
+        .bind(cancellation.reason.as_deref())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
 
-        if result.rows_affected() == 0 {
-            Err(ErrService::Repo(ErrRepo::IsEmpty))
-        } else {
-            Ok(true)
+        rows.into_iter().map(Book::try_from).collect()
+    }
+
+    async fn insert_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut inserted = Vec::with_capacity(books.len());
+        for book in books {
+            let row = sqlx::query_as::<_, BookRowDto>(INSERT_BOOK)
+                .bind(book.room_id)
+                .bind(book.user_id.id)
+                .bind(book.date.date)
+                .bind(book.slot.start)
+                .bind(book.slot.end)
+                .bind(book.attendees)
+                .bind(book.series_id)
+                .bind(book.status.as_str())
+                .bind(book.starts_at)
+                .bind(book.ends_at)
+                .bind(book.created_at)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(map_write_err)?;
+
+            inserted.push(Book::try_from(row).map_err(|_| ErrType::RawConversionFailed)?);
         }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(inserted)
+    }
+
+    async fn update_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut updated = Vec::with_capacity(books.len());
+        for book in books {
+            let row = sqlx::query_as::<_, BookRowDto>(UPDATE_BOOK)
+                .bind(book.id)
+                .bind(book.room_id)
+                .bind(book.user_id.id)
+                .bind(book.date.date)
+                .bind(book.slot.start)
+                .bind(book.slot.end)
+                .bind(book.attendees)
+                .bind(book.status.as_str())
+                .bind(book.starts_at)
+                .bind(book.ends_at)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(map_write_err)?;
+
+            updated.push(Book::try_from(row).map_err(|_| ErrType::RawConversionFailed)?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(updated)
+    }
+
+    async fn cancel_series(
+        &self,
+        series_id: Uuid,
+        from: Option<NaiveDate>,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<Book>, ErrService> {
+        let rows = sqlx::query_as::<_, BookRowDto>(
+            "WITH b AS (UPDATE books SET status = 'cancelled', cancelled_at = $3, cancelled_by = $4, cancel_reason = $5 \
+            WHERE series_id = $1 AND ($2::date IS NULL OR date >= $2) AND status NOT IN ('cancelled', 'rejected', 'expired') RETURNING *) \
+            SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at \
+            FROM b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id",
+        )
+        .bind(series_id)
+        .bind(from)
+        .bind(cancellation.at)
+        .bind(cancellation.by.as_ref().map(|u| u.id))
+        .bind(cancellation.reason.as_deref())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let books: Vec<Book> = rows
+            .into_iter()
+            .map(|dto| dto.try_into().map_err(|_| ErrBook::UnableToRead))
+            .collect::<Result<_, _>>()?;
+
+        Ok(books)
     }
 
     async fn is_room_already_booked(
         &self,
-        room: &str,
-        date: &NaiveDate,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
     ) -> Result<bool, ErrService> {
-        let result = sqlx::query("SELECT 1 FROM books WHERE room_name = $1 AND date = $2 LIMIT 1")
-            .bind(room)
-            .bind(date)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|_| ErrService::Repo(ErrRepo::BadRequest))?;
+        let result = sqlx::query(
+            "SELECT 1 FROM books WHERE room_id = $1 AND starts_at < $3 AND ends_at > $2 AND status NOT IN ('cancelled', 'rejected', 'expired') LIMIT 1",
+        )
+        .bind(room_id)
+        .bind(starts_at)
+        .bind(ends_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| ErrService::Repo(ErrRepo::BadRequest))?;
 
         Ok(result.is_some())
     }
+
+    async fn get_available_rooms(
+        &self,
+        range: &BookDateRange,
+        slot: Option<&BookSlot>,
+    ) -> Result<Vec<Room>, ErrService> {
+        let rows = sqlx::query_as::<_, RoomRowDto>(
+            "SELECT r.id, r.room_name, r.capacity, r.floor, r.building, r.equipment, r.requires_approval, r.time_zone FROM rooms r WHERE NOT EXISTS ( \
+                SELECT 1 FROM books b WHERE b.room_id = r.id AND b.status NOT IN ('cancelled', 'rejected', 'expired') \
+                AND b.date BETWEEN $1 AND $2 \
+                AND ($3::time IS NULL OR (b.start_time < $4 AND b.end_time > $3)) \
+            ) ORDER BY r.room_name",
+        )
+        .bind(range.from.date)
+        .bind(range.to.date)
+        .bind(slot.map(|s| s.start))
+        .bind(slot.map(|s| s.end))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let rooms: Vec<Room> = rows
+            .into_iter()
+            .map(|dto| dto.try_into().map_err(|_| ErrRepo::DoesntExist))
+            .collect::<Result<_, _>>()?;
+
+        Ok(rooms)
+    }
 }
@@ -1,20 +1,193 @@
+// The legacy routes are still served, see `LEGACY_ROUTES`.
+#![allow(deprecated)]
+
 use axum::{
     Router,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
 };
 
 use crate::{
     app::state::AppState,
     features::book::handlers::{create_booking, delete_book, list_book},
+    infra::backend::Backend,
+};
+
+use super::handlers::{
+    approve_book, cancel_book, delete_all_books, delete_series, export_books, get_book,
+    import_books, patch_book, put_book, reject_book, update_book, update_book_status,
+    update_series,
 };
 
-use super::handlers::{delete_all_books, update_book};
+pub fn book_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route("/book", post(create_booking::<B>).get(list_book::<B>))
+        .route("/book/export.csv", get(export_books::<B>))
+        .route("/book/import.csv", post(import_books::<B>))
+        .route(
+            "/book/{id}",
+            get(get_book::<B>)
+                .put(put_book::<B>)
+                .patch(patch_book::<B>)
+                .delete(cancel_book::<B>),
+        )
+        .route("/book/{id}/status", put(update_book_status::<B>))
+        .route("/book/{id}/approve", post(approve_book::<B>))
+        .route("/book/{id}/reject", post(reject_book::<B>))
+        .route("/book/series/update", post(update_series::<B>))
+        .route("/book/series", delete(delete_series::<B>))
+}
 
-pub fn book_routes() -> Router<AppState> {
+/// Body-addressed routes superseded by `/book/{id}`, served while `LEGACY_ROUTES` is on.
+pub fn book_legacy_routes<B: Backend>() -> Router<AppState<B>> {
     Router::new()
-        .route("/book", post(create_booking))
-        .route("/book/update", post(update_book))
-        .route("/book", get(list_book))
-        .route("/book", delete(delete_book))
-        .route("/book/delete_all", delete(delete_all_books))
+        .route("/book/update", post(update_book::<B>))
+        .route("/book", delete(delete_book::<B>))
+}
+
+/// Routes reserved to admins.
+pub fn book_admin_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new().route("/book/delete_all", delete(delete_all_books::<B>))
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::app::harness::{TestApp, day};
+
+    #[tokio::test]
+    async fn bookings_are_checked() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let member = app.signup("jordan").await;
+        app.create_room(&admin, "alpha", 8).await;
+        let date = day(1);
+
+        let response = app.book(&member, "alpha", &date, "09:00", "10:00").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["user_name"], "jordan");
+        assert_eq!(response.body["status"], "confirmed");
+        assert_eq!(response.body["start_time"], "09:00:00");
+
+        for (room, date, start, end, status, code) in [
+            (
+                "alpha",
+                date.as_str(),
+                "09:30",
+                "10:30",
+                StatusCode::CONFLICT,
+                "BOOK_ALREADY_BOOKED",
+            ),
+            (
+                "alpha",
+                &day(-1),
+                "09:00",
+                "10:00",
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "BOOK_DATE_PAST",
+            ),
+            (
+                "alpha",
+                "tomorrow",
+                "09:00",
+                "10:00",
+                StatusCode::BAD_REQUEST,
+                "BOOK_INVALID_DATE_FORMAT",
+            ),
+            (
+                "alpha",
+                &date,
+                "9h",
+                "10:00",
+                StatusCode::BAD_REQUEST,
+                "BOOK_INVALID_TIME_FORMAT",
+            ),
+            (
+                "alpha",
+                &date,
+                "11:00",
+                "10:00",
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "BOOK_INVALID_TIME_RANGE",
+            ),
+            (
+                "gamma",
+                &date,
+                "09:00",
+                "10:00",
+                StatusCode::NOT_FOUND,
+                "ROOM_NOT_FOUND",
+            ),
+        ] {
+            let response = app.book(&admin, room, date, start, end).await;
+            assert_eq!((response.status, response.code()), (status, code));
+        }
+
+        let response = app
+            .post(
+                "/book",
+                &member,
+                json!({
+                    "room_name": "alpha",
+                    "date": date,
+                    "start_time": "12:00",
+                    "end_time": "13:00",
+                    "attendees": 9,
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.code(), "BOOK_CAPACITY_EXCEEDED");
+        assert_eq!(response.body["errors"][0]["field"], "attendees");
+    }
+
+    #[tokio::test]
+    async fn bookings_are_changed_by_their_booker() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let member = app.signup("jordan").await;
+        let other = app.signup("camille").await;
+        app.create_room(&admin, "alpha", 8).await;
+        let date = day(1);
+        let id = app
+            .book(&member, "alpha", &date, "09:00", "10:00")
+            .await
+            .body["id"]
+            .as_i64()
+            .unwrap();
+        let uri = format!("/book/{id}");
+
+        let response = app
+            .patch(&uri, &other, json!({ "end_time": "11:00" }))
+            .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(response.code(), "BOOK_NOT_OWNER");
+        let response = app
+            .patch(&uri, &member, json!({ "end_time": "11:00" }))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["end_time"], "11:00:00");
+
+        let response = app.delete(&uri, &other).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        let response = app.delete(&format!("{uri}?reason=Moved"), &member).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+        assert_eq!(response.body, json!(null));
+        let response = app.delete(&uri, &member).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.code(), "BOOK_CANCELLED");
+
+        let response = app.get(&uri, &member).await;
+        assert_eq!(response.body["status"], "cancelled");
+        assert_eq!(response.body["cancellation"]["reason"], "Moved");
+        let response = app.get("/book/999", &member).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.code(), "BOOK_NOT_FOUND");
+
+        let response = app.get("/book", &member).await;
+        assert_eq!(response.body, json!({ "items": [], "next_cursor": null }));
+        let response = app.get("/book?include_cancelled=true", &member).await;
+        assert_eq!(response.body["items"].as_array().unwrap().len(), 1);
+    }
 }
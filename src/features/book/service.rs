@@ -1,19 +1,45 @@
 use dashmap::{self, DashSet};
-use tracing::info;
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
 
 use crate::{
-    domain::{Book, BookDate, RoomName, UserName},
-    error::{ErrBook, ErrDomain, ErrRepo, ErrService},
-    features::{room::repo::RoomRepo, user::repo::UserRepo},
+    domain::{
+        Book, BookDate, BookDateRange, BookFilter, BookKey, BookSlot, BookStatus, Cancellation,
+        Page, PageRequest, Recurrence, Room, RoomName, User, UserID, UserName, WaitlistEntry,
+    },
+    error::{ErrAuth, ErrBook, ErrDomain, ErrRepo, ErrService},
+    features::{room::repo::RoomRepo, user::repo::UserRepo, waitlist::repo::WaitlistRepo},
+    infra::{
+        clock::{Clock, SystemClock},
+        notifier::{LogNotifier, Notification, Notifier},
+    },
 };
 
 use super::repo::BookRepo;
 
-use chrono::Local;
+use chrono::{DateTime, Duration, Utc};
+
+/// Raw booking input as received by the API, validated by [`BookService`].
+/// The booker isn't part of it: it's the authenticated caller.
+pub struct BookRequest<'a> {
+    pub room: &'a str,
+    pub date: &'a str,
+    pub start_time: &'a str,
+    pub end_time: &'a str,
+    pub attendees: i32,
+}
 
 pub struct BookService<T> {
     repo: T,
     cache: DashSet<Book>,
+    cache_ready: AtomicBool,
+    notifier: Arc<dyn Notifier>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T> BookService<T> {
@@ -21,98 +47,398 @@ impl<T> BookService<T> {
         Self {
             repo,
             cache: DashSet::new(),
+            cache_ready: AtomicBool::new(false),
+            notifier: Arc::new(LogNotifier),
+            clock: Arc::new(SystemClock),
         }
     }
+
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
 }
 
 impl<T> BookService<T>
 where
-    T: RoomRepo + UserRepo + BookRepo,
+    T: RoomRepo + UserRepo + BookRepo + WaitlistRepo,
 {
     pub async fn book_room(
         &self,
-        room: &str,
-        user: &str,
-        desired_date: &str,
+        user: &User,
+        request: BookRequest<'_>,
     ) -> Result<Book, ErrService> {
-        let date =
-            BookDate::new(desired_date).map_err(|_| ErrDomain::Book(ErrBook::InvalidDateFormat))?;
-        let room_name = RoomName::new(room)?;
-        let user_name = UserName::new(user)?;
+        let book = self.validate_request(user, &request).await?;
+        self.check_slot_free(&book, None)?;
 
-        if date.date < Local::now().date_naive() {
-            return Err(ErrService::Book(ErrBook::InvalidDate));
+        let inserted_book = self.repo.insert_book(&book).await?;
+        self.cache.insert(inserted_book.clone());
+
+        Ok(inserted_book)
+    }
+
+    /// Books on behalf of `booker`, e.g. from another system's calendar: through the same
+    /// checks as any booking, but confirmed right away. Calendars bring their history
+    /// along, so past bookings are taken too, as `completed` once over. Reserved to those
+    /// managing every booking.
+    pub async fn import_book(
+        &self,
+        caller: &User,
+        booker: &str,
+        request: BookRequest<'_>,
+    ) -> Result<Book, ErrService> {
+        let booker = self.import_booker(caller, booker).await?;
+        let book = self.request_book(&booker, &request).await?;
+        let status = if book.ends_at <= self.clock.now() {
+            BookStatus::Completed
+        } else {
+            BookStatus::Confirmed
+        };
+        let book = self.check_import(Book { status, ..book }, &[])?;
+
+        let inserted_book = self.repo.insert_book(&book).await?;
+        self.cache.insert(inserted_book.clone());
+
+        Ok(inserted_book)
+    }
+
+    /// Checks a booking on behalf of `booker` may be imported along with `pending`, the
+    /// bookings to be imported with it, and returns it unsaved.
+    pub async fn prepare_import(
+        &self,
+        caller: &User,
+        booker: &str,
+        request: BookRequest<'_>,
+        pending: &[Book],
+    ) -> Result<Book, ErrService> {
+        let booker = self.import_booker(caller, booker).await?;
+        let book = Book {
+            status: BookStatus::Confirmed,
+            ..self.validate_request(&booker, &request).await?
+        };
+
+        self.check_import(book, pending)
+    }
+
+    /// The user named `booker`, once `caller` may import bookings.
+    async fn import_booker(&self, caller: &User, booker: &str) -> Result<User, ErrService> {
+        if !caller.role.manages_all_bookings() {
+            return Err(ErrService::Auth(ErrAuth::Forbidden));
         }
 
-        let exist_room = self.repo.get_one_room(&room_name).await.is_ok();
-        if !exist_room {
-            return Err(ErrService::Book(ErrBook::RoomNotFound));
+        self.repo
+            .get_one_user(&UserName::new(booker)?)
+            .await
+            .map_err(|_| ErrService::Book(ErrBook::UserNotFound))
+    }
+
+    /// Checks the imported `book` is neither on record already nor conflicting, with
+    /// `pending` too.
+    fn check_import(&self, book: Book, pending: &[Book]) -> Result<Book, ErrService> {
+        // Imported twice, from overlapping exports or a second run.
+        let same = |b: &Book| {
+            b.is_live()
+                && b.room_id == book.room_id
+                && b.user_id == book.user_id
+                && b.date == book.date
+                && b.slot == book.slot
+        };
+        if self.cache.iter().any(|b| same(&b)) || pending.iter().any(same) {
+            return Err(ErrService::Book(ErrBook::Duplicate));
+        }
+        self.check_slot_free(&book, None)?;
+        if pending
+            .iter()
+            .any(|b| b.conflicts_with(book.room_id, book.starts_at, book.ends_at))
+        {
+            return Err(ErrService::Book(ErrBook::AlreadyBooked));
         }
 
-        let exist_user = self.repo.get_one_user(&user_name).await.is_ok();
-        if !exist_user {
-            return Err(ErrService::Book(ErrBook::UserNotFound));
+        Ok(book)
+    }
+
+    /// Saves bookings checked by [`Self::prepare_import`], every one or none of them.
+    pub async fn import_books(&self, books: Vec<Book>) -> Result<Vec<Book>, ErrService> {
+        for book in &books {
+            self.check_slot_free(book, None)?;
         }
 
-        if self.is_exist_book(&room_name, &date).await? {
-            return Err(ErrService::Book(ErrBook::AlreadyBooked));
+        let books = self.repo.insert_books(&books).await?;
+        for book in &books {
+            self.cache.insert(book.clone());
         }
 
-        let book = Book::new(room, user, date)?;
-        let inserted_book = self.repo.insert_book(&book).await?;
-        self.cache.insert(book.clone());
+        Ok(books)
+    }
 
-        Ok(inserted_book)
+    /// Books every occurrence of `recurrence`, starting on the request's date, as one series.
+    /// Nothing is booked if any occurrence conflicts.
+    pub async fn book_series(
+        &self,
+        user: &User,
+        request: BookRequest<'_>,
+        recurrence: &Recurrence,
+    ) -> Result<(Uuid, Vec<Book>), ErrService> {
+        let first = self.validate_request(user, &request).await?;
+
+        let series_id = Uuid::new_v4();
+        let books: Vec<Book> = recurrence
+            .occurrences(&first.date)?
+            .into_iter()
+            .map(|date| {
+                Book {
+                    date,
+                    series_id: Some(series_id),
+                    ..first.clone()
+                }
+                .with_instants()
+            })
+            .collect();
+
+        for book in &books {
+            self.check_slot_free(book, None)?;
+        }
+
+        let inserted_books = self.repo.insert_books(&books).await?;
+        for book in &inserted_books {
+            self.cache.insert(book.clone());
+        }
+
+        Ok((series_id, inserted_books))
     }
 
-    pub async fn update_book_by_id(
+    /// Moves the upcoming occurrences of a series, only those from `from_date` when given,
+    /// to another room and/or time slot. Past occurrences are left untouched. Moved by a
+    /// member into a room requiring approval, they are pending again.
+    pub async fn update_series(
         &self,
-        old_book_id: i32,
+        caller: &User,
+        series_id: Uuid,
+        from_date: Option<&str>,
         room: &str,
-        user: &str,
-        date: &str,
-    ) -> Result<Book, ErrService> {
-        let room = RoomName::new(room)?;
-        let user = UserName::new(user)?;
-        let date = BookDate::new(date)?;
+        start_time: &str,
+        end_time: &str,
+    ) -> Result<Vec<Book>, ErrService> {
+        self.check_series_owner(caller, series_id)?;
+        let from = from_date.map(BookDate::new).transpose()?;
+        let room_name = RoomName::new(room)?;
+        let slot = BookSlot::new(start_time, end_time)?;
 
-        if !self.is_exist_book_id(&old_book_id).await? {
-            return Err(ErrService::Book(ErrBook::InvalidID));
+        let room = self
+            .repo
+            .get_one_room(&room_name)
+            .await
+            .map_err(|_| ErrService::Book(ErrBook::RoomNotFound))?;
+
+        let moved: Vec<Book> = self
+            .series_occurrences(series_id, from.as_ref())
+            .into_iter()
+            .filter(|b| b.date.date >= self.clock.today_in(b.time_zone))
+            .collect();
+        let books: Vec<Book> = moved
+            .iter()
+            .map(|b| {
+                Book {
+                    room_id: room.id,
+                    room_name: room.room_name.clone(),
+                    time_zone: room.time_zone,
+                    slot: slot.clone(),
+                    status: if room.needs_approval_from(caller) {
+                        BookStatus::Pending
+                    } else {
+                        b.status
+                    },
+                    ..b.clone()
+                }
+                .with_instants()
+            })
+            .collect();
+
+        if books.is_empty() {
+            return Err(ErrService::Book(ErrBook::SeriesNotFound));
         }
 
-        if self.is_exist_book(&room, &date).await? {
-            return Err(ErrService::Book(ErrBook::AlreadyBooked));
+        if books.iter().any(|b| b.attendees > room.capacity.seats) {
+            return Err(ErrService::Book(ErrBook::CapacityExceeded));
+        }
+
+        let conflict = books.iter().find_map(|book| {
+            self.cache
+                .iter()
+                .filter(|b| b.series_id != Some(series_id))
+                .find(|b| b.conflicts_with(book.room_id, book.starts_at, book.ends_at))
+                .map(|b| b.clone())
+        });
+        if let Some(holder) = conflict {
+            return Err(Self::taken_by(&holder));
         }
 
-        if date.date < Local::now().date_naive() {
+        let updated_books = self.repo.update_books(&books).await?;
+        for book in &updated_books {
+            self.cache.retain(|b| b.id != book.id);
+            self.cache.insert(book.clone());
+        }
+        self.promote_waitlist(&moved).await;
+
+        Ok(updated_books)
+    }
+
+    /// Cancels a whole series, or only its occurrences from `from_date` onward.
+    pub async fn cancel_series(
+        &self,
+        caller: &User,
+        series_id: Uuid,
+        from_date: Option<&str>,
+        reason: Option<String>,
+    ) -> Result<Vec<Book>, ErrService> {
+        self.check_series_owner(caller, series_id)?;
+        let from = from_date.map(BookDate::new).transpose()?;
+
+        let cancelled = self
+            .repo
+            .cancel_series(
+                series_id,
+                from.as_ref().map(|f| f.date),
+                &self.cancellation(caller, reason),
+            )
+            .await?;
+        if cancelled.is_empty() {
+            return Err(ErrService::Book(ErrBook::SeriesNotFound));
+        }
+
+        for book in &cancelled {
+            self.cache.retain(|b| b.id != book.id);
+            self.cache.insert(book.clone());
+        }
+        self.promote_waitlist(&cancelled).await;
+
+        Ok(cancelled)
+    }
+
+    /// The live occurrences of a series, only those dated `from` onward when given.
+    fn series_occurrences(&self, series_id: Uuid, from: Option<&BookDate>) -> Vec<Book> {
+        self.cache
+            .iter()
+            .filter(|b| b.series_id == Some(series_id) && b.is_live())
+            .filter(|b| from.is_none_or(|from| b.date.date >= from.date))
+            .map(|b| b.clone())
+            .collect()
+    }
+
+    /// Parses a request into a not yet persisted book of `user`, checking its date isn't
+    /// past, its room exists and can seat its attendees.
+    async fn validate_request(
+        &self,
+        user: &User,
+        request: &BookRequest<'_>,
+    ) -> Result<Book, ErrService> {
+        let book = self.request_book(user, request).await?;
+
+        // Past on the room's wall clock, wherever the server is.
+        if book.date.date < self.clock.today_in(book.time_zone) {
             return Err(ErrService::Book(ErrBook::InvalidDate));
         }
 
-        let existing_users = self.repo.get_one_user(&user).await?;
-        if existing_users.user_name != user {
-            return Err(ErrService::Book(ErrBook::UserNotFound));
+        Ok(book)
+    }
+
+    /// Parses a request into a not yet persisted book of `user`, checking its room exists
+    /// and can seat its attendees, whatever its date.
+    async fn request_book(
+        &self,
+        user: &User,
+        request: &BookRequest<'_>,
+    ) -> Result<Book, ErrService> {
+        let date =
+            BookDate::new(request.date).map_err(|_| ErrDomain::Book(ErrBook::InvalidDateFormat))?;
+        let slot = BookSlot::new(request.start_time, request.end_time)?;
+        let room_name = RoomName::new(request.room)?;
+
+        if request.attendees < 1 {
+            return Err(ErrService::Book(ErrBook::InvalidAttendees));
         }
 
-        let existing_rooms = self.repo.get_one_room(&room).await?;
-        if existing_rooms.room_name != room {
-            return Err(ErrService::Book(ErrBook::RoomNotFound));
+        let room = self
+            .repo
+            .get_one_room(&room_name)
+            .await
+            .map_err(|_| ErrService::Book(ErrBook::RoomNotFound))?;
+
+        if request.attendees > room.capacity.seats {
+            return Err(ErrService::Book(ErrBook::CapacityExceeded));
         }
 
+        Ok(Book::new(
+            &room,
+            user,
+            date,
+            slot,
+            request.attendees,
+            self.clock.now(),
+        ))
+    }
+
+    /// Moves a booking to another room, date or slot. It keeps its booker, series and
+    /// status, unless a member moves it into a room requiring approval: it's pending again.
+    pub async fn update_book_by_id(
+        &self,
+        caller: &User,
+        old_book_id: i32,
+        request: BookRequest<'_>,
+    ) -> Result<Book, ErrService> {
+        let old_book = self.owned_book(caller, old_book_id)?;
+        old_book.check_live()?;
+
+        let book = self.validate_request(caller, &request).await?;
         let book = Book {
             id: old_book_id,
-            room_name: room,
-            user_name: user,
-            date,
+            user_id: old_book.user_id.clone(),
+            user_name: old_book.user_name.clone(),
+            series_id: old_book.series_id,
+            status: if book.status == BookStatus::Pending {
+                BookStatus::Pending
+            } else {
+                old_book.status
+            },
+            created_at: old_book.created_at,
+            ..book
         };
+        self.check_slot_free(&book, Some(old_book_id))?;
 
         let book = self.repo.update_book(&book).await?;
-        self.cache.remove(&book);
+        self.cache.retain(|b| b.id != old_book_id);
         self.cache.insert(book.clone());
+        self.promote_waitlist(&[old_book]).await;
+
         Ok(book)
     }
 
-    pub async fn list_book(&self) -> Result<Vec<Book>, ErrService> {
-        self.repo.get_all_books().await
+    pub async fn list_book(
+        &self,
+        filter: &BookFilter,
+        page: &PageRequest<BookKey>,
+    ) -> Result<Page<Book>, ErrService> {
+        self.repo.list_books(filter, page).await
+    }
+
+    /// Cached bookings matching `filter`, soonest first.
+    pub fn cached_books(&self, filter: &BookFilter) -> Vec<Book> {
+        let mut books: Vec<Book> = self
+            .cache
+            .iter()
+            .filter(|b| filter.matches(b))
+            .map(|b| b.clone())
+            .collect();
+        books.sort_by(|a, b| {
+            (&a.date.date, a.slot.start, a.id).cmp(&(&b.date.date, b.slot.start, b.id))
+        });
+
+        books
     }
 
     pub async fn list_book_by_cache(&self) -> Result<Vec<Book>, ErrService> {
@@ -123,54 +449,1248 @@ where
             .collect::<Vec<Book>>())
     }
 
-    pub async fn delete_book_by_id(&self, book_id: i32) -> Result<(), ErrService> {
-        let deleted = self.repo.delete_book_by_id(book_id).await?;
-        if deleted {
-            self.cache.retain(|x| x.id != book_id);
-            Ok(())
-        } else {
-            Err(ErrService::Repo(ErrRepo::UnableToDelete))
+    /// Cancels a booking: it stays on record, with who cancelled it, when and why,
+    /// but frees its slot.
+    pub async fn cancel_book(
+        &self,
+        caller: &User,
+        book_id: i32,
+        reason: Option<String>,
+    ) -> Result<Book, ErrService> {
+        self.owned_book(caller, book_id)?.check_live()?;
+
+        let book = self
+            .repo
+            .cancel_book(book_id, &self.cancellation(caller, reason))
+            .await?;
+        self.cache.retain(|b| b.id != book_id);
+        self.cache.insert(book.clone());
+        self.promote_waitlist(std::slice::from_ref(&book)).await;
+
+        Ok(book)
+    }
+
+    /// Records how a booking went: `completed` or `no_show` once its day has come, or
+    /// back to `confirmed`. Reserved to those managing every booking.
+    pub async fn set_book_status(
+        &self,
+        caller: &User,
+        book_id: i32,
+        status: &str,
+    ) -> Result<Book, ErrService> {
+        if !caller.role.manages_all_bookings() {
+            return Err(ErrService::Auth(ErrAuth::Forbidden));
+        }
+        let status = BookStatus::new(status)?;
+        let book = self.get_book(book_id)?;
+
+        book.check_live()?;
+        let settled = matches!(status, BookStatus::Completed | BookStatus::NoShow);
+        if !(settled || status == BookStatus::Confirmed)
+            || book.status == BookStatus::Pending
+            || (settled && book.date.date > self.clock.today_in(book.time_zone))
+        {
+            return Err(ErrService::Book(ErrBook::InvalidStatus));
+        }
+
+        let book = self.repo.set_book_status(book_id, status).await?;
+        self.cache.retain(|b| b.id != book_id);
+        self.cache.insert(book.clone());
+
+        Ok(book)
+    }
+
+    /// Confirms a pending booking. Reserved to those managing every booking.
+    pub async fn approve_book(&self, caller: &User, book_id: i32) -> Result<Book, ErrService> {
+        let book = self.pending_book(caller, book_id)?;
+
+        let book = self
+            .repo
+            .resolve_pending_book(book.id, BookStatus::Confirmed, None)
+            .await?;
+        self.cache.retain(|b| b.id != book_id);
+        self.cache.insert(book.clone());
+
+        self.notifier
+            .notify(&book.user_id, Notification::BookingApproved(book.clone()))
+            .await;
+
+        Ok(book)
+    }
+
+    /// Turns a pending booking down, freeing its slot. Reserved to those managing every
+    /// booking.
+    pub async fn reject_book(
+        &self,
+        caller: &User,
+        book_id: i32,
+        reason: Option<String>,
+    ) -> Result<Book, ErrService> {
+        let book = self.pending_book(caller, book_id)?;
+
+        let book = self
+            .repo
+            .resolve_pending_book(
+                book.id,
+                BookStatus::Rejected,
+                Some(&self.cancellation(caller, reason)),
+            )
+            .await?;
+        self.cache.retain(|b| b.id != book_id);
+        self.cache.insert(book.clone());
+
+        self.notifier
+            .notify(&book.user_id, Notification::BookingRejected(book.clone()))
+            .await;
+        self.promote_waitlist(std::slice::from_ref(&book)).await;
+
+        Ok(book)
+    }
+
+    /// Expires the pending bookings left without a decision for `ttl`, or whose start
+    /// has come, freeing their slot.
+    pub async fn expire_pending(&self, ttl: Duration) -> Result<Vec<Book>, ErrService> {
+        let now = self.clock.now();
+        let stale: Vec<Book> = self
+            .cache
+            .iter()
+            .filter(|b| b.status == BookStatus::Pending)
+            .filter(|b| b.created_at + ttl <= now || b.starts_at <= now)
+            .map(|b| b.clone())
+            .collect();
+
+        let expiry = Cancellation {
+            at: now,
+            by: None,
+            reason: Some("No decision before the request expired".to_string()),
+        };
+        let mut expired = Vec::with_capacity(stale.len());
+        for book in stale {
+            // Approved or rejected meanwhile, by this instance or another one.
+            let book = match self
+                .repo
+                .resolve_pending_book(book.id, BookStatus::Expired, Some(&expiry))
+                .await
+            {
+                Ok(book) => book,
+                Err(ErrService::Book(ErrBook::NotPending)) => continue,
+                Err(e) => return Err(e),
+            };
+            self.cache.retain(|b| b.id != book.id);
+            self.cache.insert(book.clone());
+
+            self.notifier
+                .notify(&book.user_id, Notification::BookingExpired(book.clone()))
+                .await;
+            expired.push(book);
+        }
+        self.promote_waitlist(&expired).await;
+
+        Ok(expired)
+    }
+
+    /// Queues `user` for a slot that is already booked; free slots are to be booked instead.
+    pub async fn join_waitlist(
+        &self,
+        user: &User,
+        request: BookRequest<'_>,
+    ) -> Result<WaitlistEntry, ErrService> {
+        let book = self.validate_request(user, &request).await?;
+
+        if !self
+            .is_exist_book(book.room_id, book.starts_at, book.ends_at, None)
+            .await?
+        {
+            return Err(ErrService::Book(ErrBook::SlotAvailable));
+        }
+
+        let entry = WaitlistEntry {
+            id: 0,
+            room_id: book.room_id,
+            user_id: book.user_id,
+            room_name: book.room_name,
+            user_name: book.user_name,
+            date: book.date,
+            slot: book.slot,
+            attendees: book.attendees,
+            created_at: self.clock.now(),
+        };
+        self.repo.insert_waitlist_entry(&entry).await
+    }
+
+    /// The caller's waitlist entries, or everyone's for those managing every booking.
+    pub async fn list_waitlist(&self, caller: &User) -> Result<Vec<WaitlistEntry>, ErrService> {
+        let user: Option<&UserID> =
+            (!caller.role.manages_all_bookings()).then_some(&caller.user_id);
+
+        self.repo.list_waitlist(user).await
+    }
+
+    pub async fn leave_waitlist(&self, caller: &User, entry_id: i32) -> Result<(), ErrService> {
+        let entry = self
+            .repo
+            .get_waitlist_entry(entry_id)
+            .await?
+            .ok_or(ErrService::Book(ErrBook::WaitlistEntryNotFound))?;
+
+        if !caller.can_manage_bookings_of(&entry.user_id) {
+            return Err(ErrService::Book(ErrBook::NotOwner));
         }
+
+        self.repo.delete_waitlist_entry(entry_id).await?;
+        Ok(())
     }
 
+    /// Books the waitlist entries whose slot `freed` bookings made available, longest
+    /// waiting first, and notifies their users. Failures are logged, not returned: the
+    /// change that freed the slots already went through.
+    async fn promote_waitlist(&self, freed: &[Book]) {
+        let days: HashMap<(i32, BookDate), RoomName> = freed
+            .iter()
+            .filter(|b| b.date.date >= self.clock.today_in(b.time_zone))
+            .map(|b| ((b.room_id, b.date.clone()), b.room_name.clone()))
+            .collect();
+
+        for ((room_id, date), room_name) in days {
+            let (room, entries) = match tokio::try_join!(
+                self.repo.get_one_room(&room_name),
+                self.repo.get_waitlist(room_id, date.date)
+            ) {
+                Ok(found) => found,
+                Err(e) => {
+                    warn!("Unable to read the waitlist of room {room_id}: {:?}", e);
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let user = match self.repo.get_one_user(&entry.user_name).await {
+                    Ok(user) => user,
+                    Err(e) => {
+                        warn!(
+                            "Unable to read the user of waitlist entry {}: {:?}",
+                            entry.id, e
+                        );
+                        continue;
+                    }
+                };
+                let book = entry.to_book(&room, &user, self.clock.now());
+                if self
+                    .cache
+                    .iter()
+                    .any(|b| b.conflicts_with(book.room_id, book.starts_at, book.ends_at))
+                {
+                    continue;
+                }
+
+                let book = match self.repo.insert_book(&book).await {
+                    Ok(book) => book,
+                    Err(e) => {
+                        warn!("Unable to promote waitlist entry {}: {:?}", entry.id, e);
+                        continue;
+                    }
+                };
+                self.cache.insert(book.clone());
+                if let Err(e) = self.repo.delete_waitlist_entry(entry.id).await {
+                    warn!(
+                        "Unable to remove promoted waitlist entry {}: {:?}",
+                        entry.id, e
+                    );
+                }
+
+                self.notifier
+                    .notify(&entry.user_id, Notification::WaitlistPromoted(book))
+                    .await;
+            }
+        }
+    }
+
+    fn cancellation(&self, caller: &User, reason: Option<String>) -> Cancellation {
+        Cancellation {
+            at: self.clock.now(),
+            by: Some(caller.user_id.clone()),
+            reason: reason
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty()),
+        }
+    }
+
+    pub fn get_book(&self, book_id: i32) -> Result<Book, ErrService> {
+        self.cache
+            .iter()
+            .find(|b| b.id == book_id)
+            .map(|b| b.clone())
+            .ok_or(ErrService::Book(ErrBook::BookNotFound))
+    }
+
+    /// Looks a booking up, making sure `caller` may change or cancel it.
+    fn owned_book(&self, caller: &User, book_id: i32) -> Result<Book, ErrService> {
+        let book = self.get_book(book_id)?;
+
+        if !caller.can_manage_bookings_of(&book.user_id) {
+            return Err(ErrService::Book(ErrBook::NotOwner));
+        }
+        Ok(book)
+    }
+
+    /// Looks a pending booking up, making sure `caller` may decide on it.
+    fn pending_book(&self, caller: &User, book_id: i32) -> Result<Book, ErrService> {
+        if !caller.role.manages_all_bookings() {
+            return Err(ErrService::Auth(ErrAuth::Forbidden));
+        }
+        let book = self.get_book(book_id)?;
+
+        if book.status != BookStatus::Pending {
+            return Err(ErrService::Book(ErrBook::NotPending));
+        }
+        Ok(book)
+    }
+
+    /// Makes sure `caller` may change or cancel the series, which has a single booker.
+    fn check_series_owner(&self, caller: &User, series_id: Uuid) -> Result<(), ErrService> {
+        let booker = self
+            .cache
+            .iter()
+            .find(|b| b.series_id == Some(series_id))
+            .map(|b| b.user_id.clone())
+            .ok_or(ErrService::Book(ErrBook::SeriesNotFound))?;
+
+        if !caller.can_manage_bookings_of(&booker) {
+            return Err(ErrService::Book(ErrBook::NotOwner));
+        }
+        Ok(())
+    }
+
+    /// Checks the cache for a booking of room `room_id` between `starts_at` and `ends_at`.
+    /// `ignore_id` leaves out the booking being updated so it can't conflict with itself.
     pub async fn is_exist_book(
         &self,
-        room: &RoomName,
-        date: &BookDate,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        ignore_id: Option<i32>,
     ) -> Result<bool, ErrService> {
         Ok(self
             .cache
             .iter()
-            .any(|b| (b.date == *date) && (b.room_name == *room)))
+            .filter(|b| Some(b.id) != ignore_id)
+            .any(|b| b.conflicts_with(room_id, starts_at, ends_at)))
+    }
+
+    /// Makes sure no other booking holds `book`'s slot. `ignore_id` leaves out the
+    /// booking being updated so it can't conflict with itself.
+    fn check_slot_free(&self, book: &Book, ignore_id: Option<i32>) -> Result<(), ErrService> {
+        let holder = self
+            .cache
+            .iter()
+            .filter(|b| Some(b.id) != ignore_id)
+            .find(|b| b.conflicts_with(book.room_id, book.starts_at, book.ends_at))
+            .map(|b| b.clone());
+
+        match holder {
+            Some(holder) => Err(Self::taken_by(&holder)),
+            None => Ok(()),
+        }
+    }
+
+    /// The conflict reported for a slot held by `holder`: only tentatively when it awaits
+    /// approval.
+    fn taken_by(holder: &Book) -> ErrService {
+        if holder.status == BookStatus::Pending {
+            ErrService::Book(ErrBook::SlotHeld)
+        } else {
+            ErrService::Book(ErrBook::AlreadyBooked)
+        }
+    }
+
+    /// Filters `rooms` (the room cache) down to those free over `range`, or free during
+    /// `slot` on each of its days when given. Falls back to the database while the
+    /// caches are cold.
+    pub async fn available_rooms(
+        &self,
+        rooms: Vec<Room>,
+        range: &BookDateRange,
+        slot: Option<&BookSlot>,
+    ) -> Result<Vec<Room>, ErrService> {
+        if rooms.is_empty() || !self.cache_ready.load(Ordering::Acquire) {
+            return self.repo.get_available_rooms(range, slot).await;
+        }
+
+        let mut available: Vec<Room> = rooms
+            .into_iter()
+            .filter(|room| {
+                !self.cache.iter().any(|b| {
+                    b.is_live()
+                        && b.room_id == room.id
+                        && range.contains(&b.date)
+                        && slot.is_none_or(|slot| b.slot.overlaps(slot))
+                })
+            })
+            .collect();
+        available.sort_by(|a, b| a.room_name.name.cmp(&b.room_name.name));
+
+        Ok(available)
+    }
+
+    /// Refreshes the room name and time zone carried by cached bookings once `room` was
+    /// changed, their instants with it as the store does.
+    pub fn rename_room(&self, room: &Room) {
+        let renamed: Vec<Book> = self
+            .cache
+            .iter()
+            .filter(|b| b.room_id == room.id)
+            .map(|b| {
+                Book {
+                    room_name: room.room_name.clone(),
+                    time_zone: room.time_zone,
+                    ..b.clone()
+                }
+                .with_instants()
+            })
+            .collect();
+        for book in renamed {
+            self.cache.retain(|b| b.id != book.id);
+            self.cache.insert(book);
+        }
+    }
+
+    /// Refreshes the user name carried by cached bookings once `user` was renamed.
+    pub fn rename_user(&self, user: &User) {
+        let renamed: Vec<Book> = self
+            .cache
+            .iter()
+            .filter(|b| b.user_id == user.user_id)
+            .map(|b| Book {
+                user_name: user.user_name.clone(),
+                ..b.clone()
+            })
+            .collect();
+        for book in renamed {
+            self.cache.retain(|b| b.id != book.id);
+            self.cache.insert(book);
+        }
+    }
+
+    /// Drops the bookings of a deleted user from the cache, the store having purged them.
+    pub fn forget_user(&self, user_id: &UserID) {
+        self.cache.retain(|b| b.user_id != *user_id);
+    }
+
+    /// Drops the bookings of a deleted room from the cache, the store having purged them.
+    pub fn forget_room(&self, room_id: i32) {
+        self.cache.retain(|b| b.room_id != room_id);
     }
 
     pub async fn is_exist_book_id(&self, id: &i32) -> Result<bool, ErrService> {
         Ok(self.cache.iter().any(|b| b.id == *id))
     }
 
-    pub async fn delete_all_book(&self) -> Result<(), ErrService> {
-        let deleted = self.repo.delete_all_book().await?;
-        if deleted {
-            self.cache.clear();
-            info!("Cache lenght: {:?}", self.cache.len());
-            Ok(())
-        } else {
-            Err(ErrService::Repo(ErrRepo::UnableToDelete))
+    /// Cancels every live booking the way [`Self::cancel_book`] does: they stay on
+    /// record, and the waitlist takes the freed slots.
+    pub async fn cancel_all_books(
+        &self,
+        caller: &User,
+        reason: Option<String>,
+    ) -> Result<Vec<Book>, ErrService> {
+        let cancelled = self
+            .repo
+            .cancel_all_books(&self.cancellation(caller, reason))
+            .await?;
+        if cancelled.is_empty() {
+            return Err(ErrService::Repo(ErrRepo::IsEmpty));
+        }
+
+        for book in &cancelled {
+            self.cache.retain(|b| b.id != book.id);
+            self.cache.insert(book.clone());
         }
+        self.promote_waitlist(&cancelled).await;
+
+        Ok(cancelled)
     }
 
     pub async fn populate_cache(&self) -> Result<(), ErrService> {
-        self.list_book().await?.into_iter().for_each(|e| {
-            let book = Book {
-                id: e.id,
-                room_name: e.room_name,
-                user_name: e.user_name,
-                date: e.date,
-            };
-            self.cache.insert(book);
-        });
+        self.repo
+            .get_all_books()
+            .await?
+            .into_iter()
+            .for_each(|book| {
+                self.cache.insert(book);
+            });
 
+        self.cache_ready.store(true, Ordering::Release);
         info!("BookService cache lenght: {}", self.cache.len());
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use chrono_tz::Tz;
+
+    use crate::{
+        domain::{BookStatus, Recurrence, Room},
+        error::{ErrAuth, ErrBook, ErrRepo, ErrService},
+        features::{
+            book::{repo::BookRepo, service::BookRequest},
+            room::repo::RoomRepo,
+            waitlist::repo::WaitlistRepo,
+        },
+        infra::{
+            clock::Clock,
+            in_memory::in_memo_helper::{Fixture, day, err_book, request},
+        },
+    };
+
+    #[tokio::test]
+    async fn rejects_invalid_requests() {
+        let f = Fixture::new().await;
+        let (yesterday, date) = (day(-1), day(1));
+        let book = |req| f.service.book_room(&f.member, req);
+
+        assert!(matches!(
+            err_book(book(request("alpha", "2026-12-01", "09:00", "10:00")).await),
+            ErrBook::InvalidDateFormat
+        ));
+        assert!(matches!(
+            err_book(book(request("alpha", &yesterday, "09:00", "10:00")).await),
+            ErrBook::InvalidDate
+        ));
+        assert!(matches!(
+            err_book(book(request("alpha", &date, "9h", "10:00")).await),
+            ErrBook::InvalidTimeFormat
+        ));
+        assert!(matches!(
+            err_book(book(request("alpha", &date, "10:00", "09:00")).await),
+            ErrBook::InvalidTimeRange
+        ));
+        assert!(matches!(
+            err_book(book(request("gamma", &date, "09:00", "10:00")).await),
+            ErrBook::RoomNotFound
+        ));
+        let crowd = |attendees| BookRequest {
+            attendees,
+            ..request("alpha", &date, "09:00", "10:00")
+        };
+        assert!(matches!(
+            err_book(book(crowd(0)).await),
+            ErrBook::InvalidAttendees
+        ));
+        assert!(matches!(
+            err_book(book(crowd(9)).await),
+            ErrBook::CapacityExceeded
+        ));
+
+        assert!(book(crowd(8)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_double_bookings() {
+        let f = Fixture::new().await;
+        let date = day(1);
+
+        let first = f
+            .service
+            .book_room(&f.member, request("alpha", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_room(&f.other_member, request("alpha", &date, "09:30", "10:30"))
+                    .await
+            ),
+            ErrBook::AlreadyBooked
+        ));
+        // back to back, or the same slot another day or in another room
+        for (room, date, start, end) in [
+            ("alpha", date.as_str(), "10:00", "11:00"),
+            ("alpha", &day(2), "09:00", "10:00"),
+            ("beta", &date, "09:00", "10:00"),
+        ] {
+            assert!(
+                f.service
+                    .book_room(&f.other_member, request(room, date, start, end))
+                    .await
+                    .is_ok()
+            );
+        }
+
+        f.service
+            .cancel_book(&f.member, first.id, None)
+            .await
+            .unwrap();
+        assert!(
+            f.service
+                .book_room(&f.other_member, request("alpha", &date, "09:30", "10:00"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_check_ownership_and_conflicts() {
+        let f = Fixture::new().await;
+        let date = day(1);
+        let first = f
+            .service
+            .book_room(&f.member, request("alpha", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+        let second = f
+            .service
+            .book_room(&f.member, request("alpha", &date, "10:00", "11:00"))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            err_book(
+                f.service
+                    .update_book_by_id(
+                        &f.other_member,
+                        first.id,
+                        request("alpha", &date, "12:00", "13:00")
+                    )
+                    .await
+            ),
+            ErrBook::NotOwner
+        ));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .update_book_by_id(&f.member, 999, request("alpha", &date, "12:00", "13:00"))
+                    .await
+            ),
+            ErrBook::BookNotFound
+        ));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .update_book_by_id(
+                        &f.member,
+                        second.id,
+                        request("alpha", &date, "09:30", "10:30")
+                    )
+                    .await
+            ),
+            ErrBook::AlreadyBooked
+        ));
+
+        // can't conflict with itself; a facility manager moves anyone's booking
+        let moved = f
+            .service
+            .update_book_by_id(
+                &f.manager,
+                second.id,
+                request("alpha", &date, "10:30", "11:30"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(moved.user_id, f.member.user_id);
+        assert_eq!(
+            f.repo.find_book(second.id).await.unwrap().as_ref(),
+            Some(&moved)
+        );
+
+        f.service
+            .cancel_book(&f.member, first.id, None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            err_book(f.service.cancel_book(&f.member, first.id, None).await),
+            ErrBook::Cancelled
+        ));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .update_book_by_id(
+                        &f.member,
+                        first.id,
+                        request("alpha", &date, "12:00", "13:00")
+                    )
+                    .await
+            ),
+            ErrBook::Cancelled
+        ));
+        assert!(matches!(
+            err_book(f.service.cancel_book(&f.member, 999, None).await),
+            ErrBook::BookNotFound
+        ));
+    }
+
+    #[tokio::test]
+    async fn pending_bookings_hold_their_slot_until_decided() {
+        let f = Fixture::new().await;
+        let date = day(1);
+
+        let pending = f
+            .service
+            .book_room(&f.member, request("beta", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+        assert_eq!(pending.status, BookStatus::Pending);
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_room(&f.other_member, request("beta", &date, "09:00", "10:00"))
+                    .await
+            ),
+            ErrBook::SlotHeld
+        ));
+        assert!(matches!(
+            f.service.approve_book(&f.member, pending.id).await,
+            Err(ErrService::Auth(ErrAuth::Forbidden))
+        ));
+
+        let approved = f
+            .service
+            .approve_book(&f.manager, pending.id)
+            .await
+            .unwrap();
+        assert_eq!(approved.status, BookStatus::Confirmed);
+        assert!(matches!(
+            err_book(f.service.approve_book(&f.manager, pending.id).await),
+            ErrBook::NotPending
+        ));
+
+        let rejected = f
+            .service
+            .book_room(&f.other_member, request("beta", &date, "10:00", "11:00"))
+            .await
+            .unwrap();
+        f.service
+            .reject_book(&f.manager, rejected.id, Some("Maintenance".to_string()))
+            .await
+            .unwrap();
+        assert!(matches!(
+            err_book(
+                f.service
+                    .cancel_book(&f.other_member, rejected.id, None)
+                    .await
+            ),
+            ErrBook::Declined
+        ));
+        assert!(
+            f.service
+                .book_room(&f.member, request("beta", &date, "10:00", "11:00"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn statuses_follow_the_booking_day() {
+        let f = Fixture::new().await;
+        let book = f
+            .service
+            .book_room(&f.member, request("alpha", &day(1), "09:00", "10:00"))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            f.service
+                .set_book_status(&f.member, book.id, "completed")
+                .await,
+            Err(ErrService::Auth(ErrAuth::Forbidden))
+        ));
+        for status in ["completed", "pending", "done"] {
+            assert!(matches!(
+                err_book(f.service.set_book_status(&f.manager, book.id, status).await),
+                ErrBook::InvalidStatus
+            ));
+        }
+        assert!(
+            f.service
+                .set_book_status(&f.manager, book.id, "confirmed")
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn series_are_booked_and_changed_as_a_whole() {
+        let f = Fixture::new().await;
+        let start = day(1);
+        let daily = |count| Recurrence::new("daily", None, None, Some(count), &[]).unwrap();
+
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_series(
+                        &f.member,
+                        request("alpha", &start, "09:00", "10:00"),
+                        &daily(Recurrence::MAX_OCCURRENCES as u32 + 1)
+                    )
+                    .await
+            ),
+            ErrBook::TooManyOccurrences
+        ));
+
+        f.service
+            .book_room(&f.other_member, request("alpha", &day(2), "09:30", "10:30"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_series(
+                        &f.member,
+                        request("alpha", &start, "09:00", "10:00"),
+                        &daily(3)
+                    )
+                    .await
+            ),
+            ErrBook::AlreadyBooked
+        ));
+        assert_eq!(f.repo.get_all_books().await.unwrap().len(), 1);
+
+        let (series_id, books) = f
+            .service
+            .book_series(
+                &f.member,
+                request("alpha", &start, "08:00", "09:00"),
+                &daily(3),
+            )
+            .await
+            .unwrap();
+        assert_eq!(books.len(), 3);
+
+        assert!(matches!(
+            err_book(
+                f.service
+                    .update_series(&f.member, series_id, None, "alpha", "09:00", "10:00")
+                    .await
+            ),
+            ErrBook::AlreadyBooked
+        ));
+        let moved = f
+            .service
+            .update_series(&f.member, series_id, None, "beta", "08:00", "09:00")
+            .await
+            .unwrap();
+        assert!(moved.iter().all(|b| b.status == BookStatus::Pending));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .cancel_series(&f.other_member, series_id, None, None)
+                    .await
+            ),
+            ErrBook::NotOwner
+        ));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .cancel_series(&f.member, uuid::Uuid::new_v4(), None, None)
+                    .await
+            ),
+            ErrBook::SeriesNotFound
+        ));
+
+        let cancelled = f
+            .service
+            .cancel_series(&f.member, series_id, Some(&day(2)), None)
+            .await
+            .unwrap();
+        assert_eq!(cancelled.len(), 2);
+        assert!(matches!(
+            err_book(
+                f.service
+                    .cancel_series(&f.member, series_id, Some(&day(2)), None)
+                    .await
+            ),
+            ErrBook::SeriesNotFound
+        ));
+    }
+
+    #[tokio::test]
+    async fn imports_are_reserved_and_not_repeated() {
+        let f = Fixture::new().await;
+        let date = day(1);
+
+        assert!(matches!(
+            f.service
+                .import_book(
+                    &f.member,
+                    "jordan",
+                    request("beta", &date, "09:00", "10:00")
+                )
+                .await,
+            Err(ErrService::Auth(ErrAuth::Forbidden))
+        ));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .import_book(
+                        &f.manager,
+                        "nobody",
+                        request("beta", &date, "09:00", "10:00")
+                    )
+                    .await
+            ),
+            ErrBook::UserNotFound
+        ));
+
+        let imported = f
+            .service
+            .import_book(
+                &f.manager,
+                "jordan",
+                request("beta", &date, "09:00", "10:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(imported.user_id, f.other_member.user_id);
+        assert_eq!(imported.status, BookStatus::Confirmed);
+        assert!(matches!(
+            err_book(
+                f.service
+                    .import_book(
+                        &f.manager,
+                        "jordan",
+                        request("beta", &date, "09:00", "10:00")
+                    )
+                    .await
+            ),
+            ErrBook::Duplicate
+        ));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .import_book(
+                        &f.manager,
+                        "sophie",
+                        request("beta", &date, "09:00", "10:00")
+                    )
+                    .await
+            ),
+            ErrBook::AlreadyBooked
+        ));
+
+        // history is taken as held, today's slot still to come as confirmed
+        let past = f
+            .service
+            .import_book(
+                &f.manager,
+                "jordan",
+                request("beta", &day(-30), "09:00", "10:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(past.status, BookStatus::Completed);
+        let later = f
+            .service
+            .import_book(
+                &f.manager,
+                "jordan",
+                request("beta", &day(0), "09:00", "10:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(later.status, BookStatus::Confirmed);
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_room(
+                        &f.other_member,
+                        request("beta", &day(-30), "11:00", "12:00")
+                    )
+                    .await
+            ),
+            ErrBook::InvalidDate
+        ));
+    }
+
+    #[tokio::test]
+    async fn freed_slots_go_to_the_waitlist() {
+        let f = Fixture::new().await;
+        let date = day(1);
+
+        assert!(matches!(
+            err_book(
+                f.service
+                    .join_waitlist(&f.member, request("alpha", &date, "09:00", "10:00"))
+                    .await
+            ),
+            ErrBook::SlotAvailable
+        ));
+        let taken = f
+            .service
+            .book_room(&f.other_member, request("alpha", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+
+        let entry = f
+            .service
+            .join_waitlist(&f.member, request("alpha", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            err_book(
+                f.service
+                    .join_waitlist(&f.member, request("alpha", &date, "09:00", "10:00"))
+                    .await
+            ),
+            ErrBook::AlreadyQueued
+        ));
+        assert!(matches!(
+            err_book(f.service.leave_waitlist(&f.other_member, entry.id).await),
+            ErrBook::NotOwner
+        ));
+        assert!(matches!(
+            err_book(f.service.leave_waitlist(&f.member, 999).await),
+            ErrBook::WaitlistEntryNotFound
+        ));
+
+        f.service
+            .cancel_book(&f.other_member, taken.id, None)
+            .await
+            .unwrap();
+        let promoted = f.service.cached_books(&Default::default());
+        assert!(
+            promoted
+                .iter()
+                .any(|b| b.user_id == f.member.user_id && b.is_live())
+        );
+        assert!(f.repo.get_waitlist_entry(entry.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn days_pass_on_the_service_clock() {
+        let f = Fixture::new().await;
+        let (today, tomorrow) = (day(0), day(1));
+
+        let book = f
+            .service
+            .book_room(&f.member, request("alpha", &today, "07:00", "08:00"))
+            .await
+            .unwrap();
+        assert_eq!(book.created_at, f.clock.now());
+        assert!(
+            f.service
+                .set_book_status(&f.manager, book.id, "completed")
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_room(&f.member, request("alpha", &day(-1), "09:00", "10:00"))
+                    .await
+            ),
+            ErrBook::InvalidDate
+        ));
+
+        let later = f
+            .service
+            .book_room(&f.member, request("alpha", &tomorrow, "09:00", "10:00"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            err_book(
+                f.service
+                    .set_book_status(&f.manager, later.id, "no_show")
+                    .await
+            ),
+            ErrBook::InvalidStatus
+        ));
+
+        f.clock.advance(Duration::days(1));
+        assert!(
+            f.service
+                .set_book_status(&f.manager, later.id, "no_show")
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            err_book(
+                f.service
+                    .update_book_by_id(
+                        &f.member,
+                        book.id,
+                        request("alpha", &today, "08:00", "09:00")
+                    )
+                    .await
+            ),
+            ErrBook::InvalidDate
+        ));
+    }
+
+    #[tokio::test]
+    async fn pending_requests_expire_without_a_decision() {
+        let f = Fixture::new().await;
+        let ttl = Duration::hours(48);
+
+        let early = f
+            .service
+            .book_room(&f.member, request("beta", &day(1), "09:00", "10:00"))
+            .await
+            .unwrap();
+        let late = f
+            .service
+            .book_room(&f.member, request("beta", &day(7), "09:00", "10:00"))
+            .await
+            .unwrap();
+        f.service
+            .join_waitlist(&f.other_member, request("beta", &day(7), "09:00", "10:00"))
+            .await
+            .unwrap();
+        assert!(f.service.expire_pending(ttl).await.unwrap().is_empty());
+
+        // the first one's start comes before its request is 48 hours old
+        f.clock.advance(Duration::hours(25));
+        let expired = f.service.expire_pending(ttl).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, early.id);
+        assert_eq!(expired[0].status, BookStatus::Expired);
+
+        f.clock.advance(Duration::hours(24));
+        let expired = f.service.expire_pending(ttl).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, late.id);
+        let cancellation = expired[0].cancellation.as_ref().unwrap();
+        assert_eq!(cancellation.at, f.clock.now());
+
+        // its slot went to the waitlist, pending too in a room requiring approval
+        let promoted = f
+            .service
+            .cached_books(&Default::default())
+            .into_iter()
+            .find(|b| b.user_id == f.other_member.user_id)
+            .unwrap();
+        assert_eq!(promoted.status, BookStatus::Pending);
+        assert_eq!(promoted.created_at, f.clock.now());
+    }
+
+    #[tokio::test]
+    async fn past_days_are_told_on_the_room_wall_clock() {
+        let f = Fixture::new().await;
+        let honolulu = f
+            .repo
+            .insert_room(
+                &Room::new("honolulu", 4)
+                    .unwrap()
+                    .with_time_zone(Tz::Pacific__Honolulu),
+            )
+            .await
+            .unwrap();
+        f.repo
+            .insert_room(
+                &Room::new("tokyo", 4)
+                    .unwrap()
+                    .with_time_zone(Tz::Asia__Tokyo),
+            )
+            .await
+            .unwrap();
+        let yesterday = day(-1);
+
+        // 08:00 UTC is 22:00 the day before in Honolulu
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_room(&f.member, request("alpha", &yesterday, "23:00", "23:30"))
+                    .await
+            ),
+            ErrBook::InvalidDate
+        ));
+        let late = f
+            .service
+            .book_room(&f.member, request("honolulu", &yesterday, "23:00", "23:30"))
+            .await
+            .unwrap();
+        assert_eq!(late.room_id, honolulu.id);
+        assert_eq!(late.time_zone, Tz::Pacific__Honolulu);
+        assert_eq!(late.starts_at, f.clock.now() + Duration::hours(1));
+
+        // 16:00 UTC is 01:00 the day after in Tokyo
+        f.clock.advance(Duration::hours(8));
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_room(&f.member, request("tokyo", &day(0), "18:00", "19:00"))
+                    .await
+            ),
+            ErrBook::InvalidDate
+        ));
+        assert!(
+            f.service
+                .book_room(&f.member, request("alpha", &day(0), "18:00", "19:00"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelling_every_booking_keeps_them_on_record() {
+        let f = Fixture::new().await;
+        let date = day(1);
+        let taken = f
+            .service
+            .book_room(&f.other_member, request("alpha", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+        f.service
+            .book_room(&f.other_member, request("alpha", &day(2), "09:00", "10:00"))
+            .await
+            .unwrap();
+        f.service
+            .join_waitlist(&f.member, request("alpha", &date, "09:00", "10:00"))
+            .await
+            .unwrap();
+
+        let cancelled = f
+            .service
+            .cancel_all_books(&f.manager, Some("Building closed".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(cancelled.len(), 2);
+        let record = f.repo.find_book(taken.id).await.unwrap().unwrap();
+        assert_eq!(record.status, BookStatus::Cancelled);
+        let cancellation = record.cancellation.unwrap();
+        assert_eq!(cancellation.by, Some(f.manager.user_id.clone()));
+        assert_eq!(cancellation.reason.as_deref(), Some("Building closed"));
+
+        // the waitlist took the freed slot, and is cancelled by the next call only
+        let live: Vec<_> = f
+            .service
+            .cached_books(&Default::default())
+            .into_iter()
+            .filter(|b| b.is_live())
+            .collect();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].user_id, f.member.user_id);
+        assert_eq!(
+            f.service
+                .cancel_all_books(&f.manager, None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            f.service.cancel_all_books(&f.manager, None).await,
+            Err(ErrService::Repo(ErrRepo::IsEmpty))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_series_without_occurrences() {
+        let f = Fixture::new().await;
+        // ends the day before it starts
+        let until_today = Recurrence::new("daily", None, Some(&day(0)), None, &[]).unwrap();
+
+        assert!(matches!(
+            err_book(
+                f.service
+                    .book_series(
+                        &f.member,
+                        request("alpha", &day(1), "09:00", "10:00"),
+                        &until_today
+                    )
+                    .await
+            ),
+            ErrBook::InvalidRecurrence
+        ));
+        assert!(f.service.cached_books(&Default::default()).is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    domain::{CalendarFeed, FeedSubject, UserID},
+    error::ErrType,
+    features::book::dto::BookDto,
+};
+
+/// Query of the `.ics` routes: the feed's secret token, instead of a bearer token.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeedQuery {
+    pub token: Option<String>,
+}
+
+/// A subscribable calendar feed: its `url` works without an `Authorization` header.
+#[derive(Serialize, ToSchema)]
+pub struct CalendarFeedDto {
+    pub token: String,
+    #[schema(example = "/room/1/calendar.ics?token=...")]
+    pub url: String,
+}
+
+/// Query of `POST /book/import`: the room and booker of every event, winning over their
+/// `LOCATION` and `ORGANIZER` (exports are usually per room or per user).
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    pub room: Option<String>,
+    pub user: Option<String>,
+}
+
+/// What became of each event of an imported calendar, in the calendar's order.
+#[derive(Serialize, ToSchema, Default)]
+pub struct ImportReportDto {
+    pub created: usize,
+    pub skipped: usize,
+    pub conflicts: usize,
+    pub events: Vec<ImportedEventDto>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportedEventDto {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    /// `created`, `skipped` or `conflict`.
+    #[schema(example = "conflict")]
+    pub outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub book: Option<BookDto>,
+    /// Problem code of the booking attempt, when it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "BOOK_ALREADY_BOOKED")]
+    pub code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct CalendarFeedRowDto {
+    pub token: String,
+    pub room_id: Option<i32>,
+    pub user_id: Option<Uuid>,
+}
+
+impl TryFrom<CalendarFeedRowDto> for CalendarFeed {
+    type Error = ErrType;
+
+    fn try_from(dto: CalendarFeedRowDto) -> Result<Self, Self::Error> {
+        let subject = match (dto.room_id, dto.user_id) {
+            (Some(room_id), None) => FeedSubject::Room(room_id),
+            (None, Some(id)) => FeedSubject::User(UserID { id }),
+            _ => return Err(ErrType::RawConversionFailed),
+        };
+
+        Ok(CalendarFeed {
+            token: dto.token,
+            subject,
+        })
+    }
+}
+
+impl From<CalendarFeed> for CalendarFeedDto {
+    fn from(feed: CalendarFeed) -> Self {
+        let path = match &feed.subject {
+            FeedSubject::Room(id) => format!("/room/{id}/calendar.ics"),
+            FeedSubject::User(user_id) => format!("/users/{}/calendar.ics", user_id.id),
+        };
+
+        CalendarFeedDto {
+            url: format!("{path}?token={}", feed.token),
+            token: feed.token,
+        }
+    }
+}
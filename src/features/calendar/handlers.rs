@@ -0,0 +1,349 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header::CONTENT_TYPE},
+    response::IntoResponse,
+};
+use chrono::Days;
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use crate::{
+    app::state::AppState,
+    domain::{Book, BookDate, BookFilter, FeedSubject, RoomName, User, UserID},
+    error::{ErrAuth, ErrBook, ErrService, Problem, ProblemDto},
+    features::{
+        auth::middleware::{AuthUser, authenticate},
+        book::{dto::BookDto, service::BookRequest},
+        calendar::{
+            dto::{CalendarFeedDto, FeedQuery, ImportQuery, ImportReportDto, ImportedEventDto},
+            ics::{IcsEvent, parse_events, render_calendar},
+        },
+    },
+    infra::{backend::Backend, clock::Clock},
+};
+
+/// How far back calendars go; older bookings are left out of them.
+const CALENDAR_PAST_DAYS: u64 = 30;
+
+const TEXT_CALENDAR: &str = "text/calendar; charset=utf-8";
+
+/// Lets the request in with the feed's `token`, or else with a bearer token whose user
+/// may read the calendar of `subject`.
+async fn authorize_feed<B: Backend>(
+    state: &AppState<B>,
+    headers: &HeaderMap,
+    subject: &FeedSubject,
+    token: Option<&str>,
+) -> Result<(), ErrService> {
+    match token {
+        Some(token) => state.calendar_service.check_token(subject, token).await,
+        None => {
+            let caller = authenticate(state, headers).await?;
+            state.calendar_service.check_readable(&caller, subject)
+        }
+    }
+}
+
+/// Live bookings from [`CALENDAR_PAST_DAYS`] before today on `clock` onward, counted in
+/// UTC days.
+fn calendar_filter(clock: &dyn Clock) -> BookFilter {
+    let from = clock.today_in(Tz::UTC) - Days::new(CALENDAR_PAST_DAYS);
+
+    BookFilter {
+        from: Some(BookDate { date: from }),
+        ..BookFilter::default()
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/room/{id}/calendar.ics",
+    tag = "calendar",
+    params(("id" = i32, Path, description = "Room id"), FeedQuery),
+    security((), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The room's bookings as an iCalendar feed", body = String, content_type = "text/calendar"),
+        (status = 401, description = "Neither a feed token nor a valid bearer token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room or feed token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn room_calendar<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<i32>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ErrService> {
+    authorize_feed(
+        &state,
+        &headers,
+        &FeedSubject::Room(id),
+        query.token.as_deref(),
+    )
+    .await?;
+    let room = state.room_service.get_room(id)?;
+
+    let books = state.book_service.cached_books(&BookFilter {
+        room: Some(room.room_name.clone()),
+        ..calendar_filter(state.clock.as_ref())
+    });
+    let name = format!("{} bookings", room.room_name.name);
+
+    Ok((
+        [(CONTENT_TYPE, TEXT_CALENDAR)],
+        render_calendar(&name, &books, true, state.clock.now()),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/calendar.ics",
+    tag = "calendar",
+    params(("id" = Uuid, Path, description = "User id"), FeedQuery),
+    security((), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The user's bookings as an iCalendar feed", body = String, content_type = "text/calendar"),
+        (status = 401, description = "Neither a feed token nor a valid bearer token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Another user's calendar, and the caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user or feed token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn user_calendar<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ErrService> {
+    let subject = FeedSubject::User(UserID { id });
+    authorize_feed(&state, &headers, &subject, query.token.as_deref()).await?;
+    let user = state.user_service.get_user(id)?;
+
+    let books = state.book_service.cached_books(&BookFilter {
+        user: Some(user.user_name.clone()),
+        ..calendar_filter(state.clock.as_ref())
+    });
+    let name = format!("{} bookings", user.user_name.name);
+
+    Ok((
+        [(CONTENT_TYPE, TEXT_CALENDAR)],
+        render_calendar(&name, &books, false, state.clock.now()),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/room/{id}/calendar/feed",
+    tag = "calendar",
+    params(("id" = i32, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room's feed URL, created on first request", body = CalendarFeedDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn room_calendar_feed<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    state.room_service.get_room(id)?;
+    let feed = state
+        .calendar_service
+        .feed(&user, FeedSubject::Room(id))
+        .await?;
+
+    Ok(Json(CalendarFeedDto::from(feed)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/room/{id}/calendar/feed",
+    tag = "calendar",
+    params(("id" = i32, Path, description = "Room id")),
+    responses(
+        (status = 204, description = "Feed token revoked"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn revoke_room_calendar_feed<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    state
+        .calendar_service
+        .revoke(&user, &FeedSubject::Room(id))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/calendar/feed",
+    tag = "calendar",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "The user's feed URL, created on first request", body = CalendarFeedDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Another user's calendar, and the caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn user_calendar_feed<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ErrService> {
+    let owner = state.user_service.get_user(id)?;
+    let feed = state
+        .calendar_service
+        .feed(&user, FeedSubject::User(owner.user_id))
+        .await?;
+
+    Ok(Json(CalendarFeedDto::from(feed)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}/calendar/feed",
+    tag = "calendar",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 204, description = "Feed token revoked"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Another user's calendar, and the caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn revoke_user_calendar_feed<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ErrService> {
+    state
+        .calendar_service
+        .revoke(&user, &FeedSubject::User(UserID { id }))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/book/import",
+    tag = "calendar",
+    params(ImportQuery),
+    request_body(content = String, content_type = "text/calendar", description = "An iCalendar export; past events are taken as history, `completed` once over"),
+    responses(
+        (status = 200, description = "What became of each event", body = ImportReportDto),
+        (status = 400, description = "Not an iCalendar document", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller isn't a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn import_calendar<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Result<impl IntoResponse, ErrService> {
+    if !user.role.manages_all_bookings() {
+        return Err(ErrService::Auth(ErrAuth::Forbidden));
+    }
+
+    let mut report = ImportReportDto::default();
+    for event in parse_events(&body)? {
+        let mut entry = ImportedEventDto {
+            uid: event.uid.clone(),
+            summary: event.summary.clone(),
+            outcome: "created",
+            book: None,
+            code: None,
+            reason: None,
+        };
+
+        match import_event(&state, &user, &query, &event).await {
+            Ok(book) => {
+                report.created += 1;
+                entry.book = Some(BookDto::from(book));
+            }
+            Err(Skip::Unfit(reason)) => {
+                report.skipped += 1;
+                entry.outcome = "skipped";
+                entry.reason = Some(reason.to_string());
+            }
+            Err(Skip::Refused(err)) => {
+                if matches!(
+                    err,
+                    ErrService::Book(ErrBook::AlreadyBooked | ErrBook::SlotHeld)
+                ) {
+                    report.conflicts += 1;
+                    entry.outcome = "conflict";
+                } else {
+                    report.skipped += 1;
+                    entry.outcome = "skipped";
+                }
+                let problem = Problem::from(err);
+                entry.code = Some(problem.code());
+                entry.reason = Some(problem.detail().to_string());
+            }
+        }
+        report.events.push(entry);
+    }
+
+    Ok(Json(report))
+}
+
+/// Why an imported event didn't become a booking.
+enum Skip {
+    /// The event can't be a booking as it is.
+    Unfit(&'static str),
+    /// The booking it maps onto was refused.
+    Refused(ErrService),
+}
+
+/// Maps `event` onto a room and a booker by name, the query's before the event's, and
+/// books it at its times on the room's wall clock, past ones included.
+async fn import_event<B: Backend>(
+    state: &AppState<B>,
+    caller: &User,
+    query: &ImportQuery,
+    event: &IcsEvent,
+) -> Result<Book, Skip> {
+    let room = query
+        .room
+        .as_deref()
+        .or(event.location.as_deref())
+        .ok_or(Skip::Unfit("No room: give a `room` or a LOCATION"))?;
+    // Unknown rooms are reported by the booking itself.
+    let time_zone = RoomName::new(room)
+        .ok()
+        .and_then(|name| state.room_service.get_room_by_name_on_cache(&name).ok()?)
+        .map_or(Tz::UTC, |r| r.time_zone);
+    let (date, start, end) = event.slot(time_zone).map_err(Skip::Unfit)?;
+    let booker = query
+        .user
+        .as_deref()
+        .or(event.organizer.as_deref())
+        .ok_or(Skip::Unfit("No booker: give a `user` or an ORGANIZER"))?;
+
+    let (date, start_time, end_time) = (
+        date.format("%d.%m.%y").to_string(),
+        start.format("%H:%M").to_string(),
+        end.format("%H:%M").to_string(),
+    );
+    let request = BookRequest {
+        room,
+        date: &date,
+        start_time: &start_time,
+        end_time: &end_time,
+        attendees: event.attendees.max(1),
+    };
+
+    state
+        .book_service
+        .import_book(caller, booker, request)
+        .await
+        .map_err(Skip::Refused)
+}
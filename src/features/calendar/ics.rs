@@ -0,0 +1,390 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    domain::{Book, BookStatus, instant_in},
+    error::ErrBook,
+};
+
+/// Names this API as the producer of the calendars (RFC 5545 `PRODID`).
+const PRODID: &str = "-//Room Reservations//Bookings//EN";
+
+/// Right-hand side of the event UIDs, so they can't clash with other producers' events.
+const UID_DOMAIN: &str = "room-reservations";
+
+/// Content lines longer than this many octets are folded (RFC 5545, 3.1).
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Renders `books` as an RFC 5545 calendar named `name`, one VEVENT per booking, stamped
+/// `stamp`. `show_booker` adds who booked to each event's summary, for room calendars.
+///
+/// Events start and end at UTC instants, which calendar apps show in their user's zone.
+pub fn render_calendar(
+    name: &str,
+    books: &[Book],
+    show_booker: bool,
+    stamp: DateTime<Utc>,
+) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, &format!("PRODID:{PRODID}"));
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(name)));
+
+    for book in books {
+        let summary = if show_booker {
+            format!("{} ({})", book.room_name.name, book.user_name.name)
+        } else {
+            book.room_name.name.clone()
+        };
+
+        push_line(&mut ics, "BEGIN:VEVENT");
+        // Derived from the booking id only, so updates replace the event in place.
+        push_line(&mut ics, &format!("UID:book-{}@{UID_DOMAIN}", book.id));
+        push_line(&mut ics, &format!("DTSTAMP:{}", utc(stamp)));
+        push_line(&mut ics, &format!("CREATED:{}", utc(book.created_at)));
+        push_line(&mut ics, &format!("DTSTART:{}", utc(book.starts_at)));
+        push_line(&mut ics, &format!("DTEND:{}", utc(book.ends_at)));
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&summary)));
+        push_line(
+            &mut ics,
+            &format!("LOCATION:{}", escape(&book.room_name.name)),
+        );
+        push_line(
+            &mut ics,
+            &format!("DESCRIPTION:{}", escape(&description(book))),
+        );
+        push_line(&mut ics, &format!("STATUS:{}", status(book.status)));
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+fn description(book: &Book) -> String {
+    let attendees = match book.attendees {
+        1 => "1 attendee".to_string(),
+        n => format!("{n} attendees"),
+    };
+    format!("{attendees}\nBooked by {}", book.user_name.name)
+}
+
+/// Pending bookings only hold their slot tentatively.
+fn status(status: BookStatus) -> &'static str {
+    match status {
+        BookStatus::Pending => "TENTATIVE",
+        BookStatus::Cancelled | BookStatus::Rejected | BookStatus::Expired => "CANCELLED",
+        BookStatus::Confirmed | BookStatus::Completed | BookStatus::NoShow => "CONFIRMED",
+    }
+}
+
+fn utc(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes a TEXT value (RFC 5545, 3.3.11).
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folded into continuation lines starting with a space past
+/// [`MAX_LINE_OCTETS`], never splitting a character.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+/// A VEVENT read from an imported calendar, before it's mapped onto rooms and users.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct IcsEvent {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub location: Option<String>,
+    /// The organizer's `CN`, or else the local part of their address.
+    pub organizer: Option<String>,
+    pub start: Option<IcsDateTime>,
+    pub end: Option<IcsDateTime>,
+    pub attendees: i32,
+    pub cancelled: bool,
+    pub recurring: bool,
+}
+
+/// A `DTSTART` / `DTEND` value. `TZID` times are read as UTC instants when their zone
+/// is an IANA name, as floating times otherwise.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IcsDateTime {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+impl IcsDateTime {
+    fn parse(value: &str, params: &[(String, String)]) -> Option<Self> {
+        let all_day = params
+            .iter()
+            .any(|(name, value)| name == "VALUE" && value.eq_ignore_ascii_case("DATE"));
+        if all_day || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(IcsDateTime::Date);
+        }
+
+        let zone = params
+            .iter()
+            .find(|(name, _)| name == "TZID")
+            .and_then(|(_, tzid)| tzid.parse::<Tz>().ok());
+
+        match value.strip_suffix('Z') {
+            Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(|at| IcsDateTime::Utc(at.and_utc())),
+            None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(|at| match zone {
+                    Some(zone) => IcsDateTime::Utc(instant_in(zone, at.date(), at.time())),
+                    None => IcsDateTime::Floating(at),
+                }),
+        }
+    }
+
+    /// The date and time on the wall clocks of `time_zone`, `None` for all-day values.
+    /// Floating times are taken as they are.
+    fn local(&self, time_zone: Tz) -> Option<NaiveDateTime> {
+        match self {
+            IcsDateTime::Date(_) => None,
+            IcsDateTime::Floating(at) => Some(*at),
+            IcsDateTime::Utc(at) => Some(at.with_timezone(&time_zone).naive_local()),
+        }
+    }
+}
+
+impl IcsEvent {
+    /// The day and time range the event would book in a room of `time_zone`, or why it
+    /// can't be a booking.
+    pub fn slot(&self, time_zone: Tz) -> Result<(NaiveDate, NaiveTime, NaiveTime), &'static str> {
+        if self.cancelled {
+            return Err("Cancelled event");
+        }
+        if self.recurring {
+            return Err("Recurring events aren't imported, book them as a series");
+        }
+        if matches!(self.start, Some(IcsDateTime::Date(_))) {
+            return Err("All-day event");
+        }
+        let (Some(start), Some(end)) = (self.start, self.end) else {
+            return Err("Event without both a start and an end");
+        };
+        let (Some(start), Some(end)) = (start.local(time_zone), end.local(time_zone)) else {
+            return Err("All-day event");
+        };
+        if start.date() != end.date() {
+            return Err("Event spanning several days");
+        }
+
+        Ok((start.date(), start.time(), end.time()))
+    }
+}
+
+/// Reads the VEVENTs of an iCalendar (RFC 5545) document. Properties of their nested
+/// components, such as alarms, are ignored.
+pub fn parse_events(ics: &str) -> Result<Vec<IcsEvent>, ErrBook> {
+    let unfolded = ics
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    if !unfolded.trim_start().starts_with("BEGIN:VCALENDAR") {
+        return Err(ErrBook::InvalidCalendar);
+    }
+
+    let mut events = Vec::new();
+    let mut event: Option<IcsEvent> = None;
+    // Components open within the current event.
+    let mut nested = 0;
+
+    for line in unfolded.lines().filter(|l| !l.trim().is_empty()) {
+        let (name, params, value) = split_line(line).ok_or(ErrBook::InvalidCalendar)?;
+
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(IcsEvent::default())
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) => events.extend(event.take()),
+            (_, Some(_)) if nested > 0 => {}
+            ("UID", Some(e)) => e.uid = Some(value.to_string()),
+            ("SUMMARY", Some(e)) => e.summary = Some(unescape(value)),
+            ("LOCATION", Some(e)) => e.location = Some(unescape(value)),
+            ("ORGANIZER", Some(e)) => e.organizer = organizer(&params, value),
+            ("DTSTART", Some(e)) => e.start = IcsDateTime::parse(value, &params),
+            ("DTEND", Some(e)) => e.end = IcsDateTime::parse(value, &params),
+            ("ATTENDEE", Some(e)) => e.attendees += 1,
+            ("STATUS", Some(e)) => e.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            ("RRULE" | "RDATE", Some(e)) => e.recurring = true,
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+/// A content line's parameters: upper-cased names, unquoted values.
+type Params = Vec<(String, String)>;
+
+/// Splits a content line into its upper-cased name, parameters and value.
+fn split_line(line: &str) -> Option<(String, Params, &str)> {
+    // The value starts at the first colon outside of a quoted parameter value.
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(k, v)| (k.trim().to_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+
+    Some((name, params, value))
+}
+
+fn organizer(params: &[(String, String)], value: &str) -> Option<String> {
+    let common_name = params
+        .iter()
+        .find(|(name, _)| name == "CN")
+        .map(|(_, cn)| cn.clone());
+
+    common_name.or_else(|| {
+        let address = value
+            .strip_prefix("mailto:")
+            .or(value.strip_prefix("MAILTO:"))?;
+        address.split('@').next().map(str::to_string)
+    })
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::domain::{BookDate, BookSlot, Role, Room, User};
+
+    #[test]
+    fn renders_bookings_as_folded_escaped_events() {
+        let mut room = Room::new("board", 12)
+            .unwrap()
+            .with_approval(true)
+            .with_time_zone(Tz::Europe__Paris);
+        room.id = 1;
+        let user = User::new("jordan").unwrap().with_role(Role::Member);
+        let mut book = Book::new(
+            &room,
+            &user,
+            BookDate::new("10.01.27").unwrap(),
+            BookSlot::new("09:00", "10:30").unwrap(),
+            3,
+            Utc::now(),
+        );
+        book.id = 42;
+        let stamp = Utc.with_ymd_and_hms(2026, 10, 18, 8, 0, 0).unwrap();
+
+        let ics = render_calendar(
+            "BOARD bookings; reserved, for the board and its many committees",
+            &[book],
+            true,
+            stamp,
+        );
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nUID:book-42@room-reservations\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20261018T080000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20270110T080000Z\r\nDTEND:20270110T093000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:BOARD (jordan)\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:3 attendees\\nBooked by jordan\r\n"));
+        assert!(ics.contains("\r\nSTATUS:TENTATIVE\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(ics.replace("\r\n ", "").contains(
+            "\r\nX-WR-CALNAME:BOARD bookings\\; reserved\\, for the board and its many committees\r\n"
+        ));
+    }
+
+    #[test]
+    fn parses_events_skipping_nested_components() {
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:abc-1\r\nSUMMARY:Weekly sync\\, team A\r\n\
+            LOCATION:board\r\nORGANIZER;CN=\"Jordan\":mailto:j.doe@example.com\r\n\
+            ATTENDEE:mailto:a@example.com\r\nATTENDEE:mailto:b@exam\r\n ple.com\r\n\
+            DTSTART;TZID=Europe/Paris:20270110T090000\r\nDTEND;TZID=Europe/Paris:20270110T103000\r\n\
+            BEGIN:VALARM\r\nDESCRIPTION:Reminder\r\nEND:VALARM\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:abc-2\r\nORGANIZER:mailto:sam@example.com\r\n\
+            DTSTART;VALUE=DATE:20270111\r\nDTEND;VALUE=DATE:20270112\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:abc-3\r\nSTATUS:CANCELLED\r\nRRULE:FREQ=WEEKLY\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let events = parse_events(ics).unwrap();
+        assert_eq!(events.len(), 3);
+
+        let sync = &events[0];
+        assert_eq!(sync.uid.as_deref(), Some("abc-1"));
+        assert_eq!(sync.summary.as_deref(), Some("Weekly sync, team A"));
+        assert_eq!(sync.location.as_deref(), Some("board"));
+        assert_eq!(sync.organizer.as_deref(), Some("Jordan"));
+        assert_eq!(sync.attendees, 2);
+        let (date, start, end) = sync.slot(Tz::Europe__Paris).unwrap();
+        assert_eq!(date, NaiveDate::from_ymd_opt(2027, 1, 10).unwrap());
+        assert_eq!(
+            (start.to_string(), end.to_string()),
+            ("09:00:00".into(), "10:30:00".into())
+        );
+        let (_, start, _) = sync.slot(Tz::Asia__Tokyo).unwrap();
+        assert_eq!(start.to_string(), "17:00:00");
+
+        assert_eq!(events[1].organizer.as_deref(), Some("sam"));
+        assert_eq!(events[1].slot(Tz::UTC), Err("All-day event"));
+        assert!(events[2].cancelled && events[2].recurring);
+        assert!(events[2].slot(Tz::UTC).is_err());
+        assert!(matches!(
+            parse_events("not a calendar"),
+            Err(ErrBook::InvalidCalendar)
+        ));
+    }
+}
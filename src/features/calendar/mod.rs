@@ -0,0 +1,6 @@
+pub mod dto;
+pub mod handlers;
+pub mod ics;
+pub mod repo;
+pub mod routes;
+pub mod service;
@@ -0,0 +1,38 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::{
+    app::state::AppState,
+    features::calendar::handlers::{
+        import_calendar, revoke_room_calendar_feed, revoke_user_calendar_feed, room_calendar,
+        room_calendar_feed, user_calendar, user_calendar_feed,
+    },
+    infra::backend::Backend,
+};
+
+/// The `.ics` feeds, authenticated by their own token or a bearer token: mounted outside
+/// of `require_auth` so calendar apps can subscribe to them.
+pub fn calendar_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route("/room/{id}/calendar.ics", get(room_calendar::<B>))
+        .route("/users/{id}/calendar.ics", get(user_calendar::<B>))
+}
+
+pub fn calendar_feed_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route(
+            "/room/{id}/calendar/feed",
+            post(room_calendar_feed::<B>).delete(revoke_room_calendar_feed::<B>),
+        )
+        .route(
+            "/users/{id}/calendar/feed",
+            post(user_calendar_feed::<B>).delete(revoke_user_calendar_feed::<B>),
+        )
+}
+
+/// Reserved to facility managers, checked by the handler.
+pub fn calendar_import_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new().route("/book/import", post(import_calendar::<B>))
+}
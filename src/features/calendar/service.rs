@@ -0,0 +1,72 @@
+use crate::{
+    domain::{CalendarFeed, FeedSubject, User},
+    error::{ErrAuth, ErrRepo, ErrService},
+};
+
+use super::repo::CalendarRepo;
+
+pub struct CalendarService<T> {
+    repo: T,
+}
+
+impl<T> CalendarService<T> {
+    pub fn new(repo: T) -> Self {
+        Self { repo }
+    }
+}
+
+impl<T> CalendarService<T>
+where
+    T: CalendarRepo,
+{
+    /// The feed of `subject`, created on first request.
+    pub async fn feed(
+        &self,
+        caller: &User,
+        subject: FeedSubject,
+    ) -> Result<CalendarFeed, ErrService> {
+        self.check_readable(caller, &subject)?;
+
+        if let Some(feed) = self.repo.get_feed(&subject).await? {
+            return Ok(feed);
+        }
+        match self
+            .repo
+            .insert_feed(&CalendarFeed::new(subject.clone()))
+            .await?
+        {
+            Some(feed) => Ok(feed),
+            // Created meanwhile by a racing request.
+            None => self
+                .repo
+                .get_feed(&subject)
+                .await?
+                .ok_or(ErrService::Repo(ErrRepo::RequestError)),
+        }
+    }
+
+    /// Invalidates the feed's token; subscribers need the next one.
+    pub async fn revoke(&self, caller: &User, subject: &FeedSubject) -> Result<(), ErrService> {
+        if !subject.managed_by(caller) {
+            return Err(ErrService::Auth(ErrAuth::Forbidden));
+        }
+
+        self.repo.delete_feed(subject).await?;
+        Ok(())
+    }
+
+    /// Checks `token` is the one opening the feed of `subject`.
+    pub async fn check_token(&self, subject: &FeedSubject, token: &str) -> Result<(), ErrService> {
+        match self.repo.find_feed(token).await? {
+            Some(feed) if feed.subject == *subject => Ok(()),
+            _ => Err(ErrService::Auth(ErrAuth::UnknownFeed)),
+        }
+    }
+
+    pub fn check_readable(&self, caller: &User, subject: &FeedSubject) -> Result<(), ErrService> {
+        if !subject.readable_by(caller) {
+            return Err(ErrService::Auth(ErrAuth::Forbidden));
+        }
+        Ok(())
+    }
+}
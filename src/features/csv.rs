@@ -0,0 +1,216 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use csv::{ErrorKind, ReaderBuilder, StringRecord, Trim, Writer};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::{ErrService, Problem};
+
+pub const TEXT_CSV: &str = "text/csv; charset=utf-8";
+
+/// Query of the CSV imports: `dry_run` validates every row but writes none.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CsvImportQuery {
+    pub dry_run: Option<bool>,
+}
+
+/// What a CSV import did. Rows are written together, and only when none of them failed.
+#[derive(Serialize, ToSchema)]
+pub struct CsvImportReportDto {
+    pub dry_run: bool,
+    /// Rows read, the header aside.
+    pub rows: usize,
+    /// Rows written: none on a dry run or when any row failed.
+    pub imported: usize,
+    pub errors: Vec<CsvRowErrorDto>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CsvRowErrorDto {
+    /// Line of the row in the file, the header being line 1.
+    pub line: u64,
+    #[schema(example = "ROOM_ALREADY_EXISTS")]
+    pub code: &'static str,
+    pub detail: String,
+}
+
+impl CsvImportReportDto {
+    pub fn new(query: &CsvImportQuery) -> Self {
+        CsvImportReportDto {
+            dry_run: query.dry_run.unwrap_or(false),
+            rows: 0,
+            imported: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Whether the rows read so far may be written.
+    pub fn is_writable(&self) -> bool {
+        !self.dry_run && self.errors.is_empty()
+    }
+
+    pub fn reject(&mut self, line: u64, err: ErrService) {
+        let problem = Problem::from(err);
+        self.errors.push(CsvRowErrorDto {
+            line,
+            code: problem.code(),
+            detail: problem.detail().to_string(),
+        });
+    }
+
+    /// Reads the rows of `body`, a CSV document with a header line naming the columns,
+    /// along with their line. Unreadable rows are rejected on the spot.
+    pub fn read_rows<T: DeserializeOwned>(&mut self, body: &str) -> Vec<(u64, T)> {
+        let mut reader = ReaderBuilder::new()
+            .trim(Trim::All)
+            .flexible(true)
+            .from_reader(body.as_bytes());
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(err) => {
+                self.reject_unreadable(1, &err);
+                return Vec::new();
+            }
+        };
+
+        let mut rows = Vec::new();
+        let mut record = StringRecord::new();
+        loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    let line = err.position().map_or(0, |p| line_at(body, p.byte()));
+                    self.reject_unreadable(line, &err);
+                    break;
+                }
+            }
+            self.rows += 1;
+            let line = record.position().map_or(0, |p| line_at(body, p.byte()));
+            match record.deserialize::<T>(Some(&headers)) {
+                Ok(row) => rows.push((line, row)),
+                Err(err) => self.reject_unreadable(line, &err),
+            }
+        }
+
+        rows
+    }
+
+    fn reject_unreadable(&mut self, line: u64, err: &csv::Error) {
+        let detail = match err.kind() {
+            ErrorKind::Deserialize { err, .. } => err.to_string(),
+            _ => err.to_string(),
+        };
+        self.errors.push(CsvRowErrorDto {
+            line,
+            code: "CSV_INVALID_ROW",
+            detail,
+        });
+    }
+}
+
+/// Line of the row starting at `offset`, blank lines before it aside: the reader's own
+/// count leaves out the blank lines it skipped.
+fn line_at(body: &str, offset: u64) -> u64 {
+    let bytes = body.as_bytes();
+    let mut offset = (offset as usize).min(bytes.len());
+    while matches!(bytes.get(offset), Some(b'\r' | b'\n')) {
+        offset += 1;
+    }
+
+    bytes[..offset].iter().filter(|&&b| b == b'\n').count() as u64 + 1
+}
+
+/// 200 once written or found writable, 422 when any row failed.
+impl IntoResponse for CsvImportReportDto {
+    fn into_response(mut self) -> Response {
+        self.errors.sort_by_key(|e| e.line);
+        let status = if self.errors.is_empty() {
+            StatusCode::OK
+        } else {
+            StatusCode::UNPROCESSABLE_ENTITY
+        };
+
+        (status, Json(self)).into_response()
+    }
+}
+
+/// Renders `rows` as a CSV document, with a header line named after their fields.
+pub fn write_rows<T: Serialize>(rows: impl IntoIterator<Item = T>) -> Result<String, ErrService> {
+    let mut writer = Writer::from_writer(Vec::new());
+    for row in rows {
+        writer
+            .serialize(row)
+            .map_err(|e| ErrService::IO(std::io::Error::other(e)))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ErrService::IO(e.into_error()))?;
+
+    String::from_utf8(bytes).map_err(|e| ErrService::IO(std::io::Error::other(e)))
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    use super::{CsvImportQuery, CsvImportReportDto, write_rows};
+
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    struct Row {
+        name: String,
+        seats: i32,
+        floor: Option<i32>,
+    }
+
+    #[test]
+    fn reads_rows_with_their_line_and_rejects_unreadable_ones() {
+        let mut report = CsvImportReportDto::new(&CsvImportQuery { dry_run: None });
+        let body = "name,seats,floor\n alpha , 8 ,\nbeta,many,2\n\ngamma,4,3\n";
+
+        let rows: Vec<(u64, Row)> = report.read_rows(body);
+
+        assert_eq!(report.rows, 3);
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    2,
+                    Row {
+                        name: "alpha".into(),
+                        seats: 8,
+                        floor: None
+                    }
+                ),
+                (
+                    5,
+                    Row {
+                        name: "gamma".into(),
+                        seats: 4,
+                        floor: Some(3)
+                    }
+                ),
+            ]
+        );
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 3);
+        assert_eq!(report.errors[0].code, "CSV_INVALID_ROW");
+        assert!(!report.is_writable());
+    }
+
+    #[test]
+    fn writes_a_header_then_one_line_per_row() {
+        let csv = write_rows([Row {
+            name: "a, b".into(),
+            seats: 2,
+            floor: None,
+        }])
+        .unwrap();
+
+        assert_eq!(csv, "name,seats,floor\n\"a, b\",2,\n");
+    }
+}
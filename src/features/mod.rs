@@ -1,3 +1,8 @@
+pub mod auth;
 pub mod book;
+pub mod calendar;
+pub mod csv;
+pub mod page;
 pub mod room;
 pub mod user;
+pub mod waitlist;
@@ -0,0 +1,87 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    domain::{MAX_PAGE_SIZE, Page, PageRequest, SortOrder},
+    error::{ErrRepo, ErrService},
+};
+
+/// Page size when the query doesn't say.
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// One page of a listing. `next_cursor`, passed back as `cursor`, fetches the next one;
+/// it's null on the last page.
+#[derive(Serialize, ToSchema)]
+pub struct PageDto<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> PageDto<T> {
+    /// `key` renders the sort key of an item, the last one's resumes the listing.
+    pub fn from_page<U>(page: Page<U>, key: impl Fn(&U) -> String) -> Self
+    where
+        T: From<U>,
+    {
+        let next_cursor = match page.items.last() {
+            Some(last) if page.has_more => Some(encode_cursor(&key(last))),
+            _ => None,
+        };
+
+        PageDto {
+            items: page.items.into_iter().map(T::from).collect(),
+            next_cursor,
+        }
+    }
+}
+
+/// Validates the paging part of a list query; `parse_key` reads back a key rendered
+/// for [`PageDto::from_page`].
+pub fn page_request<K>(
+    limit: Option<u32>,
+    cursor: Option<&str>,
+    order: Option<&str>,
+    parse_key: impl Fn(&str) -> Option<K>,
+) -> Result<PageRequest<K>, ErrService> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ErrService::Repo(ErrRepo::InvalidPageRequest));
+    }
+
+    let order = match order.map(|o| o.trim().to_lowercase()).as_deref() {
+        None | Some("asc") => SortOrder::Asc,
+        Some("desc") => SortOrder::Desc,
+        Some(_) => return Err(ErrService::Repo(ErrRepo::InvalidPageRequest)),
+    };
+
+    let after = cursor
+        .map(|cursor| {
+            decode_cursor(cursor)
+                .and_then(|key| parse_key(&key))
+                .ok_or(ErrService::Repo(ErrRepo::InvalidCursor))
+        })
+        .transpose()?;
+
+    Ok(PageRequest {
+        limit,
+        after,
+        order,
+    })
+}
+
+/// Cursors are opaque to clients: hex-encoded sort keys, safe in a query string.
+fn encode_cursor(key: &str) -> String {
+    key.bytes().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_cursor(cursor: &str) -> Option<String> {
+    if !cursor.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
@@ -1,33 +1,176 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    domain::{Room, RoomName},
-    error::ErrDomain,
+    domain::{
+        BookDate, BookDateRange, BookSlot, Equipment, PageRequest, Room, RoomCapacity, RoomFilter,
+        RoomLocation, RoomName, parse_time_zone,
+    },
+    error::{ErrBook, ErrDomain, ErrService},
+    features::page::page_request,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateRoomDto {
     pub room_name: String,
+    pub capacity: i32,
+    pub floor: Option<i32>,
+    pub building: Option<String>,
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    /// Members' bookings wait for a facility manager's approval.
+    #[serde(default)]
+    pub requires_approval: bool,
+    /// IANA time zone of the room, `UTC` by default.
+    #[schema(example = "Europe/Paris")]
+    pub time_zone: Option<String>,
 }
 
+/// A row of `POST /room/import.csv`, where `equipment` separates its tags with `|`.
 #[derive(Deserialize)]
+pub struct RoomCsvRow {
+    pub room_name: String,
+    pub capacity: i32,
+    pub floor: Option<i32>,
+    pub building: Option<String>,
+    pub equipment: Option<String>,
+    pub requires_approval: Option<bool>,
+    pub time_zone: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct DeleteRoomByIdDto {
     pub id: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateRoomNameDto {
     pub old_name: String,
     pub new_name: String,
 }
 
-#[derive(Serialize)]
+/// Body of `PATCH /room/{id}`: only the attributes to change.
+#[derive(Deserialize, ToSchema)]
+pub struct PatchRoomDto {
+    pub room_name: Option<String>,
+    pub capacity: Option<i32>,
+    pub floor: Option<i32>,
+    pub building: Option<String>,
+    pub equipment: Option<Vec<String>>,
+    pub requires_approval: Option<bool>,
+    /// Bookings keep their date and times, read in the new zone.
+    #[schema(example = "Europe/Paris")]
+    pub time_zone: Option<String>,
+}
+
+impl PatchRoomDto {
+    /// Applies the attributes given onto `current`.
+    pub fn merge(self, current: Room) -> Result<Room, ErrDomain> {
+        Ok(Room {
+            id: current.id,
+            room_name: match self.room_name {
+                Some(name) => RoomName::new(&name)?,
+                None => current.room_name,
+            },
+            capacity: match self.capacity {
+                Some(seats) => RoomCapacity::new(seats)?,
+                None => current.capacity,
+            },
+            location: RoomLocation::new(
+                self.floor.or(current.location.floor),
+                self.building
+                    .as_deref()
+                    .or(current.location.building.as_deref()),
+            ),
+            equipment: match self.equipment {
+                Some(equipment) => Equipment::parse_all(&equipment)?,
+                None => current.equipment,
+            },
+            requires_approval: self.requires_approval.unwrap_or(current.requires_approval),
+            time_zone: match self.time_zone {
+                Some(time_zone) => parse_time_zone(&time_zone)?,
+                None => current.time_zone,
+            },
+        })
+    }
+}
+
+/// Query of `GET /room/available`: either a single `date` or a `from`..=`to` range,
+/// optionally narrowed to a `start_time`..`end_time` slot (whole days otherwise).
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AvailabilityQuery {
+    pub date: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
+impl AvailabilityQuery {
+    pub fn range(&self) -> Result<BookDateRange, ErrService> {
+        match (&self.date, &self.from, &self.to) {
+            (Some(date), None, None) => Ok(BookDateRange::single(BookDate::new(date)?)),
+            (None, Some(from), Some(to)) => Ok(BookDateRange::new(
+                BookDate::new(from)?,
+                BookDate::new(to)?,
+            )?),
+            _ => Err(ErrService::Book(ErrBook::InvalidDateRange)),
+        }
+    }
+
+    pub fn slot(&self) -> Result<Option<BookSlot>, ErrService> {
+        match (&self.start_time, &self.end_time) {
+            (Some(start), Some(end)) => Ok(Some(BookSlot::new(start, end)?)),
+            (None, None) => Ok(None),
+            _ => Err(ErrService::Book(ErrBook::InvalidTimeFormat)),
+        }
+    }
+}
+
+/// Query of `GET /room`: paging, plus optional `building` and `min_capacity` filters.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListRoomQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub order: Option<String>,
+    pub building: Option<String>,
+    pub min_capacity: Option<i32>,
+}
+
+impl ListRoomQuery {
+    pub fn filter(&self) -> RoomFilter {
+        RoomFilter {
+            building: self.building.clone(),
+            min_capacity: self.min_capacity,
+        }
+    }
+
+    pub fn page(&self) -> Result<PageRequest<RoomName>, ErrService> {
+        page_request(
+            self.limit,
+            self.cursor.as_deref(),
+            self.order.as_deref(),
+            |key| RoomName::new(key).ok(),
+        )
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct RoomDto {
     pub id: i32,
     pub room_name: String,
+    pub capacity: i32,
+    pub floor: Option<i32>,
+    pub building: Option<String>,
+    pub equipment: Vec<String>,
+    pub requires_approval: bool,
+    #[schema(example = "Europe/Paris")]
+    pub time_zone: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UpdateRoomDto {
     pub id: i32,
     pub new_name: String,
@@ -37,6 +180,19 @@ pub struct UpdateRoomDto {
 pub struct RoomRowDto {
     pub id: i32,
     pub room_name: String,
+    pub capacity: i32,
+    pub floor: Option<i32>,
+    pub building: Option<String>,
+    pub equipment: Vec<String>,
+    pub requires_approval: bool,
+    pub time_zone: String,
+}
+
+/// Rooms are in `UTC` unless told otherwise.
+fn time_zone_or_utc(time_zone: Option<&str>) -> Result<chrono_tz::Tz, ErrDomain> {
+    time_zone
+        .filter(|tz| !tz.trim().is_empty())
+        .map_or(Ok(chrono_tz::Tz::UTC), parse_time_zone)
 }
 
 impl TryFrom<CreateRoomDto> for Room {
@@ -46,6 +202,36 @@ impl TryFrom<CreateRoomDto> for Room {
         Ok(Room {
             id: 0,
             room_name: RoomName::new(&dto.room_name)?,
+            capacity: RoomCapacity::new(dto.capacity)?,
+            location: RoomLocation::new(dto.floor, dto.building.as_deref()),
+            equipment: Equipment::parse_all(&dto.equipment)?,
+            requires_approval: dto.requires_approval,
+            time_zone: time_zone_or_utc(dto.time_zone.as_deref())?,
+        })
+    }
+}
+
+impl TryFrom<RoomCsvRow> for Room {
+    type Error = ErrDomain;
+
+    fn try_from(row: RoomCsvRow) -> Result<Self, Self::Error> {
+        let equipment: Vec<&str> = row
+            .equipment
+            .as_deref()
+            .unwrap_or_default()
+            .split('|')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect();
+
+        Ok(Room {
+            id: 0,
+            room_name: RoomName::new(&row.room_name)?,
+            capacity: RoomCapacity::new(row.capacity)?,
+            location: RoomLocation::new(row.floor, row.building.as_deref()),
+            equipment: Equipment::parse_all(&equipment)?,
+            requires_approval: row.requires_approval.unwrap_or(false),
+            time_zone: time_zone_or_utc(row.time_zone.as_deref())?,
         })
     }
 }
@@ -57,6 +243,11 @@ impl TryFrom<RoomRowDto> for Room {
         Ok(Room {
             id: dto.id,
             room_name: RoomName::new(&dto.room_name)?,
+            capacity: RoomCapacity::new(dto.capacity)?,
+            location: RoomLocation::new(dto.floor, dto.building.as_deref()),
+            equipment: Equipment::parse_all(&dto.equipment)?,
+            requires_approval: dto.requires_approval,
+            time_zone: parse_time_zone(&dto.time_zone)?,
         })
     }
 }
@@ -66,6 +257,16 @@ impl From<Room> for RoomDto {
         RoomDto {
             id: room.id,
             room_name: room.room_name.name,
+            capacity: room.capacity.seats,
+            floor: room.location.floor,
+            building: room.location.building,
+            equipment: room
+                .equipment
+                .iter()
+                .map(|e| e.as_str().to_string())
+                .collect(),
+            requires_approval: room.requires_approval,
+            time_zone: room.time_zone.name().to_string(),
         }
     }
 }
@@ -1,42 +1,120 @@
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
 use tracing::info;
 
 use std::sync::Arc;
 
 use crate::{
     app::state::AppState,
-    error::ErrService,
-    features::room::{
-        dto::{CreateRoomDto, RoomDto},
-        service::RoomService,
+    domain::Room,
+    error::{ErrService, ProblemDto},
+    features::{
+        csv::{CsvImportQuery, CsvImportReportDto},
+        page::PageDto,
+        room::{
+            dto::{CreateRoomDto, ListRoomQuery, RoomDto},
+            service::RoomService,
+        },
     },
+    infra::backend::Backend,
 };
 
-use super::dto::{DeleteRoomByIdDto, UpdateRoomDto, UpdateRoomNameDto};
+use super::dto::{
+    AvailabilityQuery, DeleteRoomByIdDto, PatchRoomDto, RoomCsvRow, UpdateRoomDto,
+    UpdateRoomNameDto,
+};
 
 pub type SharedRoomService<T> = Arc<RoomService<T>>;
 
-pub async fn create_room(
-    State(state): State<AppState>,
+#[utoipa::path(
+    post,
+    path = "/room",
+    tag = "room",
+    request_body = CreateRoomDto,
+    responses(
+        (status = 200, description = "The new room", body = RoomDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room name already taken", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid name, capacity, equipment or time zone", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_room<B: Backend>(
+    State(state): State<AppState<B>>,
     Json(payload): Json<CreateRoomDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     info!("create room tag");
     let service = state.room_service;
     info!("create room tag after service");
 
-    let dto = service.add_room(&payload.room_name).await?;
+    let dto = service.add_room(Room::try_from(payload)?).await?;
     info!("create room tag after dto");
 
-    let room_dto = RoomDto {
-        id: dto.id,
-        room_name: dto.room_name.name,
-    };
+    let room_dto = RoomDto::from(dto);
 
     Ok(Json(room_dto))
 }
 
-pub async fn update_room_name(
-    State(state): State<AppState>,
+#[utoipa::path(
+    post,
+    path = "/room/import.csv",
+    tag = "room",
+    params(CsvImportQuery),
+    request_body(content = String, content_type = "text/csv", description = "A header line, then one room per line: `room_name,capacity,floor,building,equipment,requires_approval,time_zone`"),
+    responses(
+        (status = 200, description = "Every room imported, or importable on a dry run", body = CsvImportReportDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "A room name was taken meanwhile; nothing was imported", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Rows in error; nothing was imported", body = CsvImportReportDto),
+    )
+)]
+pub async fn import_rooms<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<CsvImportQuery>,
+    body: String,
+) -> Result<impl IntoResponse, ErrService> {
+    let service = state.room_service;
+
+    let mut report = CsvImportReportDto::new(&query);
+    let mut rooms: Vec<Room> = Vec::new();
+    for (line, row) in report.read_rows::<RoomCsvRow>(&body) {
+        let room = Room::try_from(row)
+            .map_err(ErrService::from)
+            .and_then(|room| service.check_addable(&room, &rooms).map(|_| room));
+        match room {
+            Ok(room) => rooms.push(room),
+            Err(err) => report.reject(line, err),
+        }
+    }
+
+    if report.is_writable() {
+        report.imported = service.add_rooms(rooms).await?.len();
+    }
+
+    Ok(report)
+}
+
+#[utoipa::path(
+    post,
+    path = "/room/update",
+    tag = "room",
+    request_body = UpdateRoomNameDto,
+    responses(
+        (status = 200, description = "The renamed room", body = UpdateRoomDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room name already taken", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn update_room_name<B: Backend>(
+    State(state): State<AppState<B>>,
     Json(payload): Json<UpdateRoomNameDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.room_service;
@@ -44,6 +122,7 @@ pub async fn update_room_name(
     let dto = service
         .update_room(&payload.old_name, &payload.new_name)
         .await?;
+    state.book_service.rename_room(&dto);
 
     let room_dto = UpdateRoomDto {
         id: dto.id,
@@ -53,28 +132,179 @@ pub async fn update_room_name(
     Ok(Json(room_dto))
 }
 
-pub async fn list_room(State(state): State<AppState>) -> Result<impl IntoResponse, ErrService> {
+#[utoipa::path(
+    get,
+    path = "/room/{id}",
+    tag = "room",
+    params(("id" = i32, Path, description = "Room id")),
+    responses(
+        (status = 200, description = "The room", body = RoomDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_room<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    let room = state.room_service.get_room(id)?;
+
+    Ok(Json(RoomDto::from(room)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/room/{id}",
+    tag = "room",
+    params(("id" = i32, Path, description = "Room id")),
+    request_body = CreateRoomDto,
+    responses(
+        (status = 200, description = "The updated room", body = RoomDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room name already taken", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid name, capacity, equipment or time zone", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn put_room<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<i32>,
+    Json(payload): Json<CreateRoomDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let room = state
+        .room_service
+        .replace_room(id, Room::try_from(payload)?)
+        .await?;
+    state.book_service.rename_room(&room);
+
+    Ok(Json(RoomDto::from(room)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/room/{id}",
+    tag = "room",
+    params(("id" = i32, Path, description = "Room id")),
+    request_body = PatchRoomDto,
+    responses(
+        (status = 200, description = "The updated room", body = RoomDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room name already taken", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid name, capacity, equipment or time zone", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_room<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<i32>,
+    Json(payload): Json<PatchRoomDto>,
+) -> Result<impl IntoResponse, ErrService> {
     let service = state.room_service;
-    let rooms = service.list_rooms().await?;
 
-    let dto: Vec<RoomDto> = rooms
-        .into_iter()
-        .map(|u| RoomDto {
-            id: u.id,
-            room_name: u.room_name.name,
-        })
-        .collect();
+    let room = payload.merge(service.get_room(id)?)?;
+    let room = service.replace_room(id, room).await?;
+    state.book_service.rename_room(&room);
 
-    Ok(Json(dto))
+    Ok(Json(RoomDto::from(room)))
 }
 
-pub async fn delete_room(
-    State(state): State<AppState>,
-    Json(payload): Json<DeleteRoomByIdDto>,
+#[utoipa::path(
+    delete,
+    path = "/room/{id}",
+    tag = "room",
+    params(("id" = i32, Path, description = "Room id")),
+    responses(
+        (status = 204, description = "Room deleted, with its cancelled and settled bookings"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room has pending or confirmed bookings", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_room_by_id<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    state.room_service.delete_room_by_id(id).await?;
+    state.book_service.forget_room(id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/room",
+    tag = "room",
+    params(ListRoomQuery),
+    responses(
+        (status = 200, description = "One page of rooms", body = PageDto<RoomDto>),
+        (status = 400, description = "Invalid cursor or paging", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_room<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<ListRoomQuery>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.room_service;
+    let rooms = service.list_rooms(&query.filter(), &query.page()?).await?;
 
-    service.delete_room_by_id(payload.id).await?;
+    let dto: PageDto<RoomDto> = PageDto::from_page(rooms, |room| room.room_name.name.clone());
+
+    Ok(Json(dto))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/room",
+    tag = "room",
+    request_body = DeleteRoomByIdDto,
+    responses(
+        (status = 200, description = "Room deleted, with its cancelled and settled bookings"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Room has pending or confirmed bookings", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn delete_room<B: Backend>(
+    State(state): State<AppState<B>>,
+    Json(payload): Json<DeleteRoomByIdDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    state.room_service.delete_room_by_id(payload.id).await?;
+    state.book_service.forget_room(payload.id);
 
     Ok(())
 }
+
+#[utoipa::path(
+    get,
+    path = "/room/available",
+    tag = "room",
+    params(AvailabilityQuery),
+    responses(
+        (status = 200, description = "Rooms free over the whole range", body = Vec<RoomDto>),
+        (status = 400, description = "Invalid date range or slot", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_available_rooms<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<AvailabilityQuery>,
+) -> Result<impl IntoResponse, ErrService> {
+    let range = query.range()?;
+    let slot = query.slot()?;
+
+    let rooms = state.room_service.list_cache_rooms().await?;
+    let available = state
+        .book_service
+        .available_rooms(rooms, &range, slot.as_ref())
+        .await?;
+
+    let dto: Vec<RoomDto> = available.into_iter().map(RoomDto::from).collect();
+
+    Ok(Json(dto))
+}
@@ -1,69 +1,160 @@
 use crate::{
-    domain::{Room, RoomName},
+    domain::{Page, PageRequest, Room, RoomFilter, RoomName},
     error::{ErrRepo, ErrRoom, ErrService},
     features::room::dto::RoomRowDto,
-    infra::db::DBClient,
+    infra::db::{DBClient, is_foreign_key_violation, keyset},
 };
 
 use async_trait::async_trait;
 
+const INSERT_ROOM: &str = "INSERT INTO rooms (room_name, capacity, floor, building, equipment, requires_approval, time_zone) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id, room_name, capacity, floor, building, equipment, requires_approval, time_zone";
+
 #[async_trait]
 pub trait RoomRepo: Send + Sync {
     async fn insert_room(&self, room: &Room) -> Result<Room, ErrService>;
-    async fn update_room(&self, id: i32, new_name: RoomName) -> Result<Room, ErrService>;
+    /// Inserts every room or none of them.
+    async fn insert_rooms(&self, rooms: &[Room]) -> Result<Vec<Room>, ErrService>;
+    async fn update_room(&self, room: &Room) -> Result<Room, ErrService>;
     async fn delete_room_by_id(&self, room: i32) -> Result<bool, ErrService>;
     async fn get_all_rooms(&self) -> Result<Vec<Room>, ErrService>;
+    async fn list_rooms(
+        &self,
+        filter: &RoomFilter,
+        page: &PageRequest<RoomName>,
+    ) -> Result<Page<Room>, ErrService>;
     async fn get_one_room(&self, room_name: &RoomName) -> Result<Room, ErrService>;
 }
 
 #[async_trait]
 impl RoomRepo for DBClient {
     async fn insert_room(&self, room: &Room) -> Result<Room, ErrService> {
-        let row: RoomRowDto = sqlx::query_as::<_, RoomRowDto>(
-            "INSERT INTO rooms (room_name) VALUES ($1) RETURNING id, room_name",
-        )
-        .bind(&room.room_name.name)
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|_e| ErrRepo::BadRequest)?;
+        let row: RoomRowDto = sqlx::query_as::<_, RoomRowDto>(INSERT_ROOM)
+            .bind(&room.room_name.name)
+            .bind(room.capacity.seats)
+            .bind(room.location.floor)
+            .bind(&room.location.building)
+            .bind(
+                room.equipment
+                    .iter()
+                    .map(|e| e.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .bind(room.requires_approval)
+            .bind(room.time_zone.name())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
 
         let room: Room = row.try_into()?;
         Ok(room)
     }
 
-    async fn update_room(&self, id: i32, new_name: RoomName) -> Result<Room, ErrService> {
+    async fn insert_rooms(&self, rooms: &[Room]) -> Result<Vec<Room>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut inserted = Vec::with_capacity(rooms.len());
+        for room in rooms {
+            let row: RoomRowDto = sqlx::query_as::<_, RoomRowDto>(INSERT_ROOM)
+                .bind(&room.room_name.name)
+                .bind(room.capacity.seats)
+                .bind(room.location.floor)
+                .bind(&room.location.building)
+                .bind(
+                    room.equipment
+                        .iter()
+                        .map(|e| e.as_str())
+                        .collect::<Vec<&str>>(),
+                )
+                .bind(room.requires_approval)
+                .bind(room.time_zone.name())
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|_e| ErrRepo::BadRequest)?;
+            inserted.push(Room::try_from(row)?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(inserted)
+    }
+
+    async fn update_room(&self, room: &Room) -> Result<Room, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
         let row = sqlx::query_as::<_, RoomRowDto>(
-            "UPDATE rooms SET room_name = $1 WHERE id = $2 RETURNING id, room_name",
+            "UPDATE rooms SET room_name = $1, capacity = $2, floor = $3, building = $4, equipment = $5, requires_approval = $7, time_zone = $8 WHERE id = $6 RETURNING id, room_name, capacity, floor, building, equipment, requires_approval, time_zone",
+        )
+        .bind(&room.room_name.name)
+        .bind(room.capacity.seats)
+        .bind(room.location.floor)
+        .bind(&room.location.building)
+        .bind(
+            room.equipment
+                .iter()
+                .map(|e| e.as_str())
+                .collect::<Vec<&str>>(),
         )
-        .bind(new_name.name)
-        .bind(id)
-        .fetch_one(&self.pool)
+        .bind(room.id)
+        .bind(room.requires_approval)
+        .bind(room.time_zone.name())
+        .fetch_one(&mut *tx)
         .await
         .map_err(|_e| ErrRepo::BadRequest)?;
 
-        let room = Room {
-            id: row.id,
-            room_name: RoomName::new(&row.room_name)?,
-        };
+        // Bookings keep their wall-clock times, now read in the room's zone. They all
+        // move at once: overlaps are checked once they have.
+        sqlx::query("SET CONSTRAINTS books_no_overlap DEFERRED")
+            .execute(&mut *tx)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+        sqlx::query(
+            "UPDATE books SET starts_at = (date + start_time) AT TIME ZONE $1, ends_at = (date + end_time) AT TIME ZONE $1 WHERE room_id = $2",
+        )
+        .bind(room.time_zone.name())
+        .bind(room.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+
+        let room: Room = row.try_into()?;
 
         Ok(room)
     }
 
-    async fn delete_room_by_id(&self, room_name: i32) -> Result<bool, ErrService> {
+    async fn delete_room_by_id(&self, room_id: i32) -> Result<bool, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        sqlx::query(
+            "DELETE FROM books WHERE room_id = $1 AND status NOT IN ('pending', 'confirmed')",
+        )
+        .bind(room_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
         let row = sqlx::query("DELETE FROM rooms WHERE id = $1")
-            .bind(room_name)
-            .execute(&self.pool)
+            .bind(room_id)
+            .execute(&mut *tx)
             .await
-            .map_err(|_e| ErrRepo::BadRequest)?;
+            .map_err(|e| {
+                if is_foreign_key_violation(&e) {
+                    ErrService::Room(ErrRoom::HasBookings)
+                } else {
+                    ErrService::Repo(ErrRepo::BadRequest)
+                }
+            })?;
 
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
         Ok(row.rows_affected() != 0)
     }
 
     async fn get_all_rooms(&self) -> Result<Vec<Room>, ErrService> {
-        let row = sqlx::query_as::<_, RoomRowDto>("SELECT id, room_name FROM rooms")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query_as::<_, RoomRowDto>(
+            "SELECT id, room_name, capacity, floor, building, equipment, requires_approval, time_zone FROM rooms",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
 
         let rooms: Vec<Room> = row
             .into_iter()
@@ -73,6 +164,35 @@ impl RoomRepo for DBClient {
         Ok(rooms)
     }
 
+    async fn list_rooms(
+        &self,
+        filter: &RoomFilter,
+        page: &PageRequest<RoomName>,
+    ) -> Result<Page<Room>, ErrService> {
+        let (cmp, dir) = keyset(page.order);
+        let rows = sqlx::query_as::<_, RoomRowDto>(&format!(
+            "SELECT id, room_name, capacity, floor, building, equipment, requires_approval, time_zone FROM rooms \
+            WHERE ($1::text IS NULL OR building = $1) \
+            AND ($2::int IS NULL OR capacity >= $2) \
+            AND ($3::text IS NULL OR room_name {cmp} $3) \
+            ORDER BY room_name {dir} LIMIT $4"
+        ))
+        .bind(&filter.building)
+        .bind(filter.min_capacity)
+        .bind(page.after.as_ref().map(|r| &r.name))
+        .bind(i64::from(page.limit) + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let rooms: Vec<Room> = rows
+            .into_iter()
+            .map(|dto| dto.try_into().map_err(|_| ErrRepo::DoesntExist))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Page::from_overfetched(rooms, page.limit))
+    }
+
     async fn get_one_room(&self, room_name: &RoomName) -> Result<Room, ErrService> {
         let row = sqlx::query_as::<_, RoomRowDto>("SELECT * FROM rooms WHERE room_name = $1")
             .bind(&room_name.name)
@@ -81,10 +201,7 @@ impl RoomRepo for DBClient {
             .map_err(|_e| ErrRepo::BadRequest)?;
 
         if let Some(raw_room) = row {
-            let room = Room {
-                room_name: RoomName::new(&raw_room.room_name)?,
-                id: raw_room.id,
-            };
+            let room: Room = raw_room.try_into()?;
             Ok(room)
         } else {
             Err(ErrService::Room(ErrRoom::RoomNotFound))
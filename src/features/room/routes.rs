@@ -1,19 +1,212 @@
+// The legacy routes are still served, see `LEGACY_ROUTES`.
+#![allow(deprecated)]
+
 use axum::{
     Router,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
 };
 
 use crate::{
     app::state::AppState,
     features::room::handlers::{create_room, delete_room, list_room},
+    infra::backend::Backend,
 };
 
-use super::handlers::update_room_name;
+use super::handlers::{
+    delete_room_by_id, get_room, import_rooms, list_available_rooms, patch_room, put_room,
+    update_room_name,
+};
 
-pub fn room_routes() -> Router<AppState> {
+pub fn room_routes<B: Backend>() -> Router<AppState<B>> {
     Router::new()
-        .route("/room", post(create_room))
-        .route("/room/update", post(update_room_name))
-        .route("/room", get(list_room))
-        .route("/room", delete(delete_room))
+        .route("/room", get(list_room::<B>))
+        .route("/room/{id}", get(get_room::<B>))
+        .route("/room/available", get(list_available_rooms::<B>))
+}
+
+/// Routes reserved to admins.
+pub fn room_admin_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route("/room", post(create_room::<B>))
+        .route("/room/import.csv", post(import_rooms::<B>))
+        .route(
+            "/room/{id}",
+            put(put_room::<B>)
+                .patch(patch_room::<B>)
+                .delete(delete_room_by_id::<B>),
+        )
+}
+
+/// Body-addressed admin routes superseded by `/room/{id}`, served while `LEGACY_ROUTES` is on.
+pub fn room_legacy_admin_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route("/room/update", post(update_room_name::<B>))
+        .route("/room", delete(delete_room::<B>))
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::app::harness::{TestApp, day};
+
+    #[tokio::test]
+    async fn rooms_are_created_by_admins() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let member = app.signup("jordan").await;
+
+        let response = app
+            .post(
+                "/room",
+                &admin,
+                json!({ "room_name": "alpha", "capacity": 8, "equipment": ["projector"] }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["room_name"], "ALPHA");
+        assert_eq!(response.body["equipment"], json!(["projector"]));
+
+        let response = app
+            .post(
+                "/room",
+                &member,
+                json!({ "room_name": "beta", "capacity": 4 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(response.code(), "AUTH_FORBIDDEN");
+
+        for (body, status, code) in [
+            (
+                json!({ "room_name": "alpha", "capacity": 4 }),
+                StatusCode::CONFLICT,
+                "ROOM_ALREADY_EXISTS",
+            ),
+            (
+                json!({ "room_name": "beta", "capacity": 0 }),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "ROOM_INVALID_CAPACITY",
+            ),
+            (
+                json!({ "room_name": "beta", "capacity": 4, "equipment": ["piano"] }),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "ROOM_INVALID_EQUIPMENT",
+            ),
+        ] {
+            let response = app.post("/room", &admin, body).await;
+            assert_eq!((response.status, response.code()), (status, code));
+        }
+
+        let response = app.get("/room", &member).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["items"].as_array().unwrap().len(), 1);
+        let response = app.get("/room/42", &member).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn rooms_with_bookings_are_kept() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let alpha = app.create_room(&admin, "alpha", 8).await;
+        let beta = app.create_room(&admin, "beta", 4).await;
+        let date = day(1);
+        app.book(&admin, "alpha", &date, "09:00", "10:00").await;
+
+        let response = app
+            .get(
+                &format!("/room/available?date={date}&start_time=09:30&end_time=10:30"),
+                &admin,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.body,
+            json!([{
+                "id": beta,
+                "room_name": "BETA",
+                "capacity": 4,
+                "floor": null,
+                "building": null,
+                "equipment": [],
+                "requires_approval": false,
+                "time_zone": "UTC",
+            }])
+        );
+
+        let response = app.delete(&format!("/room/{alpha}"), &admin).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.code(), "ROOM_HAS_BOOKINGS");
+        let response = app.delete(&format!("/room/{beta}"), &admin).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn bookings_are_in_the_time_zone_of_their_room() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let body = json!({ "room_name": "alpha", "capacity": 8, "time_zone": "Asia/Tokyo" });
+        let response = app.post("/room", &admin, body).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["time_zone"], "Asia/Tokyo");
+        let alpha = response.body["id"].as_i64().unwrap();
+
+        let body = json!({ "room_name": "beta", "capacity": 4, "time_zone": "Tokyo" });
+        let response = app.post("/room", &admin, body).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.code(), "ROOM_INVALID_TIME_ZONE");
+        assert_eq!(response.body["errors"][0]["field"], "time_zone");
+
+        let response = app.book(&admin, "alpha", &day(2), "09:00", "10:00").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["time_zone"], "Asia/Tokyo");
+        let starts_at = response.body["starts_at"].as_str().unwrap();
+        assert!(starts_at.ends_with("T09:00:00+09:00"), "{starts_at}");
+        let book = format!("/book/{}", response.body["id"]);
+
+        // the booking keeps its wall-clock times in the room's new zone
+        let response = app
+            .patch(
+                &format!("/room/{alpha}"),
+                &admin,
+                json!({ "time_zone": "America/New_York" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = app.get(&book, &admin).await;
+        assert_eq!(response.body["time_zone"], "America/New_York");
+        assert_eq!(response.body["start_time"], "09:00:00");
+        let starts_at = response.body["starts_at"].as_str().unwrap();
+        assert!(starts_at.contains("T09:00:00-0"), "{starts_at}");
+    }
+
+    #[tokio::test]
+    async fn availability_needs_an_ordered_date_range() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        app.create_room(&admin, "alpha", 8).await;
+        app.create_room(&admin, "beta", 4).await;
+        app.book(&admin, "alpha", &day(2), "09:00", "10:00").await;
+
+        let response = app
+            .get(
+                &format!("/room/available?from={}&to={}", day(3), day(1)),
+                &admin,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.code(), "BOOK_INVALID_DATE_RANGE");
+
+        let response = app
+            .get(
+                &format!("/room/available?from={}&to={}", day(1), day(3)),
+                &admin,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body[0]["room_name"], "BETA");
+        assert_eq!(response.body.as_array().unwrap().len(), 1);
+    }
 }
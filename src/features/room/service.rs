@@ -1,6 +1,6 @@
 use super::repo::RoomRepo;
 use crate::{
-    domain::{Room, RoomName},
+    domain::{Page, PageRequest, Room, RoomFilter, RoomName},
     error::{ErrRepo, ErrRoom, ErrService},
 };
 
@@ -23,8 +23,7 @@ impl<T> RoomService<T> {
 }
 
 impl<T: RoomRepo> RoomService<T> {
-    pub async fn add_room(&self, room: &str) -> Result<Room, ErrService> {
-        let room: Room = Room::new(room)?;
+    pub async fn add_room(&self, room: Room) -> Result<Room, ErrService> {
         info!("cache lenght : {}", self.cache.len());
 
         if self.is_exist_room(&room.room_name).await? {
@@ -41,27 +40,56 @@ impl<T: RoomRepo> RoomService<T> {
         Ok(room)
     }
 
+    /// Checks `room` may be added along with `pending`, the rooms to be added with it.
+    pub fn check_addable(&self, room: &Room, pending: &[Room]) -> Result<(), ErrService> {
+        let taken = self.cache.iter().any(|r| r.room_name == room.room_name)
+            || pending.iter().any(|r| r.room_name == room.room_name);
+        if taken {
+            return Err(ErrService::Room(ErrRoom::AlreadyExist));
+        }
+
+        Ok(())
+    }
+
+    /// Adds every room or none of them.
+    pub async fn add_rooms(&self, rooms: Vec<Room>) -> Result<Vec<Room>, ErrService> {
+        for (i, room) in rooms.iter().enumerate() {
+            self.check_addable(room, &rooms[..i])?;
+        }
+
+        let rooms = self.repo.insert_rooms(&rooms).await?;
+        for room in &rooms {
+            self.cache.insert(room.clone());
+        }
+        info!(
+            "{} rooms added, cache has now {} entries",
+            rooms.len(),
+            self.cache.len()
+        );
+        Ok(rooms)
+    }
+
     pub async fn update_room(&self, old_room: &str, new_room: &str) -> Result<Room, ErrService> {
-        let old_room = Room::new(old_room)?;
-        let new_room = Room::new(new_room)?;
+        let old_room = RoomName::new(old_room)?;
+        let new_room = RoomName::new(new_room)?;
 
         let cache_snapshot: Vec<_> = self.cache.iter().map(|r| r.clone()).collect();
 
         let o_room = cache_snapshot
             .iter()
-            .find(|r| r.room_name == old_room.room_name)
+            .find(|r| r.room_name == old_room)
             .ok_or(ErrService::Room(ErrRoom::RoomNotFound))?;
 
-        if cache_snapshot
-            .iter()
-            .any(|r| r.room_name == new_room.room_name)
-        {
+        if cache_snapshot.iter().any(|r| r.room_name == new_room) {
             return Err(ErrService::Room(ErrRoom::AlreadyExist));
         }
 
         let room = self
             .repo
-            .update_room(o_room.id, new_room.room_name.clone())
+            .update_room(&Room {
+                room_name: new_room,
+                ..o_room.clone()
+            })
             .await
             .map_err(|e| {
                 error!("Failed to update room: {:?}", e);
@@ -69,10 +97,37 @@ impl<T: RoomRepo> RoomService<T> {
             })?;
 
         self.cache.remove(o_room);
-        self.cache.insert(Room {
-            id: o_room.id,
-            room_name: new_room.room_name,
-        });
+        self.cache.insert(room.clone());
+
+        Ok(room)
+    }
+
+    pub fn get_room(&self, room_id: i32) -> Result<Room, ErrService> {
+        self.get_room_by_id_on_cache(room_id)?
+            .ok_or(ErrService::Room(ErrRoom::RoomNotFound))
+    }
+
+    /// Overwrites every attribute of room `room_id` with those of `room`.
+    pub async fn replace_room(&self, room_id: i32, room: Room) -> Result<Room, ErrService> {
+        let old_room = self.get_room(room_id)?;
+        if self
+            .cache
+            .iter()
+            .any(|r| r.id != room_id && r.room_name == room.room_name)
+        {
+            return Err(ErrService::Room(ErrRoom::AlreadyExist));
+        }
+
+        let room = self
+            .repo
+            .update_room(&Room {
+                id: room_id,
+                ..room
+            })
+            .await?;
+
+        self.cache.remove(&old_room);
+        self.cache.insert(room.clone());
 
         Ok(room)
     }
@@ -95,8 +150,12 @@ impl<T: RoomRepo> RoomService<T> {
         }
     }
 
-    pub async fn list_rooms(&self) -> Result<Vec<Room>, ErrService> {
-        self.repo.get_all_rooms().await
+    pub async fn list_rooms(
+        &self,
+        filter: &RoomFilter,
+        page: &PageRequest<RoomName>,
+    ) -> Result<Page<Room>, ErrService> {
+        self.repo.list_rooms(filter, page).await
     }
 
     pub async fn list_cache_rooms(&self) -> Result<Vec<Room>, ErrService> {
@@ -125,13 +184,20 @@ impl<T: RoomRepo> RoomService<T> {
             .map(|r| r.clone()))
     }
 
+    pub fn get_room_by_name_on_cache(
+        &self,
+        room_name: &RoomName,
+    ) -> Result<Option<Room>, ErrService> {
+        Ok(self
+            .cache
+            .iter()
+            .find(|x| x.room_name == *room_name)
+            .map(|r| r.clone()))
+    }
+
     pub async fn populate_cache(&self) -> Result<(), ErrService> {
         let rooms = self.repo.get_all_rooms().await?;
-        rooms.into_iter().for_each(|e| {
-            let room = Room {
-                id: e.id,
-                room_name: e.room_name,
-            };
+        rooms.into_iter().for_each(|room| {
             self.cache.insert(room);
         });
 
@@ -1,17 +1,27 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    domain::{User, UserID, UserName},
-    error::ErrDomain,
+    domain::{PageRequest, Role, User, UserFilter, UserID, UserName},
+    error::{ErrDomain, ErrService},
+    features::page::page_request,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateUserDto {
     pub user_name: String,
 }
 
+/// A row of `POST /users/import.csv`; users are members unless `role` says otherwise.
 #[derive(Deserialize)]
+pub struct UserCsvRow {
+    pub user_name: String,
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateUserNameDto {
     pub old_name: String,
     pub new_name: String,
@@ -22,13 +32,71 @@ pub struct DeleteUserByIdDto {
     pub user_id: i32,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateUserRoleDto {
+    pub user_name: String,
+    pub role: String,
+}
+
+/// Body of `PUT /users/{id}`.
+#[derive(Deserialize, ToSchema)]
+pub struct ReplaceUserDto {
+    pub user_name: String,
+    pub role: String,
+}
+
+/// Body of `PATCH /users/{id}`: only the attributes to change.
+#[derive(Deserialize, ToSchema)]
+pub struct PatchUserDto {
+    pub user_name: Option<String>,
+    pub role: Option<String>,
+}
+
+/// Query of `GET /users`: paging, plus an optional `role` filter.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUserQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub order: Option<String>,
+    pub role: Option<String>,
+}
+
+impl ListUserQuery {
+    pub fn filter(&self) -> Result<UserFilter, ErrService> {
+        Ok(UserFilter {
+            role: self.role.as_deref().map(Role::new).transpose()?,
+        })
+    }
+
+    pub fn page(&self) -> Result<PageRequest<UserName>, ErrService> {
+        page_request(
+            self.limit,
+            self.cursor.as_deref(),
+            self.order.as_deref(),
+            |key| UserName::new(key).ok(),
+        )
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct UserDto {
     pub user_id: Uuid,
     pub user_name: String,
+    pub role: &'static str,
+}
+
+/// A freshly signed-up user, with the token to authenticate their next requests.
+#[derive(Serialize, ToSchema)]
+pub struct CreatedUserDto {
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub role: &'static str,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UpdateUserDto {
     pub user_id: Uuid,
     pub new_name: String,
@@ -42,6 +110,7 @@ pub struct UserUpdateRowDto {
 pub struct UserRowDto {
     pub user_id: Uuid,
     pub user_name: String,
+    pub role: String,
 }
 
 impl TryFrom<CreateUserDto> for User {
@@ -51,6 +120,22 @@ impl TryFrom<CreateUserDto> for User {
         Ok(User {
             user_id: UserID::new(),
             user_name: UserName::new(&dto.user_name)?,
+            role: Role::default(),
+        })
+    }
+}
+
+impl TryFrom<UserCsvRow> for User {
+    type Error = ErrDomain;
+
+    fn try_from(row: UserCsvRow) -> Result<Self, Self::Error> {
+        Ok(User {
+            user_id: UserID::new(),
+            user_name: UserName::new(&row.user_name)?,
+            role: match row.role.as_deref().filter(|r| !r.is_empty()) {
+                Some(role) => Role::new(role)?,
+                None => Role::default(),
+            },
         })
     }
 }
@@ -62,6 +147,7 @@ impl TryFrom<UserRowDto> for User {
         Ok(User {
             user_id: UserID { id: dto.user_id },
             user_name: UserName::new(&dto.user_name)?,
+            role: Role::new(&dto.role)?,
         })
     }
 }
@@ -71,6 +157,7 @@ impl From<User> for UserDto {
         UserDto {
             user_id: user.user_id.id,
             user_name: user.user_name.name,
+            role: user.role.as_str(),
         }
     }
 }
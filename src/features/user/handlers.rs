@@ -1,38 +1,121 @@
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
 
 use std::sync::Arc;
+use uuid::Uuid;
 
 use crate::{
     app::state::AppState,
-    error::ErrService,
-    features::user::{
-        dto::{CreateUserDto, UpdateUserDto, UserDto},
-        service::UserService,
+    domain::{User, UserID},
+    error::{ErrService, ProblemDto},
+    features::{
+        csv::{CsvImportQuery, CsvImportReportDto},
+        page::PageDto,
+        user::{
+            dto::{CreateUserDto, CreatedUserDto, ListUserQuery, UpdateUserDto, UserDto},
+            service::UserService,
+        },
     },
+    infra::backend::Backend,
 };
 
-use super::dto::UpdateUserNameDto;
+use super::dto::{PatchUserDto, ReplaceUserDto, UpdateUserNameDto, UpdateUserRoleDto, UserCsvRow};
 
 pub type SharedUserService<T> = Arc<UserService<T>>;
 
-pub async fn create_user(
-    State(state): State<AppState>,
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "user",
+    request_body = CreateUserDto,
+    security(()),
+    responses(
+        (status = 200, description = "The new user and their first token", body = CreatedUserDto),
+        (status = 409, description = "User name already taken", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid user name", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_user<B: Backend>(
+    State(state): State<AppState<B>>,
     Json(payload): Json<CreateUserDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.user_service;
 
     let dto = service.add_user(&payload.user_name).await?;
 
-    let user_dto = UserDto {
+    let token = state.auth_service.issue_token(&dto)?;
+
+    let user_dto = CreatedUserDto {
         user_id: dto.user_id.id,
         user_name: dto.user_name.name,
+        role: dto.role.as_str(),
+        token: token.token,
+        expires_at: token.expires_at,
     };
 
     Ok(Json(user_dto))
 }
 
-pub async fn update_user(
-    State(state): State<AppState>,
+#[utoipa::path(
+    post,
+    path = "/users/import.csv",
+    tag = "user",
+    params(CsvImportQuery),
+    request_body(content = String, content_type = "text/csv", description = "A header line, then one user per line: `user_name,role`"),
+    responses(
+        (status = 200, description = "Every user imported, or importable on a dry run", body = CsvImportReportDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "A user name was taken meanwhile; nothing was imported", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Rows in error; nothing was imported", body = CsvImportReportDto),
+    )
+)]
+pub async fn import_users<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<CsvImportQuery>,
+    body: String,
+) -> Result<impl IntoResponse, ErrService> {
+    let service = state.user_service;
+
+    let mut report = CsvImportReportDto::new(&query);
+    let mut users: Vec<User> = Vec::new();
+    for (line, row) in report.read_rows::<UserCsvRow>(&body) {
+        let user = User::try_from(row)
+            .map_err(ErrService::from)
+            .and_then(|user| service.check_addable(&user, &users).map(|_| user));
+        match user {
+            Ok(user) => users.push(user),
+            Err(err) => report.reject(line, err),
+        }
+    }
+
+    if report.is_writable() {
+        report.imported = service.add_users(users).await?.len();
+    }
+
+    Ok(report)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/update",
+    tag = "user",
+    request_body = UpdateUserNameDto,
+    responses(
+        (status = 200, description = "The renamed user", body = UpdateUserDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "User name already taken", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn update_user<B: Backend>(
+    State(state): State<AppState<B>>,
     Json(payload): Json<UpdateUserNameDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.user_service;
@@ -40,6 +123,7 @@ pub async fn update_user(
     let dto = service
         .update_user(&payload.old_name, &payload.new_name)
         .await?;
+    state.book_service.rename_user(&dto);
 
     let user_dto = UpdateUserDto {
         user_id: dto.user_id.id,
@@ -49,28 +133,179 @@ pub async fn update_user(
     Ok(Json(user_dto))
 }
 
-pub async fn list_users(State(state): State<AppState>) -> Result<impl IntoResponse, ErrService> {
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "user",
+    params(ListUserQuery),
+    responses(
+        (status = 200, description = "One page of users", body = PageDto<UserDto>),
+        (status = 400, description = "Invalid cursor or paging", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Unknown role", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_users<B: Backend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<ListUserQuery>,
+) -> Result<impl IntoResponse, ErrService> {
     let service = state.user_service;
-    let users = service.list_users().await?;
+    let users = service.list_users(&query.filter()?, &query.page()?).await?;
 
-    let dto: Vec<UserDto> = users
-        .into_iter()
-        .map(|u| UserDto {
-            user_id: u.user_id.id,
-            user_name: u.user_name.name,
-        })
-        .collect();
+    let dto: PageDto<UserDto> = PageDto::from_page(users, |user| user.user_name.name.clone());
 
     Ok(Json(dto))
 }
 
-pub async fn delete_user(
-    State(state): State<AppState>,
-    Json(payload): Json<CreateUserDto>,
+#[utoipa::path(
+    post,
+    path = "/users/role",
+    tag = "user",
+    request_body = UpdateUserRoleDto,
+    responses(
+        (status = 200, description = "The user with their new role", body = UserDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Unknown role", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn update_user_role<B: Backend>(
+    State(state): State<AppState<B>>,
+    Json(payload): Json<UpdateUserRoleDto>,
 ) -> Result<impl IntoResponse, ErrService> {
     let service = state.user_service;
 
-    service.delete_user_by_name(&payload.user_name).await?;
+    let user = service.set_role(&payload.user_name, &payload.role).await?;
+
+    Ok(Json(UserDto::from(user)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "user",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "The user", body = UserDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_user<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ErrService> {
+    let user = state.user_service.get_user(id)?;
+
+    Ok(Json(UserDto::from(user)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/users/{id}",
+    tag = "user",
+    params(("id" = Uuid, Path, description = "User id")),
+    request_body = ReplaceUserDto,
+    responses(
+        (status = 200, description = "The updated user", body = UserDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "User name already taken", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid name or role", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn put_user<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReplaceUserDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let user = state
+        .user_service
+        .patch_user(id, Some(&payload.user_name), Some(&payload.role))
+        .await?;
+    state.book_service.rename_user(&user);
+
+    Ok(Json(UserDto::from(user)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{id}",
+    tag = "user",
+    params(("id" = Uuid, Path, description = "User id")),
+    request_body = PatchUserDto,
+    responses(
+        (status = 200, description = "The updated user", body = UserDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "User name already taken", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid name or role", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_user<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<PatchUserDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let user = state
+        .user_service
+        .patch_user(id, payload.user_name.as_deref(), payload.role.as_deref())
+        .await?;
+    state.book_service.rename_user(&user);
+
+    Ok(Json(UserDto::from(user)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}",
+    tag = "user",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 204, description = "User deleted, with their cancelled and settled bookings"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "User has pending or confirmed bookings", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_user_by_id<B: Backend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, ErrService> {
+    state.user_service.delete_user_by_id(id).await?;
+    state.book_service.forget_user(&UserID { id });
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users",
+    tag = "user",
+    request_body = CreateUserDto,
+    responses(
+        (status = 200, description = "User deleted, with their cancelled and settled bookings"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller's role doesn't allow this action", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown user", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "User has pending or confirmed bookings", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+#[deprecated = "superseded by the `/{resource}/{id}` routes"]
+pub async fn delete_user<B: Backend>(
+    State(state): State<AppState<B>>,
+    Json(payload): Json<CreateUserDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let user = state
+        .user_service
+        .delete_user_by_name(&payload.user_name)
+        .await?;
+    state.book_service.forget_user(&user.user_id);
 
     Ok(())
 }
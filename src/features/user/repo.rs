@@ -1,41 +1,79 @@
 use crate::{
-    domain::{User, UserID, UserName},
+    domain::{Page, PageRequest, Role, User, UserFilter, UserName},
     error::{ErrRepo, ErrService, ErrUser},
     features::user::dto::UserRowDto,
-    infra::db::DBClient,
+    infra::db::{DBClient, is_foreign_key_violation, keyset},
 };
 use async_trait::async_trait;
 use uuid::Uuid;
 
+const INSERT_USER: &str = "INSERT INTO users (user_id, user_name, role) VALUES ($1, $2, $3) RETURNING user_id, user_name, role";
+
 #[async_trait]
 pub trait UserRepo: Send + Sync {
     async fn insert_user(&self, user: &User) -> Result<User, ErrService>;
+    /// Inserts every user or none of them.
+    async fn insert_users(&self, users: &[User]) -> Result<Vec<User>, ErrService>;
     async fn update_user(&self, id: Uuid, new_name: UserName) -> Result<User, ErrService>;
     async fn delete_user_by_name(&self, name: UserName) -> Result<bool, ErrService>;
+    async fn delete_user_by_id(&self, id: Uuid) -> Result<bool, ErrService>;
     async fn get_all_users(&self) -> Result<Vec<User>, ErrService>;
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        page: &PageRequest<UserName>,
+    ) -> Result<Page<User>, ErrService>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, ErrService>;
     async fn get_one_user(&self, user_name: &UserName) -> Result<User, ErrService>;
+    async fn update_role(&self, id: Uuid, role: Role) -> Result<User, ErrService>;
+}
+
+/// Users keep their pending and confirmed bookings: those still on record block the delete.
+fn map_delete_err(err: sqlx::Error) -> ErrService {
+    if is_foreign_key_violation(&err) {
+        ErrService::User(ErrUser::HasBookings)
+    } else {
+        ErrService::Repo(ErrRepo::BadRequest)
+    }
 }
 
 #[async_trait]
 impl UserRepo for DBClient {
     async fn insert_user(&self, user: &User) -> Result<User, ErrService> {
-        let row = sqlx::query_as::<_, UserRowDto>(
-            "INSERT INTO users (user_id, user_name) VALUES ($1, $2) RETURNING user_id, user_name",
-        )
-        .bind(user.user_id.id)
-        .bind(&user.user_name.name)
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query_as::<_, UserRowDto>(INSERT_USER)
+            .bind(user.user_id.id)
+            .bind(&user.user_name.name)
+            .bind(user.role.as_str())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
 
         let user: User = row.try_into()?;
         Ok(user)
     }
 
+    async fn insert_users(&self, users: &[User]) -> Result<Vec<User>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut inserted = Vec::with_capacity(users.len());
+        for user in users {
+            let row = sqlx::query_as::<_, UserRowDto>(INSERT_USER)
+                .bind(user.user_id.id)
+                .bind(&user.user_name.name)
+                .bind(user.role.as_str())
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|_e| ErrRepo::BadRequest)?;
+            inserted.push(User::try_from(row)?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(inserted)
+    }
+
     async fn update_user(&self, id: Uuid, new_name: UserName) -> Result<User, ErrService> {
         let row = sqlx::query_as::<_, UserRowDto>(
-            "UPDATE users SET user_name = $1 WHERE user_id = $2 RETURNING user_id, user_name",
+            "UPDATE users SET user_name = $1 WHERE user_id = $2 RETURNING user_id, user_name, role",
         )
         .bind(new_name.name)
         .bind(id)
@@ -43,25 +81,53 @@ impl UserRepo for DBClient {
         .await
         .map_err(|_e| ErrRepo::BadRequest)?;
 
-        let user: User = User {
-            user_id: UserID { id },
-            user_name: UserName::new(&row.user_name)?,
-        };
+        let user: User = row.try_into()?;
         Ok(user)
     }
 
     async fn delete_user_by_name(&self, name: UserName) -> Result<bool, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        sqlx::query(
+            "DELETE FROM books WHERE user_id = (SELECT user_id FROM users WHERE user_name = $1) \
+            AND status NOT IN ('pending', 'confirmed')",
+        )
+        .bind(&name.name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
         let row = sqlx::query("DELETE FROM users WHERE user_name = $1")
             .bind(name.name)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
-            .map_err(|_e| ErrRepo::BadRequest)?;
+            .map_err(map_delete_err)?;
 
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(row.rows_affected() > 0)
+    }
+
+    async fn delete_user_by_id(&self, id: Uuid) -> Result<bool, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        sqlx::query(
+            "DELETE FROM books WHERE user_id = $1 AND status NOT IN ('pending', 'confirmed')",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query("DELETE FROM users WHERE user_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_delete_err)?;
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
         Ok(row.rows_affected() > 0)
     }
 
     async fn get_all_users(&self) -> Result<Vec<User>, ErrService> {
-        let row = sqlx::query_as::<_, UserRowDto>("SELECT user_id, user_name FROM users ")
+        let row = sqlx::query_as::<_, UserRowDto>("SELECT user_id, user_name, role FROM users")
             .fetch_all(&self.pool)
             .await
             .map_err(|_e| ErrRepo::BadRequest)?;
@@ -74,9 +140,36 @@ impl UserRepo for DBClient {
         Ok(users)
     }
 
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        page: &PageRequest<UserName>,
+    ) -> Result<Page<User>, ErrService> {
+        let (cmp, dir) = keyset(page.order);
+        let rows = sqlx::query_as::<_, UserRowDto>(&format!(
+            "SELECT user_id, user_name, role FROM users \
+            WHERE ($1::text IS NULL OR role = $1) \
+            AND ($2::text IS NULL OR user_name {cmp} $2) \
+            ORDER BY user_name {dir} LIMIT $3"
+        ))
+        .bind(filter.role.map(|r| r.as_str()))
+        .bind(page.after.as_ref().map(|u| &u.name))
+        .bind(i64::from(page.limit) + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let users: Vec<User> = rows
+            .into_iter()
+            .map(|dto| dto.try_into().map_err(|_| ErrRepo::DoesntExist))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Page::from_overfetched(users, page.limit))
+    }
+
     async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, ErrService> {
         let row = sqlx::query_as::<_, UserRowDto>(
-            "SELECT user_id, user_name FROM users WHERE user_id = $1",
+            "SELECT user_id, user_name, role FROM users WHERE user_id = $1",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -90,22 +183,35 @@ impl UserRepo for DBClient {
     }
 
     async fn get_one_user(&self, user_name: &UserName) -> Result<User, ErrService> {
-        let row = sqlx::query_as::<_, UserRowDto>("SELECT * FROM users WHERE user_name = $1")
-            .bind(&user_name.name)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query_as::<_, UserRowDto>(
+            "SELECT user_id, user_name, role FROM users WHERE user_name = $1",
+        )
+        .bind(&user_name.name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
 
         if let Some(raw_user) = row {
-            let user = User {
-                user_name: UserName::new(&raw_user.user_name)?,
-                user_id: UserID {
-                    id: raw_user.user_id,
-                },
-            };
+            let user: User = raw_user.try_into()?;
             Ok(user)
         } else {
             Err(ErrService::User(ErrUser::UserNotFound))
         }
     }
+
+    async fn update_role(&self, id: Uuid, role: Role) -> Result<User, ErrService> {
+        let row = sqlx::query_as::<_, UserRowDto>(
+            "UPDATE users SET role = $1 WHERE user_id = $2 RETURNING user_id, user_name, role",
+        )
+        .bind(role.as_str())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        match row {
+            Some(dto) => Ok(User::try_from(dto)?),
+            None => Err(ErrService::User(ErrUser::UserNotFound)),
+        }
+    }
 }
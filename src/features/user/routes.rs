@@ -1,19 +1,173 @@
+// The legacy routes are still served, see `LEGACY_ROUTES`.
+#![allow(deprecated)]
+
 use axum::{
     Router,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
 };
 
 use crate::{
     app::state::AppState,
     features::user::handlers::{create_user, delete_user, list_users},
+    infra::backend::Backend,
+};
+
+use super::handlers::{
+    delete_user_by_id, get_user, import_users, patch_user, put_user, update_user, update_user_role,
 };
 
-use super::handlers::update_user;
+/// Routes open to anonymous callers: signing up returns the new user's first token.
+pub fn signup_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new().route("/users", post(create_user::<B>))
+}
+
+pub fn user_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route("/users", get(list_users::<B>))
+        .route("/users/{id}", get(get_user::<B>))
+}
+
+/// Routes reserved to admins.
+pub fn user_admin_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route("/users/import.csv", post(import_users::<B>))
+        .route(
+            "/users/{id}",
+            put(put_user::<B>)
+                .patch(patch_user::<B>)
+                .delete(delete_user_by_id::<B>),
+        )
+}
 
-pub fn user_routes() -> Router<AppState> {
+/// Body-addressed admin routes superseded by `/users/{id}`, served while `LEGACY_ROUTES` is on.
+pub fn user_legacy_admin_routes<B: Backend>() -> Router<AppState<B>> {
     Router::new()
-        .route("/users", post(create_user))
-        .route("/users/update", post(update_user))
-        .route("/users", get(list_users))
-        .route("/users", delete(delete_user))
+        .route("/users/update", post(update_user::<B>))
+        .route("/users/role", post(update_user_role::<B>))
+        .route("/users", delete(delete_user::<B>))
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::{Method, StatusCode, header};
+    use serde_json::json;
+
+    use crate::app::harness::{Caller, TestApp, day};
+
+    #[tokio::test]
+    async fn signup_is_open_and_never_makes_admins() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let member = app.signup("jordan").await;
+
+        let response = app.get(&format!("/users/{}", admin.id), &member).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["role"], "admin");
+        let response = app.get(&format!("/users/{}", member.id), &member).await;
+        assert_eq!(response.body["role"], "member");
+        let response = app
+            .patch(
+                &format!("/users/{}", member.id),
+                &member,
+                json!({ "role": "admin" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+        // with no admin left, signing up still makes a member
+        let response = app.delete(&format!("/users/{}", admin.id), &admin).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+        let newcomer = app.signup("camille").await;
+        let response = app.get(&format!("/users/{}", newcomer.id), &newcomer).await;
+        assert_eq!(response.body["role"], "member");
+
+        let response = app
+            .request(
+                Method::POST,
+                "/users",
+                None,
+                Some(json!({ "user_name": "jordan" })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.code(), "USER_ALREADY_EXISTS");
+        assert_eq!(
+            response.headers[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        assert_eq!(response.body["errors"][0]["field"], "user_name");
+
+        let response = app
+            .request(
+                Method::POST,
+                "/users",
+                None,
+                Some(json!({ "user_name": "j" })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.code(), "USER_NAME_TOO_SHORT");
+    }
+
+    #[tokio::test]
+    async fn requires_a_valid_token() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+
+        let response = app.request(Method::GET, "/users", None, None).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.code(), "AUTH_MISSING_TOKEN");
+
+        let forged = Caller {
+            token: "not-a-token".to_string(),
+            ..admin.clone()
+        };
+        let response = app.get("/users", &forged).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.code(), "AUTH_INVALID_TOKEN");
+
+        let response = app.get("/users", &admin).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["items"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn users_are_managed_by_admins() {
+        let app = TestApp::new().await;
+        let admin = app.admin.clone();
+        let member = app.signup("jordan").await;
+        let uri = format!("/users/{}", member.id);
+
+        let response = app.patch(&uri, &member, json!({ "role": "admin" })).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(response.code(), "AUTH_FORBIDDEN");
+        let response = app.patch(&uri, &admin, json!({ "role": "wizard" })).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.code(), "USER_INVALID_ROLE");
+
+        app.create_room(&admin, "alpha", 8).await;
+        let book = app.book(&member, "alpha", &day(1), "09:00", "10:00").await;
+        assert_eq!(book.status, StatusCode::OK);
+        let response = app.delete(&uri, &admin).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.code(), "USER_HAS_BOOKINGS");
+
+        // once cancelled, the booking no longer holds the user back and goes with them
+        let book_uri = format!("/book/{}", book.body["id"]);
+        let response = app.delete(&book_uri, &member).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+        let response = app.delete(&uri, &admin).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+        let response = app.get(&book_uri, &admin).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        let response = app.get("/book?status=cancelled", &admin).await;
+        assert_eq!(response.body["items"], json!([]));
+
+        let other = app.signup("camille").await;
+        let response = app.delete(&format!("/users/{}", other.id), &admin).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+        let response = app.get("/users", &other).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.code(), "AUTH_UNKNOWN_USER");
+    }
 }
@@ -1,10 +1,11 @@
 use super::repo::UserRepo;
 use crate::{
-    domain::{User, UserID, UserName},
+    domain::{Page, PageRequest, Role, User, UserFilter, UserID, UserName},
     error::{ErrRepo, ErrService, ErrUser},
 };
 use dashmap::DashSet;
 use tracing::info;
+use uuid::Uuid;
 
 #[derive(Debug)]
 pub struct UserService<T> {
@@ -22,6 +23,8 @@ impl<T> UserService<T> {
 }
 
 impl<T: UserRepo> UserService<T> {
+    /// Signs a user up as a member. Admins are never made by signing up, see
+    /// [`UserService::bootstrap_admin`].
     pub async fn add_user(&self, name: &str) -> Result<User, ErrService> {
         let user: User = User::new(name)?;
 
@@ -35,6 +38,56 @@ impl<T: UserRepo> UserService<T> {
         Ok(user)
     }
 
+    /// Makes `name` an admin, creating the user if missing, so a fresh install can be
+    /// administered (`ADMIN_USER`). Reads the store, not the cache.
+    pub async fn bootstrap_admin(&self, name: &str) -> Result<User, ErrService> {
+        let user_name = UserName::new(name)?;
+
+        let user = match self.repo.get_one_user(&user_name).await {
+            Ok(user) if user.role == Role::Admin => return Ok(user),
+            Ok(user) => {
+                let admin = self.repo.update_role(user.user_id.id, Role::Admin).await?;
+                self.cache.remove(&user);
+                admin
+            }
+            Err(ErrService::User(ErrUser::UserNotFound)) => {
+                self.repo
+                    .insert_user(&User::new(name)?.with_role(Role::Admin))
+                    .await?
+            }
+            Err(e) => return Err(e),
+        };
+        self.cache.insert(user.clone());
+
+        info!("{} is admin", user.user_name.name);
+        Ok(user)
+    }
+
+    /// Checks `user` may be added along with `pending`, the users to be added with it.
+    pub fn check_addable(&self, user: &User, pending: &[User]) -> Result<(), ErrService> {
+        let taken = self.cache.iter().any(|u| u.user_name == user.user_name)
+            || pending.iter().any(|u| u.user_name == user.user_name);
+        if taken {
+            return Err(ErrService::User(ErrUser::AlreadyExist));
+        }
+
+        Ok(())
+    }
+
+    /// Adds every user or none of them, with the roles they come with.
+    pub async fn add_users(&self, users: Vec<User>) -> Result<Vec<User>, ErrService> {
+        for (i, user) in users.iter().enumerate() {
+            self.check_addable(user, &users[..i])?;
+        }
+
+        let users = self.repo.insert_users(&users).await?;
+        for user in &users {
+            self.cache.insert(user.clone());
+        }
+        info!("cache lenght: {}", self.cache.len());
+        Ok(users)
+    }
+
     pub async fn update_user(&self, old_name: &str, new_name: &str) -> Result<User, ErrService> {
         let old_name = UserName::new(&old_name.trim().to_lowercase())?;
         let new_name = UserName::new(&new_name.trim().to_lowercase())?;
@@ -66,20 +119,95 @@ impl<T: UserRepo> UserService<T> {
         }
     }
 
-    pub async fn delete_user_by_name(&self, user_name: &str) -> Result<(), ErrService> {
+    pub async fn set_role(&self, user_name: &str, role: &str) -> Result<User, ErrService> {
         let user_name = UserName::new(user_name)?;
-        let deleted = self.repo.delete_user_by_name(user_name.clone()).await?;
+        let role = Role::new(role)?;
 
-        if deleted {
-            self.cache.retain(|u| u.user_name != user_name);
+        let old_user = self
+            .cache
+            .iter()
+            .find(|u| u.user_name == user_name)
+            .map(|u| u.key().clone())
+            .ok_or(ErrService::User(ErrUser::UserNotFound))?;
+
+        let user = self.repo.update_role(old_user.user_id.id, role).await?;
+        self.cache.remove(&old_user);
+        self.cache.insert(user.clone());
+
+        Ok(user)
+    }
+
+    pub fn get_user(&self, user_id: Uuid) -> Result<User, ErrService> {
+        self.cache
+            .iter()
+            .find(|u| u.user_id.id == user_id)
+            .map(|u| u.key().clone())
+            .ok_or(ErrService::User(ErrUser::UserNotFound))
+    }
+
+    /// Renames and/or changes the role of user `user_id`; absent fields stay as they are.
+    pub async fn patch_user(
+        &self,
+        user_id: Uuid,
+        user_name: Option<&str>,
+        role: Option<&str>,
+    ) -> Result<User, ErrService> {
+        let old_user = self.get_user(user_id)?;
+        let user_name = user_name
+            .map(|name| UserName::new(&name.trim().to_lowercase()))
+            .transpose()?;
+        let role = role.map(Role::new).transpose()?;
+
+        let mut user = old_user.clone();
+        if let Some(user_name) = user_name.filter(|name| *name != old_user.user_name) {
+            if self.is_exist_user(&user_name).await? {
+                return Err(ErrService::User(ErrUser::AlreadyExist));
+            }
+            user = self.repo.update_user(user_id, user_name).await?;
+        }
+        if let Some(role) = role.filter(|role| *role != old_user.role) {
+            user = self.repo.update_role(user_id, role).await?;
+        }
+
+        self.cache.remove(&old_user);
+        self.cache.insert(user.clone());
+
+        Ok(user)
+    }
+
+    pub async fn delete_user_by_id(&self, user_id: Uuid) -> Result<(), ErrService> {
+        if self.repo.delete_user_by_id(user_id).await? {
+            self.cache.retain(|u| u.user_id.id != user_id);
             Ok(())
+        } else {
+            Err(ErrService::User(ErrUser::UserNotFound))
+        }
+    }
+
+    /// Deletes the user named `user_name`, returning who they were.
+    pub async fn delete_user_by_name(&self, user_name: &str) -> Result<User, ErrService> {
+        let user_name = UserName::new(user_name)?;
+        let user = self
+            .cache
+            .iter()
+            .find(|u| u.user_name == user_name)
+            .map(|u| u.key().clone())
+            .ok_or(ErrService::Repo(ErrRepo::DoesntExist))?;
+
+        if self.repo.delete_user_by_name(user_name.clone()).await? {
+            self.cache.retain(|u| u.user_name != user_name);
+            Ok(user)
         } else {
             Err(ErrService::Repo(ErrRepo::DoesntExist))
         }
     }
 
-    pub async fn list_users(&self) -> Result<Vec<User>, ErrService> {
-        self.repo.get_all_users().await
+    pub async fn list_users(
+        &self,
+        filter: &UserFilter,
+        page: &PageRequest<UserName>,
+    ) -> Result<Page<User>, ErrService> {
+        self.repo.list_users(filter, page).await
     }
 
     pub async fn is_exist_user(&self, user_name: &UserName) -> Result<bool, ErrService> {
@@ -92,7 +220,7 @@ impl<T: UserRepo> UserService<T> {
         } else if let Some(value) = self.cache.get(&user.clone()) {
             Ok(value.clone())
         } else {
-            return Err(ErrService::User(ErrUser::UserNotFound));
+            Err(ErrService::User(ErrUser::UserNotFound))
         }
     }
 
@@ -108,13 +236,13 @@ impl<T: UserRepo> UserService<T> {
     }
 
     pub async fn populate_cache(&self) -> Result<(), ErrService> {
-        self.repo.get_all_users().await?.into_iter().for_each(|e| {
-            let user = User {
-                user_id: e.user_id,
-                user_name: e.user_name,
-            };
-            self.cache.insert(user);
-        });
+        self.repo
+            .get_all_users()
+            .await?
+            .into_iter()
+            .for_each(|user| {
+                self.cache.insert(user);
+            });
 
         // let users = self.repo.get_all_users().await?;
         // for element in users {
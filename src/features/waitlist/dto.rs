@@ -0,0 +1,97 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    domain::{BookDate, BookSlot, RoomName, UserID, UserName, WaitlistEntry},
+    error::ErrService,
+    features::book::service::BookRequest,
+};
+
+/// Attendee count of a waitlist entry when the request doesn't say.
+const DEFAULT_ATTENDEES: i32 = 1;
+
+/// Body of `POST /waitlist`: the slot to queue for, as if booking it.
+#[derive(Deserialize, ToSchema)]
+pub struct JoinWaitlistDto {
+    pub room_name: String,
+    #[schema(example = "24.12.30")]
+    pub date: String,
+    #[schema(example = "09:00")]
+    pub start_time: String,
+    #[schema(example = "10:30")]
+    pub end_time: String,
+    pub attendees: Option<i32>,
+}
+
+impl JoinWaitlistDto {
+    pub fn request(&self) -> BookRequest<'_> {
+        BookRequest {
+            room: &self.room_name,
+            date: &self.date,
+            start_time: &self.start_time,
+            end_time: &self.end_time,
+            attendees: self.attendees.unwrap_or(DEFAULT_ATTENDEES),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WaitlistEntryDto {
+    pub id: i32,
+    pub room_name: String,
+    pub user_name: String,
+    pub date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub attendees: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct WaitlistRowDto {
+    pub id: i32,
+    pub room_id: i32,
+    pub user_id: Uuid,
+    pub room_name: String,
+    pub user_name: String,
+    pub date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub attendees: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<WaitlistRowDto> for WaitlistEntry {
+    type Error = ErrService;
+
+    fn try_from(dto: WaitlistRowDto) -> Result<Self, Self::Error> {
+        Ok(WaitlistEntry {
+            id: dto.id,
+            room_id: dto.room_id,
+            user_id: UserID { id: dto.user_id },
+            room_name: RoomName::new(&dto.room_name)?,
+            user_name: UserName::new(&dto.user_name)?,
+            date: BookDate::from_naive(dto.date)?,
+            slot: BookSlot::from_naive(dto.start_time, dto.end_time)?,
+            attendees: dto.attendees,
+            created_at: dto.created_at,
+        })
+    }
+}
+
+impl From<WaitlistEntry> for WaitlistEntryDto {
+    fn from(entry: WaitlistEntry) -> Self {
+        WaitlistEntryDto {
+            id: entry.id,
+            room_name: entry.room_name.name,
+            user_name: entry.user_name.name,
+            date: entry.date.date,
+            start_time: entry.slot.start,
+            end_time: entry.slot.end,
+            attendees: entry.attendees,
+            created_at: entry.created_at,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::{
+    app::state::AppState,
+    error::{ErrService, ProblemDto},
+    features::{
+        auth::middleware::AuthUser,
+        waitlist::dto::{JoinWaitlistDto, WaitlistEntryDto},
+    },
+    infra::backend::Backend,
+};
+
+#[utoipa::path(
+    post,
+    path = "/waitlist",
+    tag = "waitlist",
+    request_body = JoinWaitlistDto,
+    responses(
+        (status = 201, description = "Queued; booked and notified once the slot is freed", body = WaitlistEntryDto),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown room", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 409, description = "Slot is free, or already queued for it", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 422, description = "Past date, bad slot or attendees", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn join_waitlist<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(payload): Json<JoinWaitlistDto>,
+) -> Result<impl IntoResponse, ErrService> {
+    let entry = state
+        .book_service
+        .join_waitlist(&user, payload.request())
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(WaitlistEntryDto::from(entry))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/waitlist",
+    tag = "waitlist",
+    responses(
+        (status = 200, description = "The caller's entries, everyone's for facility managers", body = Vec<WaitlistEntryDto>),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_waitlist<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ErrService> {
+    let entries = state.book_service.list_waitlist(&user).await?;
+
+    let dto: Vec<WaitlistEntryDto> = entries.into_iter().map(WaitlistEntryDto::from).collect();
+
+    Ok(Json(dto))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/waitlist/{id}",
+    tag = "waitlist",
+    params(("id" = i32, Path, description = "Waitlist entry id")),
+    responses(
+        (status = 204, description = "Left the waitlist"),
+        (status = 401, description = "Missing, invalid or expired token", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is neither the entry's user nor a facility manager", body = ProblemDto, content_type = "application/problem+json"),
+        (status = 404, description = "Unknown entry", body = ProblemDto, content_type = "application/problem+json"),
+    )
+)]
+pub async fn leave_waitlist<B: Backend>(
+    State(state): State<AppState<B>>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, ErrService> {
+    state.book_service.leave_waitlist(&user, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,4 @@
+pub mod dto;
+pub mod handlers;
+pub mod repo;
+pub mod routes;
@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+use crate::{
+    domain::{UserID, WaitlistEntry},
+    error::{ErrBook, ErrRepo, ErrService},
+    features::waitlist::dto::WaitlistRowDto,
+    infra::db::{DBClient, is_unique_violation},
+};
+
+/// Waitlist columns with the room and user names joined in, over a `w` relation.
+const SELECT_ENTRY: &str = "SELECT w.id, w.room_id, w.user_id, r.room_name, u.user_name, w.date, w.start_time, w.end_time, w.attendees, w.created_at FROM waitlist w JOIN rooms r ON r.id = w.room_id JOIN users u ON u.user_id = w.user_id";
+
+#[async_trait]
+pub trait WaitlistRepo {
+    async fn insert_waitlist_entry(
+        &self,
+        entry: &WaitlistEntry,
+    ) -> Result<WaitlistEntry, ErrService>;
+    /// Entries for a room on a date, longest waiting first.
+    async fn get_waitlist(
+        &self,
+        room_id: i32,
+        date: NaiveDate,
+    ) -> Result<Vec<WaitlistEntry>, ErrService>;
+    /// Entries of `user`, or everyone's when `None`, soonest first.
+    async fn list_waitlist(&self, user: Option<&UserID>) -> Result<Vec<WaitlistEntry>, ErrService>;
+    async fn get_waitlist_entry(&self, id: i32) -> Result<Option<WaitlistEntry>, ErrService>;
+    async fn delete_waitlist_entry(&self, id: i32) -> Result<bool, ErrService>;
+}
+
+#[async_trait]
+impl WaitlistRepo for DBClient {
+    async fn insert_waitlist_entry(
+        &self,
+        entry: &WaitlistEntry,
+    ) -> Result<WaitlistEntry, ErrService> {
+        let row = sqlx::query_as::<_, WaitlistRowDto>(
+            "WITH w AS (INSERT INTO waitlist (room_id, user_id, date, start_time, end_time, attendees, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *) \
+            SELECT w.id, w.room_id, w.user_id, r.room_name, u.user_name, w.date, w.start_time, w.end_time, w.attendees, w.created_at \
+            FROM w JOIN rooms r ON r.id = w.room_id JOIN users u ON u.user_id = w.user_id",
+        )
+        .bind(entry.room_id)
+        .bind(entry.user_id.id)
+        .bind(entry.date.date)
+        .bind(entry.slot.start)
+        .bind(entry.slot.end)
+        .bind(entry.attendees)
+        .bind(entry.created_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                ErrService::Book(ErrBook::AlreadyQueued)
+            } else {
+                ErrService::Repo(ErrRepo::BadRequest)
+            }
+        })?;
+
+        Ok(WaitlistEntry::try_from(row)?)
+    }
+
+    async fn get_waitlist(
+        &self,
+        room_id: i32,
+        date: NaiveDate,
+    ) -> Result<Vec<WaitlistEntry>, ErrService> {
+        let rows = sqlx::query_as::<_, WaitlistRowDto>(&format!(
+            "{SELECT_ENTRY} WHERE w.room_id = $1 AND w.date = $2 ORDER BY w.created_at, w.id"
+        ))
+        .bind(room_id)
+        .bind(date)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        rows.into_iter().map(WaitlistEntry::try_from).collect()
+    }
+
+    async fn list_waitlist(&self, user: Option<&UserID>) -> Result<Vec<WaitlistEntry>, ErrService> {
+        let rows = sqlx::query_as::<_, WaitlistRowDto>(&format!(
+            "{SELECT_ENTRY} WHERE ($1::uuid IS NULL OR w.user_id = $1) ORDER BY w.date, w.start_time, w.created_at"
+        ))
+        .bind(user.map(|u| u.id))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        rows.into_iter().map(WaitlistEntry::try_from).collect()
+    }
+
+    async fn get_waitlist_entry(&self, id: i32) -> Result<Option<WaitlistEntry>, ErrService> {
+        let row = sqlx::query_as::<_, WaitlistRowDto>(&format!("{SELECT_ENTRY} WHERE w.id = $1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+
+        match row {
+            Some(row) => Ok(Some(WaitlistEntry::try_from(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_waitlist_entry(&self, id: i32) -> Result<bool, ErrService> {
+        let result = sqlx::query("DELETE FROM waitlist WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,19 @@
+use axum::{
+    Router,
+    routing::{delete, post},
+};
+
+use crate::{
+    app::state::AppState,
+    features::waitlist::handlers::{join_waitlist, leave_waitlist, list_waitlist},
+    infra::backend::Backend,
+};
+
+pub fn waitlist_routes<B: Backend>() -> Router<AppState<B>> {
+    Router::new()
+        .route(
+            "/waitlist",
+            post(join_waitlist::<B>).get(list_waitlist::<B>),
+        )
+        .route("/waitlist/{id}", delete(leave_waitlist::<B>))
+}
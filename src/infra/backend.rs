@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::ErrService,
+    features::{
+        book::repo::BookRepo, calendar::repo::CalendarRepo, room::repo::RoomRepo,
+        user::repo::UserRepo, waitlist::repo::WaitlistRepo,
+    },
+    infra::db::DBClient,
+};
+
+/// A storage the service runs on: every repository, over one shared state, plus the
+/// setup of its schema. Picked on startup by `STORAGE`.
+#[async_trait]
+pub trait Backend:
+    RoomRepo + UserRepo + BookRepo + WaitlistRepo + CalendarRepo + Clone + Send + Sync + 'static
+{
+    /// Brings the schema up to date; a no-op for storages without one.
+    async fn run_migrations(&self) -> Result<(), ErrService>;
+}
+
+#[async_trait]
+impl Backend for DBClient {
+    async fn run_migrations(&self) -> Result<(), ErrService> {
+        DBClient::run_migrations(self).await
+    }
+}
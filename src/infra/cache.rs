@@ -4,12 +4,12 @@ use crate::features::{
     book::service::BookService, room::service::RoomService, user::service::UserService,
 };
 
-use super::db::DBClient;
+use super::backend::Backend;
 
-pub async fn try_init_caches(
-    user_service: &UserService<DBClient>,
-    room_service: &RoomService<DBClient>,
-    book_service: &BookService<DBClient>,
+pub async fn try_init_caches<B: Backend>(
+    user_service: &UserService<B>,
+    room_service: &RoomService<B>,
+    book_service: &BookService<B>,
 ) -> Result<(), ErrService> {
     user_service
         .populate_cache()
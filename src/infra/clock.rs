@@ -0,0 +1,57 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+/// Tells the time, so what "now" is can be pinned or moved in tests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Today, on the wall clocks of `time_zone`.
+    fn today_in(&self, time_zone: Tz) -> NaiveDate {
+        self.now().with_timezone(&time_zone).date_naive()
+    }
+}
+
+/// The time of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock standing still at a given time until moved.
+#[cfg(test)]
+pub struct ManualClock {
+    now: std::sync::RwLock<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: std::sync::RwLock::new(now),
+        }
+    }
+
+    /// A clock at `hh:mm` UTC on `date`.
+    pub fn at(date: NaiveDate, hour: u32, minute: u32) -> Self {
+        Self::new(date.and_hms_opt(hour, minute, 0).unwrap().and_utc())
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.write().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        let mut now = self.now.write().unwrap();
+        *now += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.read().unwrap()
+    }
+}
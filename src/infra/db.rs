@@ -1,4 +1,44 @@
-use sqlx::{Pool, Postgres};
+use sqlx::{Pool, Postgres, migrate::Migrator};
+use tracing::info;
+
+use crate::{domain::SortOrder, error::ErrService};
+
+/// SQL migrations from `migrations/`, embedded in the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// SQLSTATE of a row rejected by an `EXCLUDE` constraint.
+const EXCLUSION_VIOLATION: &str = "23P01";
+/// SQLSTATE of a write that would leave a foreign key dangling.
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+/// SQLSTATE of a row duplicating a `UNIQUE` key.
+const UNIQUE_VIOLATION: &str = "23505";
+
+pub fn is_exclusion_violation(err: &sqlx::Error) -> bool {
+    has_sqlstate(err, EXCLUSION_VIOLATION)
+}
+
+pub fn is_foreign_key_violation(err: &sqlx::Error) -> bool {
+    has_sqlstate(err, FOREIGN_KEY_VIOLATION)
+}
+
+pub fn is_unique_violation(err: &sqlx::Error) -> bool {
+    has_sqlstate(err, UNIQUE_VIOLATION)
+}
+
+/// Comparison and direction of a keyset-paginated query: rows come after the cursor
+/// in the listing's order.
+pub fn keyset(order: SortOrder) -> (&'static str, &'static str) {
+    match order {
+        SortOrder::Asc => (">", "ASC"),
+        SortOrder::Desc => ("<", "DESC"),
+    }
+}
+
+fn has_sqlstate(err: &sqlx::Error, sqlstate: &str) -> bool {
+    err.as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == sqlstate)
+}
 
 #[derive(Debug, Clone)]
 pub struct DBClient {
@@ -9,4 +49,11 @@ impl DBClient {
     pub fn new(pool: Pool<Postgres>) -> Self {
         Self { pool }
     }
+
+    pub async fn run_migrations(&self) -> Result<(), ErrService> {
+        MIGRATOR.run(&self.pool).await.map_err(sqlx::Error::from)?;
+
+        info!("Database schema up to date");
+        Ok(())
+    }
 }
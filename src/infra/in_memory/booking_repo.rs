@@ -1 +1,333 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use uuid::Uuid;
 
+use crate::{
+    domain::{
+        Book, BookDateRange, BookFilter, BookKey, BookSlot, BookStatus, Cancellation, Page,
+        PageRequest, Room,
+    },
+    error::{ErrBook, ErrRepo, ErrService},
+    features::book::repo::BookRepo,
+    infra::in_memory::in_memo_repo::{InMemoryRepo, Tables},
+};
+
+/// Inserts `book` under a new id, once checked.
+fn insert_book(tables: &mut Tables, book: &Book) -> Result<Book, ErrService> {
+    tables.check_book(book)?;
+
+    let book = Book {
+        id: tables.next_book_id(),
+        cancellation: None,
+        ..book.clone()
+    };
+    tables.books.insert(book.id, book.clone());
+    Ok(tables.join_book(book))
+}
+
+/// Overwrites the booking of `book.id` with `book`, once checked; its series, its
+/// cancellation and when it was made are kept.
+fn update_book(tables: &mut Tables, book: &Book) -> Result<Book, ErrService> {
+    let old = tables
+        .books
+        .get(&book.id)
+        .cloned()
+        .ok_or(ErrService::Repo(ErrRepo::BadRequest))?;
+    tables.check_book(book)?;
+
+    let book = Book {
+        series_id: old.series_id,
+        cancellation: old.cancellation,
+        created_at: old.created_at,
+        ..book.clone()
+    };
+    tables.books.insert(book.id, book.clone());
+    Ok(tables.join_book(book))
+}
+
+#[async_trait]
+impl BookRepo for InMemoryRepo {
+    async fn insert_book(&self, book: &Book) -> Result<Book, ErrService> {
+        insert_book(&mut *self.tables.write().await, book)
+    }
+
+    async fn update_book(&self, book: &Book) -> Result<Book, ErrService> {
+        update_book(&mut *self.tables.write().await, book)
+    }
+
+    async fn get_all_books(&self) -> Result<Vec<Book>, ErrService> {
+        let tables = self.tables.read().await;
+        let mut books: Vec<Book> = tables
+            .books
+            .values()
+            .map(|b| tables.join_book(b.clone()))
+            .collect();
+        books.sort_by_key(|b| (b.date.date, b.slot.start));
+
+        Ok(books)
+    }
+
+    async fn list_books(
+        &self,
+        filter: &BookFilter,
+        page: &PageRequest<BookKey>,
+    ) -> Result<Page<Book>, ErrService> {
+        let tables = self.tables.read().await;
+        let books: Vec<Book> = tables
+            .books
+            .values()
+            .map(|b| tables.join_book(b.clone()))
+            .filter(|b| filter.matches(b))
+            .collect();
+
+        Ok(Page::from_unsorted(books, page, |b| BookKey::from(b)))
+    }
+
+    async fn cancel_book(&self, id: i32, cancellation: &Cancellation) -> Result<Book, ErrService> {
+        let mut tables = self.tables.write().await;
+        let book = tables
+            .books
+            .get(&id)
+            .filter(|b| b.is_live())
+            .cloned()
+            .ok_or(ErrBook::Cancelled)?;
+
+        let book = book.cancel(cancellation.clone());
+        tables.books.insert(id, book.clone());
+        Ok(tables.join_book(book))
+    }
+
+    async fn set_book_status(&self, id: i32, status: BookStatus) -> Result<Book, ErrService> {
+        let mut tables = self.tables.write().await;
+        let book = tables
+            .books
+            .get_mut(&id)
+            .filter(|b| b.is_live())
+            .ok_or(ErrBook::Cancelled)?;
+
+        book.status = status;
+        let book = book.clone();
+        Ok(tables.join_book(book))
+    }
+
+    async fn resolve_pending_book(
+        &self,
+        id: i32,
+        status: BookStatus,
+        resolution: Option<&Cancellation>,
+    ) -> Result<Book, ErrService> {
+        let mut tables = self.tables.write().await;
+        let book = tables
+            .books
+            .get(&id)
+            .filter(|b| b.status == BookStatus::Pending)
+            .cloned()
+            .ok_or(ErrBook::NotPending)?;
+
+        let book = Book {
+            status,
+            cancellation: resolution.cloned(),
+            ..book
+        };
+        tables.check_book(&book)?;
+        tables.books.insert(id, book.clone());
+        Ok(tables.join_book(book))
+    }
+
+    async fn cancel_all_books(&self, cancellation: &Cancellation) -> Result<Vec<Book>, ErrService> {
+        let mut tables = self.tables.write().await;
+        let ids: Vec<i32> = tables
+            .books
+            .values()
+            .filter(|b| b.is_live())
+            .map(|b| b.id)
+            .collect();
+
+        let mut cancelled = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(book) = tables.books.remove(&id) {
+                let book = book.cancel(cancellation.clone());
+                tables.books.insert(id, book.clone());
+                cancelled.push(tables.join_book(book));
+            }
+        }
+
+        Ok(cancelled)
+    }
+
+    async fn get_one_book(&self, book: &Book) -> Result<Option<Book>, ErrService> {
+        self.find_book(book.id).await
+    }
+
+    async fn find_book(&self, id: i32) -> Result<Option<Book>, ErrService> {
+        let tables = self.tables.read().await;
+        Ok(tables.books.get(&id).map(|b| tables.join_book(b.clone())))
+    }
+
+    async fn purge_books_before(&self, date: NaiveDate) -> Result<u64, ErrService> {
+        let mut tables = self.tables.write().await;
+        let before = tables.books.len();
+        tables.books.retain(|_, b| b.date.date >= date);
+
+        Ok((before - tables.books.len()) as u64)
+    }
+
+    async fn insert_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService> {
+        let mut tables = self.tables.write().await;
+        let snapshot = tables.books.clone();
+
+        let mut inserted = Vec::with_capacity(books.len());
+        for book in books {
+            match insert_book(&mut tables, book) {
+                Ok(book) => inserted.push(book),
+                Err(err) => {
+                    tables.books = snapshot;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn update_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService> {
+        let mut tables = self.tables.write().await;
+        let snapshot = tables.books.clone();
+
+        let mut updated = Vec::with_capacity(books.len());
+        for book in books {
+            match update_book(&mut tables, book) {
+                Ok(book) => updated.push(book),
+                Err(err) => {
+                    tables.books = snapshot;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn cancel_series(
+        &self,
+        series_id: Uuid,
+        from: Option<NaiveDate>,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<Book>, ErrService> {
+        let mut tables = self.tables.write().await;
+        let ids: Vec<i32> = tables
+            .books
+            .values()
+            .filter(|b| {
+                b.series_id == Some(series_id)
+                    && from.is_none_or(|from| b.date.date >= from)
+                    && b.is_live()
+            })
+            .map(|b| b.id)
+            .collect();
+
+        let mut cancelled = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(book) = tables.books.remove(&id) {
+                let book = book.cancel(cancellation.clone());
+                tables.books.insert(id, book.clone());
+                cancelled.push(tables.join_book(book));
+            }
+        }
+        Ok(cancelled)
+    }
+
+    async fn is_room_already_booked(
+        &self,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    ) -> Result<bool, ErrService> {
+        Ok(self
+            .tables
+            .read()
+            .await
+            .is_booked(room_id, starts_at, ends_at))
+    }
+
+    async fn get_available_rooms(
+        &self,
+        range: &BookDateRange,
+        slot: Option<&BookSlot>,
+    ) -> Result<Vec<Room>, ErrService> {
+        let tables = self.tables.read().await;
+        let mut rooms: Vec<Room> = tables
+            .rooms
+            .values()
+            .filter(|room| {
+                !tables.books.values().any(|b| {
+                    b.is_live()
+                        && b.room_id == room.id
+                        && range.contains(&b.date)
+                        && slot.is_none_or(|slot| b.slot.overlaps(slot))
+                })
+            })
+            .cloned()
+            .collect();
+        rooms.sort_by(|a, b| a.room_name.cmp(&b.room_name));
+
+        Ok(rooms)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use crate::{
+        domain::{Book, BookDate, BookSlot, Room, User},
+        error::{ErrBook, ErrRoom, ErrService},
+        features::{book::repo::BookRepo, room::repo::RoomRepo, user::repo::UserRepo},
+        infra::in_memory::in_memo_repo::InMemoryRepo,
+    };
+
+    #[tokio::test]
+    async fn keeps_the_constraints_of_the_database() {
+        let repo = InMemoryRepo::new();
+        let room = repo
+            .insert_room(&Room::new("alpha", 8).unwrap())
+            .await
+            .unwrap();
+        let user = repo
+            .insert_user(&User::new("sophie").unwrap())
+            .await
+            .unwrap();
+        let date = BookDate::new("20.12.26").unwrap();
+        let book = |start, end| {
+            Book::new(
+                &room,
+                &user,
+                date.clone(),
+                BookSlot::new(start, end).unwrap(),
+                1,
+                Utc::now(),
+            )
+        };
+
+        let first = repo.insert_book(&book("09:00", "10:00")).await.unwrap();
+        assert!(matches!(
+            repo.insert_books(&[book("10:00", "11:00"), book("09:30", "10:30")])
+                .await,
+            Err(ErrService::Book(ErrBook::AlreadyBooked))
+        ));
+        assert_eq!(repo.get_all_books().await.unwrap(), vec![first.clone()]);
+
+        let renamed = Room::new("gamma", 8).unwrap();
+        let renamed = repo
+            .update_room(&Room {
+                id: room.id,
+                ..renamed
+            })
+            .await
+            .unwrap();
+        let found = repo.find_book(first.id).await.unwrap().unwrap();
+        assert_eq!(found.room_name, renamed.room_name);
+        assert!(matches!(
+            repo.delete_room_by_id(room.id).await,
+            Err(ErrService::Room(ErrRoom::HasBookings))
+        ));
+    }
+}
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+
+use crate::{
+    domain::{CalendarFeed, FeedSubject},
+    error::ErrService,
+    features::calendar::repo::CalendarRepo,
+    infra::in_memory::in_memo_repo::InMemoryRepo,
+};
+
+#[async_trait]
+impl CalendarRepo for InMemoryRepo {
+    async fn get_feed(&self, subject: &FeedSubject) -> Result<Option<CalendarFeed>, ErrService> {
+        let tables = self.tables.read().await;
+        Ok(tables.feeds.iter().find(|f| f.subject == *subject).cloned())
+    }
+
+    async fn find_feed(&self, token: &str) -> Result<Option<CalendarFeed>, ErrService> {
+        let tables = self.tables.read().await;
+        Ok(tables.feeds.iter().find(|f| f.token == token).cloned())
+    }
+
+    async fn insert_feed(&self, feed: &CalendarFeed) -> Result<Option<CalendarFeed>, ErrService> {
+        let mut tables = self.tables.write().await;
+        if tables
+            .feeds
+            .iter()
+            .any(|f| f.subject == feed.subject || f.token == feed.token)
+        {
+            return Ok(None);
+        }
+
+        tables.feeds.push(feed.clone());
+        Ok(Some(feed.clone()))
+    }
+
+    async fn delete_feed(&self, subject: &FeedSubject) -> Result<bool, ErrService> {
+        let mut tables = self.tables.write().await;
+        let before = tables.feeds.len();
+        tables.feeds.retain(|f| f.subject != *subject);
+
+        Ok(tables.feeds.len() < before)
+    }
+}
@@ -1 +1,106 @@
+use std::sync::Arc;
 
+use chrono::{Duration, NaiveDate};
+
+use crate::{
+    domain::{Role, Room, User},
+    error::{ErrBook, ErrDomain, ErrService},
+    features::{
+        book::service::{BookRequest, BookService},
+        room::repo::RoomRepo,
+        user::repo::UserRepo,
+    },
+    infra::{clock::ManualClock, in_memory::in_memo_repo::InMemoryRepo},
+};
+
+/// A store holding two rooms and three users, and a [`BookService`] over it whose
+/// clock stands at 08:00 UTC on [`today`] until moved.
+pub struct Fixture {
+    pub repo: InMemoryRepo,
+    pub service: BookService<InMemoryRepo>,
+    pub clock: Arc<ManualClock>,
+    /// `alpha`, 8 seats.
+    pub room: Room,
+    /// `beta`, 4 seats, members' bookings wait for approval.
+    pub approval_room: Room,
+    pub member: User,
+    pub other_member: User,
+    pub manager: User,
+}
+
+impl Fixture {
+    pub async fn new() -> Self {
+        let repo = InMemoryRepo::new();
+        let room = repo
+            .insert_room(&Room::new("alpha", 8).unwrap())
+            .await
+            .unwrap();
+        let approval_room = repo
+            .insert_room(&Room {
+                requires_approval: true,
+                ..Room::new("beta", 4).unwrap()
+            })
+            .await
+            .unwrap();
+        let member = repo
+            .insert_user(&User::new("sophie").unwrap())
+            .await
+            .unwrap();
+        let other_member = repo
+            .insert_user(&User::new("jordan").unwrap())
+            .await
+            .unwrap();
+        let manager = repo
+            .insert_user(
+                &User::new("camille")
+                    .unwrap()
+                    .with_role(Role::FacilityManager),
+            )
+            .await
+            .unwrap();
+
+        let clock = Arc::new(ManualClock::at(today(), 8, 0));
+
+        Self {
+            service: BookService::new(repo.clone()).with_clock(clock.clone()),
+            repo,
+            clock,
+            room,
+            approval_room,
+            member,
+            other_member,
+            manager,
+        }
+    }
+}
+
+/// The day the clock of a [`Fixture`] starts on, a Monday.
+pub fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2030, 6, 3).unwrap()
+}
+
+/// The date `days` from [`today`], as `dd.mm.yy`.
+pub fn day(days: i64) -> String {
+    (today() + Duration::days(days))
+        .format("%d.%m.%y")
+        .to_string()
+}
+
+/// A booking request for one attendee.
+pub fn request<'a>(room: &'a str, date: &'a str, start: &'a str, end: &'a str) -> BookRequest<'a> {
+    BookRequest {
+        room,
+        date,
+        start_time: start,
+        end_time: end,
+        attendees: 1,
+    }
+}
+
+/// The booking error of `result`, raised by the service or the domain types.
+pub fn err_book<T: std::fmt::Debug>(result: Result<T, ErrService>) -> ErrBook {
+    match result {
+        Err(ErrService::Book(err)) | Err(ErrService::Domain(ErrDomain::Book(err))) => err,
+        other => panic!("expected a booking error, got {other:?}"),
+    }
+}
@@ -1,38 +1,158 @@
-use std::{collections::HashSet, hash::Hash};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
-use crate::features::{
-    book::service::BookService, room::service::RoomService, user::service::UserService,
+use crate::{
+    domain::{Book, CalendarFeed, Room, User, WaitlistEntry},
+    error::{ErrBook, ErrRepo, ErrService},
+    features::{
+        book::service::BookService, room::service::RoomService, user::service::UserService,
+    },
+    infra::backend::Backend,
 };
 
-#[derive(Debug)]
-pub struct InMemoryRepo<T> {
-    pub repo: RwLock<HashSet<T>>,
-    pub cache: HashSet<T>,
+/// A storage kept in the process memory, lost on shutdown. Clones share the same
+/// tables, so one store serves every repository like a database would, constraints
+/// included: unique names, no overlapping live bookings, no dangling references.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryRepo {
+    pub tables: Arc<RwLock<Tables>>,
 }
 
-impl<T: Eq + Hash + Clone> InMemoryRepo<T> {
-    pub async fn new() -> Self {
-        Self {
-            repo: RwLock::new(HashSet::<T>::new()),
-            cache: HashSet::new(),
+/// The rows of an [`InMemoryRepo`], keyed by id. Ids are handed out like `SERIAL`s.
+#[derive(Debug, Default)]
+pub struct Tables {
+    pub rooms: BTreeMap<i32, Room>,
+    pub users: HashMap<Uuid, User>,
+    pub books: BTreeMap<i32, Book>,
+    pub waitlist: BTreeMap<i32, WaitlistEntry>,
+    pub feeds: Vec<CalendarFeed>,
+    last_room_id: i32,
+    last_book_id: i32,
+    last_entry_id: i32,
+}
+
+impl Tables {
+    pub fn next_room_id(&mut self) -> i32 {
+        self.last_room_id += 1;
+        self.last_room_id
+    }
+
+    pub fn next_book_id(&mut self) -> i32 {
+        self.last_book_id += 1;
+        self.last_book_id
+    }
+
+    pub fn next_entry_id(&mut self) -> i32 {
+        self.last_entry_id += 1;
+        self.last_entry_id
+    }
+
+    /// Checks `room` can be written: its name is unique, its id aside.
+    pub fn check_room(&self, room: &Room) -> Result<(), ErrService> {
+        if self
+            .rooms
+            .values()
+            .any(|r| r.id != room.id && r.room_name == room.room_name)
+        {
+            return Err(ErrService::Repo(ErrRepo::BadRequest));
         }
+
+        Ok(())
     }
 
-    pub async fn init_user_service() -> UserService<InMemoryRepo<T>> {
-        let repo: InMemoryRepo<T> = InMemoryRepo::new().await;
-        let service: UserService<InMemoryRepo<T>> = UserService::new(repo);
-        service
+    /// Checks `user` can be written: its name is unique, its id aside.
+    pub fn check_user(&self, user: &User) -> Result<(), ErrService> {
+        if self
+            .users
+            .values()
+            .any(|u| u.user_id != user.user_id && u.user_name == user.user_name)
+        {
+            return Err(ErrService::Repo(ErrRepo::BadRequest));
+        }
+
+        Ok(())
     }
 
-    pub async fn init_room_service() -> RoomService<InMemoryRepo<T>> {
-        let repo: InMemoryRepo<T> = InMemoryRepo::new().await;
-        let service: RoomService<InMemoryRepo<T>> = RoomService::new(repo);
-        service
+    /// Checks `book` can be written: its room and user exist, and it doesn't overlap
+    /// another live booking of the room when live itself.
+    pub fn check_book(&self, book: &Book) -> Result<(), ErrService> {
+        if !self.rooms.contains_key(&book.room_id) || !self.users.contains_key(&book.user_id.id) {
+            return Err(ErrService::Repo(ErrRepo::BadRequest));
+        }
+        if book.is_live()
+            && self.books.values().any(|b| {
+                b.id != book.id && b.conflicts_with(book.room_id, book.starts_at, book.ends_at)
+            })
+        {
+            return Err(ErrService::Book(ErrBook::AlreadyBooked));
+        }
+
+        Ok(())
+    }
+
+    pub fn is_booked(
+        &self,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    ) -> bool {
+        self.books
+            .values()
+            .any(|b| b.conflicts_with(room_id, starts_at, ends_at))
     }
-    pub async fn init_book_service() -> BookService<InMemoryRepo<T>> {
-        let repo: InMemoryRepo<T> = InMemoryRepo::new().await;
-        let service: BookService<InMemoryRepo<T>> = BookService::new(repo);
-        service
+
+    /// `book` with the current names of its room and user, as joined when reading.
+    pub fn join_book(&self, mut book: Book) -> Book {
+        if let Some(room) = self.rooms.get(&book.room_id) {
+            book.room_name = room.room_name.clone();
+            book.time_zone = room.time_zone;
+        }
+        if let Some(user) = self.users.get(&book.user_id.id) {
+            book.user_name = user.user_name.clone();
+        }
+        book
+    }
+
+    /// `entry` with the current names of its room and user, as joined when reading.
+    pub fn join_entry(&self, mut entry: WaitlistEntry) -> WaitlistEntry {
+        if let Some(room) = self.rooms.get(&entry.room_id) {
+            entry.room_name = room.room_name.clone();
+        }
+        if let Some(user) = self.users.get(&entry.user_id.id) {
+            entry.user_name = user.user_name.clone();
+        }
+        entry
+    }
+}
+
+impl InMemoryRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn init_user_service() -> UserService<InMemoryRepo> {
+        UserService::new(InMemoryRepo::new())
+    }
+
+    pub async fn init_room_service() -> RoomService<InMemoryRepo> {
+        RoomService::new(InMemoryRepo::new())
+    }
+
+    pub async fn init_book_service() -> BookService<InMemoryRepo> {
+        BookService::new(InMemoryRepo::new())
+    }
+}
+
+#[async_trait]
+impl Backend for InMemoryRepo {
+    async fn run_migrations(&self) -> Result<(), ErrService> {
+        Ok(())
     }
 }
@@ -1,5 +1,8 @@
 pub mod booking_repo;
+pub mod calendar_repo;
+#[cfg(test)]
 pub mod in_memo_helper;
 pub mod in_memo_repo;
 pub mod room_repo;
 pub mod user_repo;
+pub mod waitlist_repo;
@@ -1,93 +1,305 @@
-#[cfg(test)]
-mod test {
+use async_trait::async_trait;
 
-    use crate::{
-        domain::{Room, RoomName},
-        error::{ErrRepo, ErrRoom, ErrService},
-        features::room::{repo::RoomRepo, service::RoomService},
-        infra::in_memory::in_memo_repo::InMemoryRepo,
-    };
+use crate::{
+    domain::{Book, FeedSubject, Page, PageRequest, Room, RoomFilter, RoomName},
+    error::{ErrRepo, ErrRoom, ErrService},
+    features::room::repo::RoomRepo,
+    infra::in_memory::in_memo_repo::InMemoryRepo,
+};
 
-    use async_trait::async_trait;
+#[async_trait]
+impl RoomRepo for InMemoryRepo {
+    async fn insert_room(&self, room: &Room) -> Result<Room, ErrService> {
+        let mut tables = self.tables.write().await;
+        tables.check_room(room)?;
 
-    #[async_trait]
-    impl RoomRepo for InMemoryRepo<Room> {
-        async fn insert_room(&self, room: &Room) -> Result<Room, ErrService> {
-            self.repo.write().await.insert(room.clone());
-            Ok(room.clone())
-        }
-        async fn update_room(&self, id: i32, new_name: RoomName) -> Result<Room, ErrService> {
-            let old_room = {
-                let read_guard = self.repo.read().await;
-                let old_room = read_guard.iter().find(|r| r.id == id).cloned();
-                old_room
-            };
+        let room = Room {
+            id: tables.next_room_id(),
+            ..room.clone()
+        };
+        tables.rooms.insert(room.id, room.clone());
+        Ok(room)
+    }
 
-            let mut write_guard = self.repo.write().await;
-            if let Some(old_room) = old_room {
-                let new_room = Room {
-                    room_name: new_name,
-                    id: old_room.id,
-                };
-                write_guard.remove(&old_room);
-                write_guard.insert(new_room.clone());
-                Ok(new_room)
-            } else {
-                Err(ErrService::Repo(ErrRepo::Unreachable))
+    async fn insert_rooms(&self, rooms: &[Room]) -> Result<Vec<Room>, ErrService> {
+        let mut tables = self.tables.write().await;
+        for (i, room) in rooms.iter().enumerate() {
+            tables.check_room(room)?;
+            if rooms[..i].iter().any(|r| r.room_name == room.room_name) {
+                return Err(ErrService::Repo(ErrRepo::BadRequest));
             }
         }
 
-        async fn delete_room_by_id(&self, room_id: i32) -> Result<bool, ErrService> {
-            let room = {
-                let read_guard = self.repo.read().await;
-                let room = read_guard.iter().find(|r| r.id == room_id).cloned();
-                room
+        let mut inserted = Vec::with_capacity(rooms.len());
+        for room in rooms {
+            let room = Room {
+                id: tables.next_room_id(),
+                ..room.clone()
             };
+            tables.rooms.insert(room.id, room.clone());
+            inserted.push(room);
+        }
+        Ok(inserted)
+    }
+
+    async fn update_room(&self, room: &Room) -> Result<Room, ErrService> {
+        let mut tables = self.tables.write().await;
+        if !tables.rooms.contains_key(&room.id) {
+            return Err(ErrService::Repo(ErrRepo::BadRequest));
+        }
+        tables.check_room(room)?;
 
-            let mut write_guard = self.repo.write().await;
-            if let Some(room) = room {
-                write_guard.remove(&room);
-            } else {
-                return Err(ErrService::Room(ErrRoom::RoomNotFound));
+        tables.rooms.insert(room.id, room.clone());
+        // Bookings keep their wall-clock times, now read in the room's zone.
+        for book in tables.books.values_mut().filter(|b| b.room_id == room.id) {
+            *book = Book {
+                time_zone: room.time_zone,
+                ..book.clone()
             }
-            Ok(true)
+            .with_instants();
         }
-        async fn get_all_rooms(&self) -> Result<Vec<Room>, ErrService> {
-            Ok(self.repo.read().await.iter().cloned().collect())
+        Ok(room.clone())
+    }
+
+    async fn delete_room_by_id(&self, room_id: i32) -> Result<bool, ErrService> {
+        let mut tables = self.tables.write().await;
+        if tables
+            .books
+            .values()
+            .any(|b| b.room_id == room_id && b.status.is_open())
+        {
+            return Err(ErrService::Room(ErrRoom::HasBookings));
         }
-        async fn get_one_room(&self, room_name: &RoomName) -> Result<Room, ErrService> {
-            if let Some(room) = self
-                .repo
-                .read()
-                .await
-                .iter()
-                .find(|r| r.room_name == *room_name)
-            {
-                Ok(room.clone())
-            } else {
-                Err(ErrService::Room(ErrRoom::RoomNotFound))
-            }
+        if tables.rooms.remove(&room_id).is_none() {
+            return Ok(false);
         }
+
+        tables.books.retain(|_, b| b.room_id != room_id);
+        tables.waitlist.retain(|_, e| e.room_id != room_id);
+        tables
+            .feeds
+            .retain(|f| f.subject != FeedSubject::Room(room_id));
+        Ok(true)
+    }
+
+    async fn get_all_rooms(&self) -> Result<Vec<Room>, ErrService> {
+        Ok(self.tables.read().await.rooms.values().cloned().collect())
+    }
+
+    async fn list_rooms(
+        &self,
+        filter: &RoomFilter,
+        page: &PageRequest<RoomName>,
+    ) -> Result<Page<Room>, ErrService> {
+        let rooms: Vec<Room> = self
+            .tables
+            .read()
+            .await
+            .rooms
+            .values()
+            .filter(|r| filter.matches(r))
+            .cloned()
+            .collect();
+
+        Ok(Page::from_unsorted(rooms, page, |r| r.room_name.clone()))
     }
 
+    async fn get_one_room(&self, room_name: &RoomName) -> Result<Room, ErrService> {
+        self.tables
+            .read()
+            .await
+            .rooms
+            .values()
+            .find(|r| r.room_name == *room_name)
+            .cloned()
+            .ok_or(ErrService::Room(ErrRoom::RoomNotFound))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        domain::{Equipment, PageRequest, Room, RoomCapacity, RoomFilter, RoomLocation, SortOrder},
+        error::{ErrRoom, ErrService},
+        features::room::{dto::PatchRoomDto, service::RoomService},
+        infra::in_memory::in_memo_repo::InMemoryRepo,
+    };
+
     #[tokio::test]
     async fn add_and_list_rooms() {
-        let service: RoomService<InMemoryRepo<Room>> = InMemoryRepo::init_room_service().await;
+        let service: RoomService<InMemoryRepo> = InMemoryRepo::init_room_service().await;
 
-        let room1 = service.add_room("room1").await.unwrap();
+        let room1 = service
+            .add_room(Room::new("room1", 8).unwrap())
+            .await
+            .unwrap();
 
-        assert!(service.add_room("room1").await.is_err()); // already exists
-        assert!(service.add_room("room2").await.is_ok());
-        assert!(service.add_room("r").await.is_err()); // too short
         assert!(
             service
-                .add_room("thisnameistoolongforasingleroom")
+                .add_room(Room::new("room1", 8).unwrap())
                 .await
                 .is_err()
+        ); // already exists
+        assert!(
+            service
+                .add_room(Room::new("room2", 4).unwrap())
+                .await
+                .is_ok()
         );
+        assert!(Room::new("r", 4).is_err()); // too short
+        assert!(Room::new("thisnameistoolongforasingleroom", 4).is_err());
+        assert!(Room::new("room3", 0).is_err()); // no seats
+        assert!(Room::new("room3", RoomCapacity::MAX_SEATS + 1).is_err());
         assert!(service.list_cache_rooms().await.is_ok());
         assert!(service.is_exist_room(&room1.room_name).await.is_ok());
 
         assert!(service.get_cache_room_by_room_struct(&room1).await.is_ok());
     }
+
+    #[tokio::test]
+    async fn add_rooms_adds_all_or_none() {
+        let service: RoomService<InMemoryRepo> = InMemoryRepo::init_room_service().await;
+        service
+            .add_room(Room::new("room1", 8).unwrap())
+            .await
+            .unwrap();
+
+        let batch = vec![
+            Room::new("room2", 4).unwrap(),
+            Room::new("room3", 6).unwrap(),
+            Room::new("room2", 10).unwrap(),
+        ];
+        assert!(matches!(
+            service.add_rooms(batch).await,
+            Err(ErrService::Room(ErrRoom::AlreadyExist))
+        ));
+        assert!(matches!(
+            service.check_addable(&Room::new("room1", 2).unwrap(), &[]),
+            Err(ErrService::Room(ErrRoom::AlreadyExist))
+        ));
+        assert_eq!(service.list_cache_rooms().await.unwrap().len(), 1);
+
+        let batch = vec![
+            Room::new("room2", 4).unwrap(),
+            Room::new("room3", 6).unwrap(),
+        ];
+        assert_eq!(service.add_rooms(batch).await.unwrap().len(), 2);
+        assert_eq!(service.list_cache_rooms().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn rename_room_keeps_attributes() {
+        let service: RoomService<InMemoryRepo> = InMemoryRepo::init_room_service().await;
+
+        let equipment = Equipment::parse_all(&["projector", "Video_Conf"]).unwrap();
+        let room = Room::new("board", 12)
+            .unwrap()
+            .with_equipment(equipment.clone());
+        service.add_room(room).await.unwrap();
+
+        let renamed = service.update_room("board", "boardroom").await.unwrap();
+        assert_eq!(renamed.capacity.seats, 12);
+        assert_eq!(renamed.equipment, equipment);
+        assert!(Equipment::parse_all(&["jacuzzi"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn list_rooms_by_page() {
+        let service: RoomService<InMemoryRepo> = InMemoryRepo::init_room_service().await;
+
+        for (id, (name, seats)) in [("delta", 4), ("alpha", 12), ("charlie", 20), ("bravo", 8)]
+            .into_iter()
+            .enumerate()
+        {
+            let mut room = Room::new(name, seats)
+                .unwrap()
+                .with_location(RoomLocation::new(None, Some("HQ")));
+            room.id = id as i32;
+            service.add_room(room).await.unwrap();
+        }
+
+        let mut page = PageRequest {
+            limit: 2,
+            after: None,
+            order: SortOrder::Asc,
+        };
+        let first = service
+            .list_rooms(&RoomFilter::default(), &page)
+            .await
+            .unwrap();
+        let names: Vec<&str> = first
+            .items
+            .iter()
+            .map(|r| r.room_name.name.as_str())
+            .collect();
+        assert_eq!(names, ["ALPHA", "BRAVO"]);
+        assert!(first.has_more);
+
+        page.after = Some(first.items[1].room_name.clone());
+        let second = service
+            .list_rooms(&RoomFilter::default(), &page)
+            .await
+            .unwrap();
+        let names: Vec<&str> = second
+            .items
+            .iter()
+            .map(|r| r.room_name.name.as_str())
+            .collect();
+        assert_eq!(names, ["CHARLIE", "DELTA"]);
+        assert!(!second.has_more);
+
+        let filter = RoomFilter {
+            building: Some("HQ".to_string()),
+            min_capacity: Some(10),
+        };
+        let page = PageRequest {
+            limit: 10,
+            after: None,
+            order: SortOrder::Desc,
+        };
+        let big = service.list_rooms(&filter, &page).await.unwrap();
+        let names: Vec<&str> = big
+            .items
+            .iter()
+            .map(|r| r.room_name.name.as_str())
+            .collect();
+        assert_eq!(names, ["CHARLIE", "ALPHA"]);
+    }
+
+    #[tokio::test]
+    async fn patch_room_keeps_untouched_attributes() {
+        let service: RoomService<InMemoryRepo> = InMemoryRepo::init_room_service().await;
+
+        let mut board = Room::new("board", 12)
+            .unwrap()
+            .with_location(RoomLocation::new(Some(3), Some("HQ")));
+        board.id = 1;
+        let mut annex = Room::new("annex", 4).unwrap();
+        annex.id = 2;
+        service.add_room(board).await.unwrap();
+        service.add_room(annex).await.unwrap();
+
+        let patch = PatchRoomDto {
+            room_name: None,
+            capacity: Some(16),
+            floor: None,
+            building: None,
+            equipment: Some(vec!["whiteboard".to_string()]),
+            requires_approval: None,
+            time_zone: None,
+        };
+        let patched = patch.merge(service.get_room(1).unwrap()).unwrap();
+        let patched = service.replace_room(1, patched).await.unwrap();
+        assert_eq!(patched.room_name.name, "BOARD");
+        assert_eq!(patched.capacity.seats, 16);
+        assert_eq!(patched.location, RoomLocation::new(Some(3), Some("HQ")));
+        assert_eq!(service.get_room(1).unwrap(), patched);
+
+        let taken = Room::new("annex", 12).unwrap();
+        assert!(matches!(
+            service.replace_room(1, taken).await,
+            Err(ErrService::Room(ErrRoom::AlreadyExist))
+        ));
+        assert!(service.get_room(3).is_err());
+    }
 }
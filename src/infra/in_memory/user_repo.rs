@@ -1,94 +1,170 @@
-#[cfg(test)]
-mod test {
+use async_trait::async_trait;
+use uuid::Uuid;
 
-    use crate::{
-        domain::{User, UserName},
-        error::{ErrService, ErrUser},
-        features::user::{repo::UserRepo, service::UserService},
-        infra::in_memory::in_memo_repo::InMemoryRepo,
+use crate::{
+    domain::{FeedSubject, Page, PageRequest, Role, User, UserFilter, UserName},
+    error::{ErrRepo, ErrService, ErrUser},
+    features::user::repo::UserRepo,
+    infra::in_memory::in_memo_repo::{InMemoryRepo, Tables},
+};
+
+/// Deletes the user `id` unless they have open bookings, with their other bookings,
+/// waitlist entries and feed. Cancellations they made lose who made them.
+fn delete_user(tables: &mut Tables, id: Uuid) -> Result<bool, ErrService> {
+    if tables
+        .books
+        .values()
+        .any(|b| b.user_id.id == id && b.status.is_open())
+    {
+        return Err(ErrService::User(ErrUser::HasBookings));
+    }
+    let Some(user) = tables.users.remove(&id) else {
+        return Ok(false);
     };
-    use async_trait::async_trait;
-    use uuid::Uuid;
-
-    #[async_trait]
-    impl UserRepo for InMemoryRepo<User> {
-        async fn insert_user(&self, user: &User) -> Result<User, ErrService> {
-            self.repo.write().await.insert(user.clone());
-            Ok(user.clone())
-        }
-        async fn delete_user_by_name(&self, user_name: UserName) -> Result<bool, ErrService> {
-            let user = {
-                let read_guard = self.repo.read().await;
-                let user = read_guard
-                    .iter()
-                    .find(|v| v.user_name == user_name)
-                    .cloned();
-                user
-            };
-
-            let mut write_guard = self.repo.write().await;
-            if let Some(key) = user {
-                write_guard.remove(&key);
-            } else {
-                return Err(ErrService::User(ErrUser::UserNotFound));
-            }
 
-            Ok(true)
+    tables.books.retain(|_, b| b.user_id.id != id);
+    tables.waitlist.retain(|_, e| e.user_id.id != id);
+    tables
+        .feeds
+        .retain(|f| f.subject != FeedSubject::User(user.user_id.clone()));
+    for book in tables.books.values_mut() {
+        if let Some(cancellation) = book.cancellation.as_mut()
+            && cancellation.by.as_ref() == Some(&user.user_id)
+        {
+            cancellation.by = None;
         }
+    }
+    Ok(true)
+}
 
-        async fn update_user(&self, id: Uuid, new_name: UserName) -> Result<User, ErrService> {
-            let old_user = {
-                let read_guard = self.repo.read().await;
-                let user = read_guard.iter().find(|u| u.user_id.id == id).cloned();
-                user
-            };
-
-            let mut write_guard = self.repo.write().await;
-            if let Some(user) = old_user {
-                let new_user = User {
-                    user_id: user.user_id.clone(),
-                    user_name: new_name,
-                };
-                write_guard.remove(&user);
-                write_guard.insert(new_user.clone());
-
-                Ok(new_user)
-            } else {
-                Err(ErrService::User(ErrUser::UserNotFound))
-            }
-        }
-        async fn get_all_users(&self) -> Result<Vec<User>, ErrService> {
-            Ok(self.repo.read().await.iter().cloned().collect())
+#[async_trait]
+impl UserRepo for InMemoryRepo {
+    async fn insert_user(&self, user: &User) -> Result<User, ErrService> {
+        let mut tables = self.tables.write().await;
+        if tables.users.contains_key(&user.user_id.id) {
+            return Err(ErrService::Repo(ErrRepo::BadRequest));
         }
+        tables.check_user(user)?;
 
-        async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, ErrService> {
-            Ok(self
-                .repo
-                .read()
-                .await
-                .iter()
-                .find(|u| u.user_id.id == id)
-                .cloned())
-        }
+        tables.users.insert(user.user_id.id, user.clone());
+        Ok(user.clone())
+    }
 
-        async fn get_one_user(&self, user_name: &UserName) -> Result<User, ErrService> {
-            if let Some(user) = self
-                .repo
-                .read()
-                .await
+    async fn insert_users(&self, users: &[User]) -> Result<Vec<User>, ErrService> {
+        let mut tables = self.tables.write().await;
+        for (i, user) in users.iter().enumerate() {
+            if tables.users.contains_key(&user.user_id.id) {
+                return Err(ErrService::Repo(ErrRepo::BadRequest));
+            }
+            tables.check_user(user)?;
+            if users[..i]
                 .iter()
-                .find(|u| u.user_name == *user_name)
+                .any(|u| u.user_id == user.user_id || u.user_name == user.user_name)
             {
-                Ok(user.clone())
-            } else {
-                Err(ErrService::User(ErrUser::UserNotFound))
+                return Err(ErrService::Repo(ErrRepo::BadRequest));
             }
         }
+
+        for user in users {
+            tables.users.insert(user.user_id.id, user.clone());
+        }
+        Ok(users.to_vec())
     }
 
+    async fn update_user(&self, id: Uuid, new_name: UserName) -> Result<User, ErrService> {
+        let mut tables = self.tables.write().await;
+        let user = User {
+            user_name: new_name,
+            ..tables
+                .users
+                .get(&id)
+                .cloned()
+                .ok_or(ErrService::Repo(ErrRepo::BadRequest))?
+        };
+        tables.check_user(&user)?;
+
+        tables.users.insert(id, user.clone());
+        Ok(user)
+    }
+
+    async fn delete_user_by_name(&self, name: UserName) -> Result<bool, ErrService> {
+        let mut tables = self.tables.write().await;
+        let id = tables
+            .users
+            .values()
+            .find(|u| u.user_name == name)
+            .map(|u| u.user_id.id);
+
+        match id {
+            Some(id) => delete_user(&mut tables, id),
+            None => Ok(false),
+        }
+    }
+
+    async fn delete_user_by_id(&self, id: Uuid) -> Result<bool, ErrService> {
+        delete_user(&mut *self.tables.write().await, id)
+    }
+
+    async fn get_all_users(&self) -> Result<Vec<User>, ErrService> {
+        Ok(self.tables.read().await.users.values().cloned().collect())
+    }
+
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        page: &PageRequest<UserName>,
+    ) -> Result<Page<User>, ErrService> {
+        let users: Vec<User> = self
+            .tables
+            .read()
+            .await
+            .users
+            .values()
+            .filter(|u| filter.matches(u))
+            .cloned()
+            .collect();
+
+        Ok(Page::from_unsorted(users, page, |u| u.user_name.clone()))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, ErrService> {
+        Ok(self.tables.read().await.users.get(&id).cloned())
+    }
+
+    async fn get_one_user(&self, user_name: &UserName) -> Result<User, ErrService> {
+        self.tables
+            .read()
+            .await
+            .users
+            .values()
+            .find(|u| u.user_name == *user_name)
+            .cloned()
+            .ok_or(ErrService::User(ErrUser::UserNotFound))
+    }
+
+    async fn update_role(&self, id: Uuid, role: Role) -> Result<User, ErrService> {
+        let mut tables = self.tables.write().await;
+        let user = tables
+            .users
+            .get_mut(&id)
+            .ok_or(ErrService::User(ErrUser::UserNotFound))?;
+
+        user.role = role;
+        Ok(user.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        domain::{PageRequest, Role, SortOrder, UserFilter, UserName},
+        features::user::service::UserService,
+        infra::in_memory::in_memo_repo::InMemoryRepo,
+    };
+
     #[tokio::test]
     async fn print_all_users() {
-        let service: UserService<InMemoryRepo<User>> = InMemoryRepo::init_user_service().await;
+        let service: UserService<InMemoryRepo> = InMemoryRepo::init_user_service().await;
 
         assert!(service.add_user("Sophie").await.is_ok());
         assert!(service.add_user("Jordan").await.is_ok());
@@ -96,7 +172,7 @@ mod test {
 
     #[tokio::test]
     async fn add_and_list_user() {
-        let service: UserService<InMemoryRepo<User>> = InMemoryRepo::init_user_service().await;
+        let service: UserService<InMemoryRepo> = InMemoryRepo::init_user_service().await;
 
         let user_ok1 = service.add_user("Sophie").await;
         let user_ok2 = service.add_user("Jordan").await;
@@ -154,7 +230,7 @@ mod test {
 
     #[tokio::test]
     async fn update_user_name() {
-        let repo = InMemoryRepo::new().await;
+        let repo = InMemoryRepo::new();
         let service = UserService::new(repo);
 
         assert!(service.add_user("Sophie").await.is_ok());
@@ -187,4 +263,104 @@ mod test {
 
         assert!(service.update_user("Unexisting", "Bob").await.is_err());
     }
+
+    #[tokio::test]
+    async fn signups_are_members_and_roles_can_change() {
+        let service = InMemoryRepo::init_user_service().await;
+
+        let first = service.add_user("Sophie").await.unwrap();
+        let second = service.add_user("Jordan").await.unwrap();
+        assert_eq!(first.role, Role::Member);
+        assert_eq!(second.role, Role::Member);
+
+        let promoted = service
+            .set_role("jordan", "facility-manager")
+            .await
+            .unwrap();
+        assert_eq!(promoted.role, Role::FacilityManager);
+        assert!(service.set_role("jordan", "owner").await.is_err());
+        assert!(service.set_role("unexisting", "member").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn admins_are_bootstrapped_by_name() {
+        let service = InMemoryRepo::init_user_service().await;
+
+        let created = service.bootstrap_admin("Camille").await.unwrap();
+        assert_eq!(created.role, Role::Admin);
+        assert_eq!(service.get_user(created.user_id.id).unwrap(), created);
+        let again = service.bootstrap_admin("camille").await.unwrap();
+        assert_eq!(again.user_id, created.user_id);
+
+        let jordan = service.add_user("Jordan").await.unwrap();
+        let promoted = service.bootstrap_admin("jordan").await.unwrap();
+        assert_eq!(promoted.user_id, jordan.user_id);
+        assert_eq!(
+            service.get_user(jordan.user_id.id).unwrap().role,
+            Role::Admin
+        );
+        assert!(service.bootstrap_admin("j").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn list_users_by_role_in_reverse_name_order() {
+        let service = InMemoryRepo::init_user_service().await;
+
+        service.bootstrap_admin("alice").await.unwrap();
+        for name in ["bob", "carol", "dave"] {
+            service.add_user(name).await.unwrap();
+        }
+
+        let filter = UserFilter {
+            role: Some(Role::Member),
+        };
+        let mut page = PageRequest {
+            limit: 2,
+            after: None,
+            order: SortOrder::Desc,
+        };
+        let first = service.list_users(&filter, &page).await.unwrap();
+        let names: Vec<&str> = first
+            .items
+            .iter()
+            .map(|u| u.user_name.name.as_str())
+            .collect();
+        assert_eq!(names, ["dave", "carol"]);
+        assert!(first.has_more);
+
+        page.after = Some(first.items[1].user_name.clone());
+        let second = service.list_users(&filter, &page).await.unwrap();
+        let names: Vec<&str> = second
+            .items
+            .iter()
+            .map(|u| u.user_name.name.as_str())
+            .collect();
+        assert_eq!(names, ["bob"]); // alice is admin
+        assert!(!second.has_more);
+    }
+
+    #[tokio::test]
+    async fn patch_and_delete_user_by_id() {
+        let service = InMemoryRepo::init_user_service().await;
+
+        service.add_user("sophie").await.unwrap();
+        let jordan = service.add_user("jordan").await.unwrap();
+        let id = jordan.user_id.id;
+
+        let promoted = service
+            .patch_user(id, None, Some("facility_manager"))
+            .await
+            .unwrap();
+        assert_eq!(promoted.user_name, jordan.user_name);
+        assert_eq!(promoted.role, Role::FacilityManager);
+
+        let renamed = service.patch_user(id, Some("Jordy"), None).await.unwrap();
+        assert_eq!(renamed.user_name.name, "jordy");
+        assert_eq!(renamed.role, Role::FacilityManager);
+        assert!(service.patch_user(id, Some("sophie"), None).await.is_err());
+
+        assert!(service.delete_user_by_id(id).await.is_ok());
+        assert!(service.get_user(id).is_err());
+        assert!(service.delete_user_by_id(id).await.is_err());
+    }
 }
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+use crate::{
+    domain::{UserID, WaitlistEntry},
+    error::{ErrBook, ErrRepo, ErrService},
+    features::waitlist::repo::WaitlistRepo,
+    infra::in_memory::in_memo_repo::InMemoryRepo,
+};
+
+#[async_trait]
+impl WaitlistRepo for InMemoryRepo {
+    async fn insert_waitlist_entry(
+        &self,
+        entry: &WaitlistEntry,
+    ) -> Result<WaitlistEntry, ErrService> {
+        let mut tables = self.tables.write().await;
+        if !tables.rooms.contains_key(&entry.room_id)
+            || !tables.users.contains_key(&entry.user_id.id)
+        {
+            return Err(ErrService::Repo(ErrRepo::BadRequest));
+        }
+        if tables.waitlist.values().any(|e| {
+            e.room_id == entry.room_id
+                && e.user_id == entry.user_id
+                && e.date == entry.date
+                && e.slot == entry.slot
+        }) {
+            return Err(ErrService::Book(ErrBook::AlreadyQueued));
+        }
+
+        let entry = WaitlistEntry {
+            id: tables.next_entry_id(),
+            ..entry.clone()
+        };
+        tables.waitlist.insert(entry.id, entry.clone());
+        Ok(tables.join_entry(entry))
+    }
+
+    async fn get_waitlist(
+        &self,
+        room_id: i32,
+        date: NaiveDate,
+    ) -> Result<Vec<WaitlistEntry>, ErrService> {
+        let tables = self.tables.read().await;
+        let mut entries: Vec<WaitlistEntry> = tables
+            .waitlist
+            .values()
+            .filter(|e| e.room_id == room_id && e.date.date == date)
+            .map(|e| tables.join_entry(e.clone()))
+            .collect();
+        entries.sort_by_key(|e| (e.created_at, e.id));
+
+        Ok(entries)
+    }
+
+    async fn list_waitlist(&self, user: Option<&UserID>) -> Result<Vec<WaitlistEntry>, ErrService> {
+        let tables = self.tables.read().await;
+        let mut entries: Vec<WaitlistEntry> = tables
+            .waitlist
+            .values()
+            .filter(|e| user.is_none_or(|user| e.user_id == *user))
+            .map(|e| tables.join_entry(e.clone()))
+            .collect();
+        entries.sort_by_key(|e| (e.date.date, e.slot.start, e.created_at));
+
+        Ok(entries)
+    }
+
+    async fn get_waitlist_entry(&self, id: i32) -> Result<Option<WaitlistEntry>, ErrService> {
+        let tables = self.tables.read().await;
+        Ok(tables
+            .waitlist
+            .get(&id)
+            .map(|e| tables.join_entry(e.clone())))
+    }
+
+    async fn delete_waitlist_entry(&self, id: i32) -> Result<bool, ErrService> {
+        Ok(self.tables.write().await.waitlist.remove(&id).is_some())
+    }
+}
@@ -1,3 +1,7 @@
+pub mod backend;
 pub mod cache;
+pub mod clock;
 pub mod db;
 pub mod in_memory;
+pub mod notifier;
+pub mod sqlite;
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::domain::{Book, UserID};
+
+/// Something users are told about, outside of the request that caused it.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    /// A waitlist entry became this booking, pending when its room requires approval.
+    WaitlistPromoted(Book),
+    /// A facility manager approved this pending booking.
+    BookingApproved(Book),
+    /// A facility manager rejected this pending booking.
+    BookingRejected(Book),
+    /// This pending booking expired without a decision.
+    BookingExpired(Book),
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, user_id: &UserID, notification: Notification);
+}
+
+/// Writes notifications to the log, until a mail or chat channel is plugged in.
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, user_id: &UserID, notification: Notification) {
+        match notification {
+            Notification::WaitlistPromoted(book) => info!(
+                user_id = %user_id.id,
+                book_id = book.id,
+                room = %book.room_name.name,
+                date = %book.date.date,
+                status = book.status.as_str(),
+                "Waitlist entry promoted to a booking"
+            ),
+            Notification::BookingApproved(book) => info!(
+                user_id = %user_id.id,
+                book_id = book.id,
+                "Booking approved"
+            ),
+            Notification::BookingRejected(book) => info!(
+                user_id = %user_id.id,
+                book_id = book.id,
+                reason = book.cancellation.and_then(|c| c.reason).unwrap_or_default(),
+                "Booking rejected"
+            ),
+            Notification::BookingExpired(book) => info!(
+                user_id = %user_id.id,
+                book_id = book.id,
+                "Booking request expired without a decision"
+            ),
+        }
+    }
+}
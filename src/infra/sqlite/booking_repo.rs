@@ -0,0 +1,603 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::SqliteExecutor;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        Book, BookDateRange, BookFilter, BookKey, BookSlot, BookStatus, Cancellation, Page,
+        PageRequest, Room,
+    },
+    error::{ErrBook, ErrRepo, ErrService},
+    features::book::{dto::BookRowDto, repo::BookRepo},
+    infra::{
+        db::keyset,
+        sqlite::{
+            SqliteClient, is_overlap_violation,
+            room_repo::{SELECT_ROOM, SqliteRoomRow, rooms},
+        },
+    },
+};
+
+/// Book columns with the room and user names joined in, over a `b` relation.
+/// SQLite can't join the rows a write returns, so writes return ids read back with it.
+const SELECT_BOOK: &str = "SELECT b.id, b.room_id, b.user_id, r.room_name, u.user_name, r.time_zone, b.date, b.start_time, b.end_time, b.attendees, b.series_id, b.status, b.cancelled_at, b.cancelled_by, b.cancel_reason, b.created_at, b.starts_at, b.ends_at FROM books b JOIN rooms r ON r.id = b.room_id JOIN users u ON u.user_id = b.user_id";
+
+const INSERT_BOOK: &str = "INSERT INTO books (room_id, user_id, date, start_time, end_time, attendees, series_id, status, created_at, starts_at, ends_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11) RETURNING id";
+
+const UPDATE_BOOK: &str = "UPDATE books SET room_id = ?2, user_id = ?3, date = ?4, start_time = ?5, end_time = ?6, attendees = ?7, status = ?8, starts_at = ?9, ends_at = ?10 WHERE id = ?1 RETURNING id";
+
+/// Statuses of the bookings kept for the record only.
+const SETTLED: &str = "('cancelled', 'rejected', 'expired')";
+
+/// Overlapping bookings are rejected by the `books_no_overlap` triggers, standing for
+/// the PostgreSQL constraint of the same name.
+fn map_write_err(err: sqlx::Error) -> ErrService {
+    if is_overlap_violation(&err) {
+        ErrService::Book(ErrBook::AlreadyBooked)
+    } else {
+        ErrService::Repo(ErrRepo::BadRequest)
+    }
+}
+
+fn books(rows: Vec<BookRowDto>) -> Result<Vec<Book>, ErrService> {
+    rows.into_iter()
+        .map(|dto| Book::try_from(dto).map_err(|_| ErrService::Book(ErrBook::UnableToRead)))
+        .collect()
+}
+
+async fn fetch_book(executor: impl SqliteExecutor<'_>, id: i32) -> Result<Book, ErrService> {
+    let row = sqlx::query_as::<_, BookRowDto>(&format!("{SELECT_BOOK} WHERE b.id = ?1"))
+        .bind(id)
+        .fetch_one(executor)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+    Book::try_from(row)
+}
+
+async fn insert_book(executor: impl SqliteExecutor<'_>, book: &Book) -> Result<i32, ErrService> {
+    sqlx::query_scalar::<_, i32>(INSERT_BOOK)
+        .bind(book.room_id)
+        .bind(book.user_id.id)
+        .bind(book.date.date)
+        .bind(book.slot.start)
+        .bind(book.slot.end)
+        .bind(book.attendees)
+        .bind(book.series_id)
+        .bind(book.status.as_str())
+        .bind(book.created_at)
+        .bind(book.starts_at)
+        .bind(book.ends_at)
+        .fetch_one(executor)
+        .await
+        .map_err(map_write_err)
+}
+
+async fn update_book(executor: impl SqliteExecutor<'_>, book: &Book) -> Result<i32, ErrService> {
+    sqlx::query_scalar::<_, i32>(UPDATE_BOOK)
+        .bind(book.id)
+        .bind(book.room_id)
+        .bind(book.user_id.id)
+        .bind(book.date.date)
+        .bind(book.slot.start)
+        .bind(book.slot.end)
+        .bind(book.attendees)
+        .bind(book.status.as_str())
+        .bind(book.starts_at)
+        .bind(book.ends_at)
+        .fetch_one(executor)
+        .await
+        .map_err(map_write_err)
+}
+
+#[async_trait]
+impl BookRepo for SqliteClient {
+    async fn insert_book(&self, book: &Book) -> Result<Book, ErrService> {
+        let id = insert_book(&self.pool, book).await?;
+        fetch_book(&self.pool, id).await
+    }
+
+    async fn update_book(&self, book: &Book) -> Result<Book, ErrService> {
+        let id = update_book(&self.pool, book).await?;
+        fetch_book(&self.pool, id).await
+    }
+
+    async fn get_all_books(&self) -> Result<Vec<Book>, ErrService> {
+        let rows = sqlx::query_as::<_, BookRowDto>(&format!(
+            "{SELECT_BOOK} ORDER BY b.date, b.start_time"
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        books(rows)
+    }
+
+    async fn list_books(
+        &self,
+        filter: &BookFilter,
+        page: &PageRequest<BookKey>,
+    ) -> Result<Page<Book>, ErrService> {
+        let (cmp, dir) = keyset(page.order);
+        let rows = sqlx::query_as::<_, BookRowDto>(&format!(
+            "{SELECT_BOOK} WHERE (?1 IS NULL OR r.room_name = ?1) \
+            AND (?2 IS NULL OR u.user_name = ?2) \
+            AND (?3 IS NULL OR b.date >= ?3) \
+            AND (?4 IS NULL OR b.date <= ?4) \
+            AND (?5 IS NULL OR (b.date, b.start_time, b.id) {cmp} (?5, ?6, ?7)) \
+            AND (b.status = ?9 OR (?9 IS NULL AND (?10 OR b.status NOT IN {SETTLED}))) \
+            ORDER BY b.date {dir}, b.start_time {dir}, b.id {dir} LIMIT ?8"
+        ))
+        .bind(filter.room.as_ref().map(|r| &r.name))
+        .bind(filter.user.as_ref().map(|u| &u.name))
+        .bind(filter.from.as_ref().map(|d| d.date))
+        .bind(filter.to.as_ref().map(|d| d.date))
+        .bind(page.after.as_ref().map(|k| k.date))
+        .bind(page.after.as_ref().map(|k| k.start))
+        .bind(page.after.as_ref().map(|k| k.id))
+        .bind(i64::from(page.limit) + 1)
+        .bind(filter.status.map(|s| s.as_str()))
+        .bind(filter.include_cancelled)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(Page::from_overfetched(books(rows)?, page.limit))
+    }
+
+    async fn cancel_book(&self, id: i32, cancellation: &Cancellation) -> Result<Book, ErrService> {
+        let id = sqlx::query_scalar::<_, i32>(&format!(
+            "UPDATE books SET status = 'cancelled', cancelled_at = ?2, cancelled_by = ?3, cancel_reason = ?4 \
+            WHERE id = ?1 AND status NOT IN {SETTLED} RETURNING id"
+        ))
+        .bind(id)
+        .bind(cancellation.at)
+        .bind(cancellation.by.as_ref().map(|u| u.id))
+        .bind(cancellation.reason.as_deref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?
+        .ok_or(ErrBook::Cancelled)?;
+
+        fetch_book(&self.pool, id).await
+    }
+
+    async fn set_book_status(&self, id: i32, status: BookStatus) -> Result<Book, ErrService> {
+        let id = sqlx::query_scalar::<_, i32>(&format!(
+            "UPDATE books SET status = ?2 WHERE id = ?1 AND status NOT IN {SETTLED} RETURNING id"
+        ))
+        .bind(id)
+        .bind(status.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?
+        .ok_or(ErrBook::Cancelled)?;
+
+        fetch_book(&self.pool, id).await
+    }
+
+    async fn resolve_pending_book(
+        &self,
+        id: i32,
+        status: BookStatus,
+        resolution: Option<&Cancellation>,
+    ) -> Result<Book, ErrService> {
+        let id = sqlx::query_scalar::<_, i32>(
+            "UPDATE books SET status = ?2, cancelled_at = ?3, cancelled_by = ?4, cancel_reason = ?5 \
+            WHERE id = ?1 AND status = 'pending' RETURNING id",
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(resolution.map(|r| r.at))
+        .bind(resolution.and_then(|r| r.by.as_ref()).map(|u| u.id))
+        .bind(resolution.and_then(|r| r.reason.as_deref()))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_write_err)?
+        .ok_or(ErrBook::NotPending)?;
+
+        fetch_book(&self.pool, id).await
+    }
+
+    async fn cancel_all_books(&self, cancellation: &Cancellation) -> Result<Vec<Book>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let ids = sqlx::query_scalar::<_, i32>(&format!(
+            "UPDATE books SET status = 'cancelled', cancelled_at = ?1, cancelled_by = ?2, cancel_reason = ?3 \
+            WHERE status NOT IN {SETTLED} RETURNING id"
+        ))
+        .bind(cancellation.at)
+        .bind(cancellation.by.as_ref().map(|u| u.id))
+        .bind(cancellation.reason.as_deref())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let mut cancelled = Vec::with_capacity(ids.len());
+        for id in ids {
+            cancelled.push(fetch_book(&mut *tx, id).await?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(cancelled)
+    }
+
+    async fn get_one_book(&self, book: &Book) -> Result<Option<Book>, ErrService> {
+        self.find_book(book.id).await
+    }
+
+    async fn find_book(&self, id: i32) -> Result<Option<Book>, ErrService> {
+        let row = sqlx::query_as::<_, BookRowDto>(&format!("{SELECT_BOOK} WHERE b.id = ?1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| ErrRepo::BadRequest)?;
+
+        Ok(row.map(Book::try_from).transpose()?)
+    }
+
+    async fn purge_books_before(&self, date: NaiveDate) -> Result<u64, ErrService> {
+        let result = sqlx::query("DELETE FROM books WHERE date < ?1")
+            .bind(date)
+            .execute(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn insert_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut inserted = Vec::with_capacity(books.len());
+        for book in books {
+            let id = insert_book(&mut *tx, book).await?;
+            inserted.push(fetch_book(&mut *tx, id).await?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(inserted)
+    }
+
+    async fn update_books(&self, books: &[Book]) -> Result<Vec<Book>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut updated = Vec::with_capacity(books.len());
+        for book in books {
+            let id = update_book(&mut *tx, book).await?;
+            updated.push(fetch_book(&mut *tx, id).await?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(updated)
+    }
+
+    async fn cancel_series(
+        &self,
+        series_id: Uuid,
+        from: Option<NaiveDate>,
+        cancellation: &Cancellation,
+    ) -> Result<Vec<Book>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let ids = sqlx::query_scalar::<_, i32>(&format!(
+            "UPDATE books SET status = 'cancelled', cancelled_at = ?3, cancelled_by = ?4, cancel_reason = ?5 \
+            WHERE series_id = ?1 AND (?2 IS NULL OR date >= ?2) AND status NOT IN {SETTLED} RETURNING id"
+        ))
+        .bind(series_id)
+        .bind(from)
+        .bind(cancellation.at)
+        .bind(cancellation.by.as_ref().map(|u| u.id))
+        .bind(cancellation.reason.as_deref())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        let mut cancelled = Vec::with_capacity(ids.len());
+        for id in ids {
+            cancelled.push(fetch_book(&mut *tx, id).await?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(cancelled)
+    }
+
+    async fn is_room_already_booked(
+        &self,
+        room_id: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    ) -> Result<bool, ErrService> {
+        let result = sqlx::query(&format!(
+            "SELECT 1 FROM books WHERE room_id = ?1 AND julianday(starts_at) < julianday(?3) AND julianday(ends_at) > julianday(?2) AND status NOT IN {SETTLED} LIMIT 1"
+        ))
+        .bind(room_id)
+        .bind(starts_at)
+        .bind(ends_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| ErrService::Repo(ErrRepo::BadRequest))?;
+
+        Ok(result.is_some())
+    }
+
+    async fn get_available_rooms(
+        &self,
+        range: &BookDateRange,
+        slot: Option<&BookSlot>,
+    ) -> Result<Vec<Room>, ErrService> {
+        let rows = sqlx::query_as::<_, SqliteRoomRow>(&format!(
+            "{SELECT_ROOM} r WHERE NOT EXISTS ( \
+                SELECT 1 FROM books b WHERE b.room_id = r.id AND b.status NOT IN {SETTLED} \
+                AND b.date BETWEEN ?1 AND ?2 \
+                AND (?3 IS NULL OR (b.start_time < ?4 AND b.end_time > ?3)) \
+            ) ORDER BY r.room_name"
+        ))
+        .bind(range.from.date)
+        .bind(range.to.date)
+        .bind(slot.map(|s| s.start))
+        .bind(slot.map(|s| s.end))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        rooms(rows)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::Tz;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::{
+        domain::{Book, BookDate, BookSlot, BookStatus, Cancellation, Room, User},
+        error::{ErrBook, ErrRoom, ErrService, ErrUser},
+        features::{book::repo::BookRepo, room::repo::RoomRepo, user::repo::UserRepo},
+        infra::{backend::Backend, sqlite::SqliteClient},
+    };
+
+    /// A migrated database in memory; one connection, each having its own.
+    async fn client() -> SqliteClient {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let client = SqliteClient::new(pool);
+        client.run_migrations().await.unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn rejects_overlaps_and_deletes_with_bookings() {
+        let db = client().await;
+        let room = db
+            .insert_room(&Room::new("alpha", 8).unwrap())
+            .await
+            .unwrap();
+        let user = db.insert_user(&User::new("sophie").unwrap()).await.unwrap();
+        let date = BookDate::new("20.12.26").unwrap();
+
+        let book = Book::new(
+            &room,
+            &user,
+            date.clone(),
+            BookSlot::new("09:00", "10:00").unwrap(),
+            2,
+            Utc::now(),
+        );
+        let created_at = book.created_at;
+        let book = db.insert_book(&book).await.unwrap();
+        assert_eq!(book.room_name, room.room_name);
+        assert_eq!(book.created_at, created_at);
+        assert_eq!(book.slot, BookSlot::new("09:00", "10:00").unwrap());
+
+        let overlapping = Book::new(
+            &room,
+            &user,
+            date.clone(),
+            BookSlot::new("09:30", "10:30").unwrap(),
+            1,
+            Utc::now(),
+        );
+        assert!(matches!(
+            db.insert_book(&overlapping).await,
+            Err(ErrService::Book(ErrBook::AlreadyBooked))
+        ));
+        let next = Book::new(
+            &room,
+            &user,
+            date.clone(),
+            BookSlot::new("10:00", "11:00").unwrap(),
+            1,
+            Utc::now(),
+        );
+        assert!(db.insert_book(&next).await.is_ok());
+
+        let cancellation = Cancellation {
+            at: chrono::Utc::now(),
+            by: Some(user.user_id.clone()),
+            reason: Some("moved".to_string()),
+        };
+        let cancelled = db.cancel_book(book.id, &cancellation).await.unwrap();
+        assert_eq!(cancelled.status, BookStatus::Cancelled);
+        assert_eq!(cancelled.cancellation, Some(cancellation.clone()));
+        let rebooked = Book {
+            slot: BookSlot::new("09:00", "10:00").unwrap(),
+            ..overlapping
+        }
+        .with_instants();
+        assert!(db.insert_book(&rebooked).await.is_ok());
+        assert!(matches!(
+            db.cancel_book(book.id, &cancellation).await,
+            Err(ErrService::Book(ErrBook::Cancelled))
+        ));
+
+        assert!(matches!(
+            db.delete_room_by_id(room.id).await,
+            Err(ErrService::Room(ErrRoom::HasBookings))
+        ));
+        assert!(matches!(
+            db.delete_user_by_id(user.user_id.id).await,
+            Err(ErrService::User(ErrUser::HasBookings))
+        ));
+        assert_eq!(
+            db.purge_books_before(date.date.succ_opt().unwrap())
+                .await
+                .unwrap(),
+            3
+        );
+        assert!(db.delete_room_by_id(room.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn deletes_go_with_the_history_of_their_user_or_room() {
+        let db = client().await;
+        let room = db
+            .insert_room(&Room::new("alpha", 8).unwrap())
+            .await
+            .unwrap();
+        let user = db.insert_user(&User::new("sophie").unwrap()).await.unwrap();
+        let manager = db
+            .insert_user(&User::new("camille").unwrap())
+            .await
+            .unwrap();
+        let book = |start, end| {
+            Book::new(
+                &room,
+                &user,
+                BookDate::new("20.12.26").unwrap(),
+                BookSlot::new(start, end).unwrap(),
+                1,
+                Utc::now(),
+            )
+        };
+        let first = db.insert_book(&book("09:00", "10:00")).await.unwrap();
+        let second = db.insert_book(&book("10:00", "11:00")).await.unwrap();
+        let cancellation = Cancellation {
+            at: Utc::now(),
+            by: Some(manager.user_id.clone()),
+            reason: None,
+        };
+        db.cancel_book(first.id, &cancellation).await.unwrap();
+
+        // the confirmed booking holds both back, nothing is deleted
+        assert!(matches!(
+            db.delete_user_by_id(user.user_id.id).await,
+            Err(ErrService::User(ErrUser::HasBookings))
+        ));
+        assert!(matches!(
+            db.delete_room_by_id(room.id).await,
+            Err(ErrService::Room(ErrRoom::HasBookings))
+        ));
+        assert_eq!(db.get_all_books().await.unwrap().len(), 2);
+
+        db.cancel_book(second.id, &cancellation).await.unwrap();
+        assert!(db.delete_user_by_id(user.user_id.id).await.unwrap());
+        assert!(db.get_all_books().await.unwrap().is_empty());
+        assert!(db.delete_room_by_id(room.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn rows_the_domain_rejects_are_unreadable() {
+        let db = client().await;
+        let room = db
+            .insert_room(&Room::new("alpha", 8).unwrap())
+            .await
+            .unwrap();
+        let user = db.insert_user(&User::new("sophie").unwrap()).await.unwrap();
+        let book = Book::new(
+            &room,
+            &user,
+            BookDate::new("20.12.26").unwrap(),
+            BookSlot::new("09:00", "10:00").unwrap(),
+            1,
+            Utc::now(),
+        );
+        db.insert_book(&book).await.unwrap();
+
+        // set by hand, past the service's checks
+        sqlx::query("UPDATE rooms SET time_zone = 'Mars/Olympus_Mons'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            db.get_all_books().await,
+            Err(ErrService::Book(ErrBook::UnableToRead))
+        ));
+    }
+
+    #[tokio::test]
+    async fn time_zone_changes_move_the_stored_instants() {
+        let db = client().await;
+        let room = db
+            .insert_room(&Room::new("alpha", 8).unwrap())
+            .await
+            .unwrap();
+        let user = db.insert_user(&User::new("sophie").unwrap()).await.unwrap();
+        let date = BookDate::new("20.12.26").unwrap();
+        for (start, end) in [("09:00", "10:00"), ("10:00", "11:00")] {
+            let book = Book::new(
+                &room,
+                &user,
+                date.clone(),
+                BookSlot::new(start, end).unwrap(),
+                1,
+                Utc::now(),
+            );
+            db.insert_book(&book).await.unwrap();
+        }
+
+        // back-to-back bookings all move an hour on, the first onto the second's old hour
+        let room = db
+            .update_room(&room.with_time_zone(Tz::Atlantic__Azores))
+            .await
+            .unwrap();
+        let books = db.get_all_books().await.unwrap();
+        let starts: Vec<_> = books.iter().map(|b| (b.time_zone, b.starts_at)).collect();
+        assert_eq!(
+            starts,
+            [
+                (
+                    Tz::Atlantic__Azores,
+                    Utc.with_ymd_and_hms(2026, 12, 20, 10, 0, 0).unwrap()
+                ),
+                (
+                    Tz::Atlantic__Azores,
+                    Utc.with_ymd_and_hms(2026, 12, 20, 11, 0, 0).unwrap()
+                ),
+            ]
+        );
+
+        // overlaps are told on the instants
+        let same_hour = Book::new(
+            &room,
+            &user,
+            date,
+            BookSlot::new("10:30", "11:00").unwrap(),
+            1,
+            Utc::now(),
+        );
+        assert!(matches!(
+            db.insert_book(&same_hour).await,
+            Err(ErrService::Book(ErrBook::AlreadyBooked))
+        ));
+        assert!(
+            db.is_room_already_booked(room.id, same_hour.starts_at, same_hour.ends_at)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !db.is_room_already_booked(
+                room.id,
+                books[1].ends_at,
+                books[1].ends_at + chrono::Duration::hours(1)
+            )
+            .await
+            .unwrap()
+        );
+    }
+}
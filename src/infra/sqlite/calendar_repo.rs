@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    domain::{CalendarFeed, FeedSubject},
+    error::{ErrRepo, ErrService},
+    features::calendar::{dto::CalendarFeedRowDto, repo::CalendarRepo},
+    infra::sqlite::SqliteClient,
+};
+
+/// The room id and user id columns of a feed of `subject`, one of them null.
+fn subject_ids(subject: &FeedSubject) -> (Option<i32>, Option<Uuid>) {
+    match subject {
+        FeedSubject::Room(id) => (Some(*id), None),
+        FeedSubject::User(user_id) => (None, Some(user_id.id)),
+    }
+}
+
+#[async_trait]
+impl CalendarRepo for SqliteClient {
+    async fn get_feed(&self, subject: &FeedSubject) -> Result<Option<CalendarFeed>, ErrService> {
+        let (room_id, user_id) = subject_ids(subject);
+        let row = sqlx::query_as::<_, CalendarFeedRowDto>(
+            "SELECT token, room_id, user_id FROM calendar_feeds WHERE room_id = ?1 OR user_id = ?2",
+        )
+        .bind(room_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(row.map(CalendarFeed::try_from).transpose()?)
+    }
+
+    async fn find_feed(&self, token: &str) -> Result<Option<CalendarFeed>, ErrService> {
+        let row = sqlx::query_as::<_, CalendarFeedRowDto>(
+            "SELECT token, room_id, user_id FROM calendar_feeds WHERE token = ?1",
+        )
+        .bind(token)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(row.map(CalendarFeed::try_from).transpose()?)
+    }
+
+    async fn insert_feed(&self, feed: &CalendarFeed) -> Result<Option<CalendarFeed>, ErrService> {
+        let (room_id, user_id) = subject_ids(&feed.subject);
+        let row = sqlx::query_as::<_, CalendarFeedRowDto>(
+            "INSERT INTO calendar_feeds (token, room_id, user_id) VALUES (?1, ?2, ?3) \
+            ON CONFLICT DO NOTHING RETURNING token, room_id, user_id",
+        )
+        .bind(&feed.token)
+        .bind(room_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(row.map(CalendarFeed::try_from).transpose()?)
+    }
+
+    async fn delete_feed(&self, subject: &FeedSubject) -> Result<bool, ErrService> {
+        let (room_id, user_id) = subject_ids(subject);
+        let result = sqlx::query("DELETE FROM calendar_feeds WHERE room_id = ?1 OR user_id = ?2")
+            .bind(room_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(result.rows_affected() != 0)
+    }
+}
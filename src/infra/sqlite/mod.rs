@@ -0,0 +1,61 @@
+//! The SQLite backend, for single-instance installs without a PostgreSQL server.
+
+pub mod booking_repo;
+pub mod calendar_repo;
+pub mod room_repo;
+pub mod user_repo;
+pub mod waitlist_repo;
+
+use async_trait::async_trait;
+use sqlx::{Pool, Sqlite, error::ErrorKind, migrate::Migrator};
+use tracing::info;
+
+use crate::{error::ErrService, infra::backend::Backend};
+
+/// SQL migrations from `migrations_sqlite/`, embedded in the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+/// Message of the trigger standing for the `books_no_overlap` constraint.
+const NO_OVERLAP: &str = "books_no_overlap";
+/// Message of the violations of a foreign key, whatever their code.
+const FOREIGN_KEY_FAILED: &str = "FOREIGN KEY constraint failed";
+
+pub fn is_overlap_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(|e| e.message().contains(NO_OVERLAP))
+}
+
+/// `ON DELETE RESTRICT` violations come as `SQLITE_CONSTRAINT_TRIGGER`, known only by
+/// their message.
+pub fn is_foreign_key_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error().is_some_and(|e| {
+        matches!(e.kind(), ErrorKind::ForeignKeyViolation)
+            || e.message().contains(FOREIGN_KEY_FAILED)
+    })
+}
+
+pub fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(|e| matches!(e.kind(), ErrorKind::UniqueViolation))
+}
+
+#[derive(Debug, Clone)]
+pub struct SqliteClient {
+    pub pool: Pool<Sqlite>,
+}
+
+impl SqliteClient {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Backend for SqliteClient {
+    async fn run_migrations(&self) -> Result<(), ErrService> {
+        MIGRATOR.run(&self.pool).await.map_err(sqlx::Error::from)?;
+
+        info!("Database schema up to date");
+        Ok(())
+    }
+}
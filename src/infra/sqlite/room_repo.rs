@@ -0,0 +1,227 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveTime};
+use sqlx::SqliteExecutor;
+
+use crate::{
+    domain::{Page, PageRequest, Room, RoomFilter, RoomName, instant_in},
+    error::{ErrRepo, ErrRoom, ErrService},
+    features::room::{dto::RoomRowDto, repo::RoomRepo},
+    infra::{
+        db::keyset,
+        sqlite::{SqliteClient, is_foreign_key_violation},
+    },
+};
+
+pub(super) const SELECT_ROOM: &str = "SELECT id, room_name, capacity, floor, building, equipment, requires_approval, time_zone FROM rooms";
+
+const INSERT_ROOM: &str = "INSERT INTO rooms (room_name, capacity, floor, building, equipment, requires_approval, time_zone) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) RETURNING id, room_name, capacity, floor, building, equipment, requires_approval, time_zone";
+
+/// Where bookings wait while their room's time zone changes, long before any booking.
+const PARKED_AT: &str = "0001-01-01T00:00:00+00:00";
+
+/// A room row, its equipment tags separated by commas.
+#[derive(sqlx::FromRow)]
+pub(super) struct SqliteRoomRow {
+    id: i32,
+    room_name: String,
+    capacity: i32,
+    floor: Option<i32>,
+    building: Option<String>,
+    equipment: String,
+    requires_approval: bool,
+    time_zone: String,
+}
+
+impl TryFrom<SqliteRoomRow> for Room {
+    type Error = ErrService;
+
+    fn try_from(row: SqliteRoomRow) -> Result<Self, Self::Error> {
+        Ok(Room::try_from(RoomRowDto {
+            id: row.id,
+            room_name: row.room_name,
+            capacity: row.capacity,
+            floor: row.floor,
+            building: row.building,
+            equipment: row
+                .equipment
+                .split(',')
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+            requires_approval: row.requires_approval,
+            time_zone: row.time_zone,
+        })?)
+    }
+}
+
+fn equipment(room: &Room) -> String {
+    room.equipment
+        .iter()
+        .map(|e| e.as_str())
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+pub(super) fn rooms(rows: Vec<SqliteRoomRow>) -> Result<Vec<Room>, ErrService> {
+    rows.into_iter()
+        .map(|row| Room::try_from(row).map_err(|_| ErrService::Repo(ErrRepo::DoesntExist)))
+        .collect()
+}
+
+async fn insert_room(executor: impl SqliteExecutor<'_>, room: &Room) -> Result<Room, ErrService> {
+    let row = sqlx::query_as::<_, SqliteRoomRow>(INSERT_ROOM)
+        .bind(&room.room_name.name)
+        .bind(room.capacity.seats)
+        .bind(room.location.floor)
+        .bind(&room.location.building)
+        .bind(equipment(room))
+        .bind(room.requires_approval)
+        .bind(room.time_zone.name())
+        .fetch_one(executor)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+    Room::try_from(row)
+}
+
+#[async_trait]
+impl RoomRepo for SqliteClient {
+    async fn insert_room(&self, room: &Room) -> Result<Room, ErrService> {
+        insert_room(&self.pool, room).await
+    }
+
+    async fn insert_rooms(&self, rooms: &[Room]) -> Result<Vec<Room>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut inserted = Vec::with_capacity(rooms.len());
+        for room in rooms {
+            inserted.push(insert_room(&mut *tx, room).await?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(inserted)
+    }
+
+    async fn update_room(&self, room: &Room) -> Result<Room, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let row = sqlx::query_as::<_, SqliteRoomRow>(
+            "UPDATE rooms SET room_name = ?1, capacity = ?2, floor = ?3, building = ?4, equipment = ?5, requires_approval = ?7, time_zone = ?8 WHERE id = ?6 RETURNING id, room_name, capacity, floor, building, equipment, requires_approval, time_zone",
+        )
+        .bind(&room.room_name.name)
+        .bind(room.capacity.seats)
+        .bind(room.location.floor)
+        .bind(&room.location.building)
+        .bind(equipment(room))
+        .bind(room.id)
+        .bind(room.requires_approval)
+        .bind(room.time_zone.name())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        // Bookings keep their wall-clock times, now read in the room's zone. SQLite
+        // knows no zones: the instants are worked out here. The overlap triggers check
+        // each row as it's moved, so the bookings are first parked on an empty instant,
+        // overlapping nothing, lest one lands on another not moved yet.
+        sqlx::query("UPDATE books SET starts_at = ?1, ends_at = ?1 WHERE room_id = ?2")
+            .bind(PARKED_AT)
+            .bind(room.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+        let books = sqlx::query_as::<_, (i32, NaiveDate, NaiveTime, NaiveTime)>(
+            "SELECT id, date, start_time, end_time FROM books WHERE room_id = ?1",
+        )
+        .bind(room.id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        for (id, date, start, end) in books {
+            sqlx::query("UPDATE books SET starts_at = ?1, ends_at = ?2 WHERE id = ?3")
+                .bind(instant_in(room.time_zone, date, start))
+                .bind(instant_in(room.time_zone, date, end))
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|_e| ErrRepo::BadRequest)?;
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+
+        Room::try_from(row)
+    }
+
+    async fn delete_room_by_id(&self, room_id: i32) -> Result<bool, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        sqlx::query(
+            "DELETE FROM books WHERE room_id = ?1 AND status NOT IN ('pending', 'confirmed')",
+        )
+        .bind(room_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query("DELETE FROM rooms WHERE id = ?1")
+            .bind(room_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                if is_foreign_key_violation(&e) {
+                    ErrService::Room(ErrRoom::HasBookings)
+                } else {
+                    ErrService::Repo(ErrRepo::BadRequest)
+                }
+            })?;
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(row.rows_affected() != 0)
+    }
+
+    async fn get_all_rooms(&self) -> Result<Vec<Room>, ErrService> {
+        let rows = sqlx::query_as::<_, SqliteRoomRow>(SELECT_ROOM)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+
+        rooms(rows)
+    }
+
+    async fn list_rooms(
+        &self,
+        filter: &RoomFilter,
+        page: &PageRequest<RoomName>,
+    ) -> Result<Page<Room>, ErrService> {
+        let (cmp, dir) = keyset(page.order);
+        let rows = sqlx::query_as::<_, SqliteRoomRow>(&format!(
+            "{SELECT_ROOM} WHERE (?1 IS NULL OR building = ?1) \
+            AND (?2 IS NULL OR capacity >= ?2) \
+            AND (?3 IS NULL OR room_name {cmp} ?3) \
+            ORDER BY room_name {dir} LIMIT ?4"
+        ))
+        .bind(&filter.building)
+        .bind(filter.min_capacity)
+        .bind(page.after.as_ref().map(|r| &r.name))
+        .bind(i64::from(page.limit) + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(Page::from_overfetched(rooms(rows)?, page.limit))
+    }
+
+    async fn get_one_room(&self, room_name: &RoomName) -> Result<Room, ErrService> {
+        let row =
+            sqlx::query_as::<_, SqliteRoomRow>(&format!("{SELECT_ROOM} WHERE room_name = ?1"))
+                .bind(&room_name.name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|_e| ErrRepo::BadRequest)?;
+
+        match row {
+            Some(row) => Room::try_from(row),
+            None => Err(ErrService::Room(ErrRoom::RoomNotFound)),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use sqlx::SqliteExecutor;
+use uuid::Uuid;
+
+use crate::{
+    domain::{Page, PageRequest, Role, User, UserFilter, UserName},
+    error::{ErrRepo, ErrService, ErrUser},
+    features::user::{dto::UserRowDto, repo::UserRepo},
+    infra::{
+        db::keyset,
+        sqlite::{SqliteClient, is_foreign_key_violation},
+    },
+};
+
+const INSERT_USER: &str = "INSERT INTO users (user_id, user_name, role) VALUES (?1, ?2, ?3) RETURNING user_id, user_name, role";
+
+fn users(rows: Vec<UserRowDto>) -> Result<Vec<User>, ErrService> {
+    rows.into_iter()
+        .map(|dto| User::try_from(dto).map_err(|_| ErrService::Repo(ErrRepo::DoesntExist)))
+        .collect()
+}
+
+fn map_delete_err(err: sqlx::Error) -> ErrService {
+    if is_foreign_key_violation(&err) {
+        ErrService::User(ErrUser::HasBookings)
+    } else {
+        ErrService::Repo(ErrRepo::BadRequest)
+    }
+}
+
+async fn insert_user(executor: impl SqliteExecutor<'_>, user: &User) -> Result<User, ErrService> {
+    let row = sqlx::query_as::<_, UserRowDto>(INSERT_USER)
+        .bind(user.user_id.id)
+        .bind(&user.user_name.name)
+        .bind(user.role.as_str())
+        .fetch_one(executor)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+    Ok(User::try_from(row)?)
+}
+
+#[async_trait]
+impl UserRepo for SqliteClient {
+    async fn insert_user(&self, user: &User) -> Result<User, ErrService> {
+        insert_user(&self.pool, user).await
+    }
+
+    async fn insert_users(&self, users: &[User]) -> Result<Vec<User>, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        let mut inserted = Vec::with_capacity(users.len());
+        for user in users {
+            inserted.push(insert_user(&mut *tx, user).await?);
+        }
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(inserted)
+    }
+
+    async fn update_user(&self, id: Uuid, new_name: UserName) -> Result<User, ErrService> {
+        let row = sqlx::query_as::<_, UserRowDto>(
+            "UPDATE users SET user_name = ?1 WHERE user_id = ?2 RETURNING user_id, user_name, role",
+        )
+        .bind(new_name.name)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(User::try_from(row)?)
+    }
+
+    async fn delete_user_by_name(&self, name: UserName) -> Result<bool, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        sqlx::query(
+            "DELETE FROM books WHERE user_id = (SELECT user_id FROM users WHERE user_name = ?1) \
+            AND status NOT IN ('pending', 'confirmed')",
+        )
+        .bind(&name.name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query("DELETE FROM users WHERE user_name = ?1")
+            .bind(name.name)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_delete_err)?;
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(row.rows_affected() > 0)
+    }
+
+    async fn delete_user_by_id(&self, id: Uuid) -> Result<bool, ErrService> {
+        let mut tx = self.pool.begin().await.map_err(|_e| ErrRepo::Unreachable)?;
+
+        sqlx::query(
+            "DELETE FROM books WHERE user_id = ?1 AND status NOT IN ('pending', 'confirmed')",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+        let row = sqlx::query("DELETE FROM users WHERE user_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_delete_err)?;
+
+        tx.commit().await.map_err(|_e| ErrRepo::BadRequest)?;
+        Ok(row.rows_affected() > 0)
+    }
+
+    async fn get_all_users(&self) -> Result<Vec<User>, ErrService> {
+        let rows = sqlx::query_as::<_, UserRowDto>("SELECT user_id, user_name, role FROM users")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_e| ErrRepo::BadRequest)?;
+
+        users(rows)
+    }
+
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        page: &PageRequest<UserName>,
+    ) -> Result<Page<User>, ErrService> {
+        let (cmp, dir) = keyset(page.order);
+        let rows = sqlx::query_as::<_, UserRowDto>(&format!(
+            "SELECT user_id, user_name, role FROM users \
+            WHERE (?1 IS NULL OR role = ?1) \
+            AND (?2 IS NULL OR user_name {cmp} ?2) \
+            ORDER BY user_name {dir} LIMIT ?3"
+        ))
+        .bind(filter.role.map(|r| r.as_str()))
+        .bind(page.after.as_ref().map(|u| &u.name))
+        .bind(i64::from(page.limit) + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(Page::from_overfetched(users(rows)?, page.limit))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, ErrService> {
+        let row = sqlx::query_as::<_, UserRowDto>(
+            "SELECT user_id, user_name, role FROM users WHERE user_id = ?1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        Ok(row.map(User::try_from).transpose()?)
+    }
+
+    async fn get_one_user(&self, user_name: &UserName) -> Result<User, ErrService> {
+        let row = sqlx::query_as::<_, UserRowDto>(
+            "SELECT user_id, user_name, role FROM users WHERE user_name = ?1",
+        )
+        .bind(&user_name.name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        match row {
+            Some(row) => Ok(User::try_from(row)?),
+            None => Err(ErrService::User(ErrUser::UserNotFound)),
+        }
+    }
+
+    async fn update_role(&self, id: Uuid, role: Role) -> Result<User, ErrService> {
+        let row = sqlx::query_as::<_, UserRowDto>(
+            "UPDATE users SET role = ?1 WHERE user_id = ?2 RETURNING user_id, user_name, role",
+        )
+        .bind(role.as_str())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_e| ErrRepo::BadRequest)?;
+
+        match row {
+            Some(row) => Ok(User::try_from(row)?),
+            None => Err(ErrService::User(ErrUser::UserNotFound)),
+        }
+    }
+}
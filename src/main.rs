@@ -1,6 +1,13 @@
 use std::net::SocketAddr;
 
-use room_reservations::{app::build::build_app, config::Config, error::ErrService};
+use room_reservations::{
+    app::build::{build_app, connect, connect_sqlite, issue_token},
+    config::{Config, Storage},
+    error::ErrService,
+    infra::{
+        backend::Backend, db::DBClient, in_memory::in_memo_repo::InMemoryRepo, sqlite::SqliteClient,
+    },
+};
 use tracing_subscriber::FmtSubscriber;
 
 #[tokio::main]
@@ -12,7 +19,34 @@ async fn main() -> Result<(), ErrService> {
     dotenv::dotenv().ok();
 
     let config = Config::init();
-    let app = build_app(&config.database_url).await?;
+
+    match &config.storage {
+        Storage::Postgres { database_url } => {
+            run(&config, DBClient::new(connect(database_url).await?)).await
+        }
+        Storage::Sqlite { database_url } => {
+            run(
+                &config,
+                SqliteClient::new(connect_sqlite(database_url).await?),
+            )
+            .await
+        }
+        Storage::Memory => run(&config, InMemoryRepo::new()).await,
+    }
+}
+
+async fn run<B: Backend>(config: &Config, backend: B) -> Result<(), ErrService> {
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        return backend.run_migrations().await;
+    }
+
+    let mut args = std::env::args().skip_while(|arg| arg != "--issue-token");
+    if let Some(user_name) = args.nth(1) {
+        println!("{}", issue_token(config, &backend, &user_name).await?);
+        return Ok(());
+    }
+
+    let app = build_app(config, backend).await?;
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     println!("Server running on http://{}", addr);